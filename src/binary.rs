@@ -0,0 +1,175 @@
+use crate::report::{DiffDetail, Difference};
+use itertools::Itertools;
+use schemars_derive::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use thiserror::Error;
+use tracing::error;
+use vg_errortools::{fat_io_wrap_std, FatIOError};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+/// Configuration options for the byte-wise comparison of binary files
+pub struct BinaryConfig {
+    /// Number of bytes shown in hex starting at the first difference - optional, default is 16
+    #[serde(default = "BinaryConfig::default_window")]
+    pub window: usize,
+}
+
+impl BinaryConfig {
+    fn default_window() -> usize {
+        16
+    }
+}
+
+impl Default for BinaryConfig {
+    fn default() -> Self {
+        BinaryConfig {
+            window: BinaryConfig::default_window(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+/// Errors during binary comparison
+pub enum Error {
+    #[error("File access failed {0}")]
+    FileAccessProblem(#[from] FatIOError),
+}
+
+/// Fills the buffer as far as the file allows, returns the number of bytes read
+fn read_chunk(file: &mut impl Read, buffer: &mut [u8], path: &Path) -> Result<usize, FatIOError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(FatIOError::from_std_io_err(e, path.to_path_buf())),
+        }
+    }
+    Ok(filled)
+}
+
+/// The offset of the first differing byte, reading both files chunk by chunk instead of loading them at once.
+/// If one file is a prefix of the other, the offset is the length of the shorter one.
+pub(crate) fn first_difference(nominal: &Path, actual: &Path) -> Result<Option<u64>, FatIOError> {
+    let mut nominal_file = fat_io_wrap_std(nominal, &File::open)?;
+    let mut actual_file = fat_io_wrap_std(actual, &File::open)?;
+    let mut nominal_buffer = vec![0u8; CHUNK_SIZE];
+    let mut actual_buffer = vec![0u8; CHUNK_SIZE];
+    let mut offset = 0u64;
+    loop {
+        let nominal_read = read_chunk(&mut nominal_file, &mut nominal_buffer, nominal)?;
+        let actual_read = read_chunk(&mut actual_file, &mut actual_buffer, actual)?;
+        let position = nominal_buffer[..nominal_read]
+            .iter()
+            .zip(&actual_buffer[..actual_read])
+            .position(|(n, a)| n != a);
+        if let Some(position) = position {
+            return Ok(Some(offset + position as u64));
+        }
+        if nominal_read != actual_read {
+            return Ok(Some(offset + nominal_read.min(actual_read) as u64));
+        }
+        if nominal_read == 0 {
+            return Ok(None);
+        }
+        offset += nominal_read as u64;
+    }
+}
+
+/// The bytes of the file starting at `offset` as hex, fewer than `window` at the end of the file
+fn hex_window(path: &Path, offset: u64, window: usize) -> Result<String, FatIOError> {
+    let mut file = fat_io_wrap_std(path, &File::open)?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| FatIOError::from_std_io_err(e, path.to_path_buf()))?;
+    let mut buffer = vec![0u8; window];
+    let read = read_chunk(&mut file, &mut buffer, path)?;
+    Ok(buffer[..read]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .join(" "))
+}
+
+pub fn compare_files<P: AsRef<Path>>(
+    nominal_path: P,
+    actual_path: P,
+    config: &BinaryConfig,
+) -> Result<Difference, Error> {
+    let (nominal, actual) = (nominal_path.as_ref(), actual_path.as_ref());
+    let mut difference = Difference::new_for_file(nominal, actual);
+    if let Some(offset) = first_difference(nominal, actual)? {
+        error!(
+            "Files {} and {} differ at byte {offset}",
+            nominal.to_string_lossy(),
+            actual.to_string_lossy()
+        );
+        difference.push_detail(DiffDetail::Binary {
+            offset,
+            nominal: hex_window(nominal, offset, config.window)?,
+            actual: hex_window(actual, offset, config.window)?,
+        });
+        difference.error();
+    }
+    Ok(difference)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let file = "tests/integ/data/images/diff_100_DPI.png";
+        let result = compare_files(file, file, &BinaryConfig::default()).unwrap();
+        assert!(!result.is_error);
+        assert!(result.detail.is_empty());
+    }
+
+    #[test]
+    fn large_files_report_first_differing_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal.bin");
+        let actual = dir.path().join("actual.bin");
+        let content: Vec<u8> = (0..5_000_000u32).map(|i| (i % 251) as u8).collect();
+        let offset = 3 * CHUNK_SIZE + 1234;
+        let mut changed = content.clone();
+        changed[offset] ^= 0xff;
+        changed[offset + 100_000] ^= 0xff;
+        std::fs::write(&nominal, &content).unwrap();
+        std::fs::write(&actual, &changed).unwrap();
+
+        let config = BinaryConfig { window: 4 };
+        let result = compare_files(&nominal, &actual, &config).unwrap();
+        assert!(result.is_error);
+        let Some(DiffDetail::Binary {
+            offset: found,
+            nominal: nominal_bytes,
+            actual: actual_bytes,
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        assert_eq!(*found, offset as u64);
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{b:02x}")).join(" ");
+        assert_eq!(*nominal_bytes, hex(&content[offset..offset + 4]));
+        assert_eq!(*actual_bytes, hex(&changed[offset..offset + 4]));
+
+        std::fs::write(&actual, &content[..offset]).unwrap();
+        let result = compare_files(&nominal, &actual, &config).unwrap();
+        let Some(DiffDetail::Binary {
+            offset: found,
+            actual: actual_bytes,
+            ..
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        assert_eq!(*found, offset as u64);
+        assert!(actual_bytes.is_empty());
+    }
+}
@@ -1,23 +1,28 @@
+use crate::json::{compare_json_values, JsonConfig};
 use crate::report;
 mod preprocessing;
 mod tokenizer;
+mod validation;
 mod value;
 
-pub use preprocessing::Preprocessor;
+pub use preprocessing::{DuplicateHeaders, Preprocessor};
+pub use validation::Validation;
 use value::Quantity;
 use value::Value;
 
+use itertools::Itertools;
 use rayon::prelude::*;
 use regex::Regex;
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek};
 use std::path::Path;
 use std::slice::{Iter, IterMut};
 use thiserror::Error;
-use tracing::error;
+use tracing::{error, info, warn};
 use vg_errortools::{fat_io_wrap_std, FatIOError};
 
 #[derive(Error, Debug)]
@@ -54,6 +59,14 @@ pub enum Error {
     #[error("The files compared have different row count. Nominal: {0}, and Actual: {1}")]
     /// Files being compared have different row numbers
     UnequalRowCount(usize, usize),
+
+    #[error("Duplicate header '{0}' found")]
+    /// A header exists more than once and duplicates are configured to fail
+    DuplicateHeader(String),
+
+    #[error("Invalid column pattern {0}")]
+    /// A column name glob of the column modes was invalid
+    InvalidColumnPattern(#[from] glob::PatternError),
 }
 
 /// A position inside a table
@@ -65,6 +78,69 @@ pub struct Position {
     pub col: usize,
 }
 
+/// The value type of a column, inferred from its cells ignoring empty and deleted ones
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum ColumnType {
+    /// all cells are quantities
+    Quantity,
+    /// all cells are strings
+    String,
+    /// quantities and strings
+    Mixed,
+    /// no cells to infer the type from
+    Empty,
+}
+
+impl Display for ColumnType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnType::Quantity => write!(f, "quantity"),
+            ColumnType::String => write!(f, "string"),
+            ColumnType::Mixed => write!(f, "mixed"),
+            ColumnType::Empty => write!(f, "empty"),
+        }
+    }
+}
+
+/// How far an out of tolerance actual value deviates from the nominal one
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+pub struct Deviation {
+    /// `|actual - nominal|`
+    pub absolute: f64,
+    /// `|actual - nominal| / |nominal|`
+    pub relative: f64,
+    /// The amount by which the deviation exceeds the tolerance, measured like the mode does
+    pub excess: f64,
+}
+
+impl Display for Deviation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "deviation: {} (relative: {}), exceeds tolerance by: {}",
+            self.absolute, self.relative, self.excess
+        )
+    }
+}
+
+/// A side of the comparison
+#[derive(JsonSchema, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// the nominal table
+    Nominal,
+    /// the actual table
+    Actual,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Nominal => write!(f, "nominal"),
+            Side::Actual => write!(f, "actual"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 /// Difference of a table entry
 pub enum DiffType {
@@ -85,6 +161,8 @@ pub enum DiffType {
         actual: Quantity,
         /// compare mode that was exceeded
         mode: Mode,
+        /// how far actual deviates from nominal
+        deviation: Deviation,
         /// position in table
         position: Position,
     },
@@ -104,6 +182,94 @@ pub enum DiffType {
         /// actual
         actual: String,
     },
+    /// The actual table is not sorted by the column, position is the first out-of-order row
+    UnsortedColumn {
+        /// name of the column
+        column: String,
+        /// whether ascending order was expected
+        ascending: bool,
+        /// the value of the previous row
+        previous: f64,
+        /// the out-of-order value
+        found: f64,
+        /// position
+        position: Position,
+    },
+    /// Both entries were [`Quantity`]s with units not convertible into each other, only with `unit_conversion`
+    IncompatibleUnits {
+        /// nominal
+        nominal: Quantity,
+        /// actual
+        actual: Quantity,
+        /// position
+        position: Position,
+    },
+    /// One table has more rows than the other, only with `row_count_mismatch` other than `Error`
+    SurplusRows {
+        /// the side having more rows
+        side: Side,
+        /// number of rows without counterpart
+        count: usize,
+    },
+    /// A row without counterpart, only with `row_count_mismatch: ReportExtraRows`
+    ExtraRow {
+        /// the side having the row
+        side: Side,
+        /// row number, starting with zero
+        row: usize,
+        /// the fields of the row
+        content: String,
+    },
+    /// The tables have a different number of columns, only with `schema_only`
+    UnequalColumnCount {
+        /// nominal
+        nominal: usize,
+        /// actual
+        actual: usize,
+    },
+    /// The inferred value types of a column differ, only with `schema_only`
+    UnequalColumnType {
+        /// column number, starting with zero
+        column: usize,
+        /// nominal
+        nominal: ColumnType,
+        /// actual
+        actual: ColumnType,
+    },
+    /// A row of one table has no row with the same key in the other table, only with `align_by_column`
+    MissingRow {
+        /// the value of the key column
+        key: String,
+        /// the side the row is missing on
+        side: Side,
+        /// position of the key cell in the aligned tables
+        position: Position,
+    },
+    /// A column of one table has no column with the same header in the other table, only with `match_columns_by_header`
+    MissingColumn {
+        /// the header of the column
+        header: String,
+        /// the side the column is missing on
+        side: Side,
+    },
+    /// A cell of the actual table does not match the regex of an `AssertColumnMatchesRegex` validation
+    RegexMismatch {
+        /// name of the column
+        column: String,
+        /// the regex the cell had to match
+        pattern: String,
+        /// the value of the cell
+        value: String,
+        /// position
+        position: Position,
+    },
+    /// Both cells of a JSON column held JSON documents differing structurally, only with `json_columns`
+    UnequalJson {
+        /// the differences found by the JSON comparison
+        differences: String,
+        /// position
+        position: Position,
+    },
 }
 
 impl Display for DiffType {
@@ -125,12 +291,25 @@ impl Display for DiffType {
                 actual,
                 nominal,
                 mode,
+                deviation,
                 position,
             } => {
                 write!(
                     f,
-                    "Line: {}, Col: {} -- Out of tolerance -- Expected {}, Found {}, Mode {}",
-                    position.row, position.col, nominal, actual, mode
+                    "Line: {}, Col: {} -- Out of tolerance -- Expected {}, Found {}, Mode {}, {}",
+                    position.row, position.col, nominal, actual, mode, deviation
+                )
+                .unwrap_or_default();
+            }
+            DiffType::IncompatibleUnits {
+                nominal,
+                actual,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Line: {}, Col: {} -- Incompatible units -- Expected {}, Found {}",
+                    position.row, position.col, nominal, actual
                 )
                 .unwrap_or_default();
             }
@@ -154,6 +333,91 @@ impl Display for DiffType {
                 )
                 .unwrap_or_default();
             }
+            DiffType::UnsortedColumn {
+                column,
+                ascending,
+                previous,
+                found,
+                position,
+            } => {
+                let order = if *ascending {
+                    "ascending"
+                } else {
+                    "descending"
+                };
+                write!(
+                    f,
+                    "Line: {}, Col: {} -- Column '{}' not sorted {} -- Found {} after {}",
+                    position.row, position.col, column, order, found, previous
+                )
+                .unwrap_or_default();
+            }
+            DiffType::SurplusRows { side, count } => {
+                write!(f, "{} surplus rows in {}", count, side).unwrap_or_default();
+            }
+            DiffType::ExtraRow { side, row, content } => {
+                write!(f, "Line: {} -- Extra row in {} -- {}", row, side, content)
+                    .unwrap_or_default();
+            }
+            DiffType::UnequalColumnCount { nominal, actual } => {
+                write!(
+                    f,
+                    "Different column count -- Expected {}, Found {}",
+                    nominal, actual
+                )
+                .unwrap_or_default();
+            }
+            DiffType::UnequalColumnType {
+                column,
+                nominal,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Col: {} -- Different column types -- Expected {}, Found {}",
+                    column, nominal, actual
+                )
+                .unwrap_or_default();
+            }
+            DiffType::MissingRow {
+                key,
+                side,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Line: {} -- Row with key '{}' missing in {}",
+                    position.row, key, side
+                )
+                .unwrap_or_default();
+            }
+            DiffType::MissingColumn { header, side } => {
+                write!(f, "Column '{}' missing in {}", header, side).unwrap_or_default();
+            }
+            DiffType::RegexMismatch {
+                column,
+                pattern,
+                value,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Line: {}, Col: {} -- '{}' of column '{}' does not match '{}'",
+                    position.row, position.col, value, column, pattern
+                )
+                .unwrap_or_default();
+            }
+            DiffType::UnequalJson {
+                differences,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Line: {}, Col: {} -- Different JSON -- {}",
+                    position.row, position.col, differences
+                )
+                .unwrap_or_default();
+            }
         };
         Ok(())
     }
@@ -166,6 +430,12 @@ pub enum Mode {
     Absolute(f64),
     /// `((a-b)/a).abs() < threshold`
     Relative(f64),
+    /// `round(a, places) == round(b, places)`
+    DecimalPlaces(u32),
+    /// `(|a|-|b|).abs() < threshold` - ignores the sign
+    AbsoluteMagnitude(f64),
+    /// `(a-b).abs() / max(|column of a|) < threshold` - relative to the largest absolute value of the nominal column
+    RelativeToColumnMax(f64),
     /// always matches
     Ignore,
 }
@@ -179,6 +449,15 @@ impl Display for Mode {
             Mode::Relative(tolerance) => {
                 write!(f, "Relative (tol: {tolerance})").unwrap_or_default();
             }
+            Mode::DecimalPlaces(places) => {
+                write!(f, "Decimal places ({places})").unwrap_or_default();
+            }
+            Mode::AbsoluteMagnitude(tolerance) => {
+                write!(f, "Absolute magnitude (tol: {tolerance})").unwrap_or_default();
+            }
+            Mode::RelativeToColumnMax(tolerance) => {
+                write!(f, "Relative to column max (tol: {tolerance})").unwrap_or_default();
+            }
             Mode::Ignore => {
                 write!(f, "Ignored").unwrap_or_default();
             }
@@ -188,6 +467,70 @@ impl Display for Mode {
 }
 
 impl Mode {
+    pub(crate) fn needs_column_max(&self) -> bool {
+        matches!(self, Mode::RelativeToColumnMax(_))
+    }
+
+    /// Like [`Mode::in_tolerance`] but with the largest absolute value of the nominal column as context
+    pub(crate) fn in_tolerance_in_column(
+        &self,
+        nominal: &Quantity,
+        actual: &Quantity,
+        column_max: f64,
+    ) -> bool {
+        match self {
+            Mode::RelativeToColumnMax(tolerance) => {
+                Mode::Absolute(tolerance * column_max).in_tolerance(nominal, actual)
+            }
+            _ => self.in_tolerance(nominal, actual),
+        }
+    }
+
+    /// The deviation in multiples of the allowed deviation, values above 1.0 are out of tolerance
+    pub(crate) fn tolerance_ratio(
+        &self,
+        nominal: &Quantity,
+        actual: &Quantity,
+        column_max: f64,
+    ) -> f64 {
+        let deviation = (nominal.value - actual.value).abs();
+        match self {
+            Mode::Absolute(tolerance) => deviation / tolerance,
+            Mode::Relative(tolerance) => deviation / nominal.value.abs() / tolerance,
+            Mode::DecimalPlaces(places) => deviation * 10.0f64.powi(*places as i32) * 2.0,
+            Mode::AbsoluteMagnitude(tolerance) => {
+                (nominal.value.abs() - actual.value.abs()).abs() / tolerance
+            }
+            Mode::RelativeToColumnMax(tolerance) => deviation / (tolerance * column_max),
+            Mode::Ignore => 0.0,
+        }
+    }
+
+    /// The deviation of actual from nominal and by how much it exceeds the tolerance of this mode
+    pub(crate) fn deviation(
+        &self,
+        nominal: &Quantity,
+        actual: &Quantity,
+        column_max: f64,
+    ) -> Deviation {
+        let absolute = (nominal.value - actual.value).abs();
+        let excess = match self {
+            Mode::Absolute(tolerance) => absolute - tolerance,
+            Mode::Relative(tolerance) => (absolute / nominal.value).abs() - tolerance,
+            Mode::DecimalPlaces(places) => absolute - 0.5 * 10.0f64.powi(-(*places as i32)),
+            Mode::AbsoluteMagnitude(tolerance) => {
+                (nominal.value.abs() - actual.value.abs()).abs() - tolerance
+            }
+            Mode::RelativeToColumnMax(tolerance) => absolute - tolerance * column_max,
+            Mode::Ignore => 0.0,
+        };
+        Deviation {
+            absolute,
+            relative: absolute / nominal.value.abs(),
+            excess,
+        }
+    }
+
     pub(crate) fn in_tolerance(&self, nominal: &Quantity, actual: &Quantity) -> bool {
         if nominal.value.is_nan() && actual.value.is_nan() {
             return true;
@@ -222,10 +565,90 @@ impl Mode {
                 let identical_units = nominal.unit == actual.unit;
                 numerically && identical_units
             }
+            Mode::DecimalPlaces(places) => {
+                let factor = 10.0f64.powi(*places as i32);
+                let numerically =
+                    (nominal.value * factor).round() == (actual.value * factor).round();
+                let identical_units = nominal.unit == actual.unit;
+                numerically && identical_units
+            }
+            Mode::AbsoluteMagnitude(tolerance) => {
+                let magnitude = |q: &Quantity| Quantity {
+                    value: q.value.abs(),
+                    unit: q.unit.clone(),
+                };
+                Mode::Absolute(*tolerance).in_tolerance(&magnitude(nominal), &magnitude(actual))
+            }
+            Mode::RelativeToColumnMax(_) => {
+                let column_max = nominal.value.abs().max(actual.value.abs());
+                self.in_tolerance_in_column(nominal, actual, column_max)
+            }
         }
     }
 }
 
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How multiple comparison modes are combined for a single cell
+pub enum ModeCombination {
+    /// A cell must be within tolerance of all modes, every failed mode is reported
+    #[default]
+    All,
+    /// A cell within tolerance of any mode passes, otherwise the mode closest to passing is reported
+    Any,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How tables with different row counts are handled
+pub enum RowCountMismatch {
+    /// Abort the comparison of the file with an error
+    #[default]
+    Error,
+    /// Compare the rows present in both tables and report the number of surplus rows
+    CompareCommonPrefix,
+    /// Like `CompareCommonPrefix`, but additionally report every surplus row
+    ReportExtraRows,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How the differences are shown in the detail page of the report
+pub enum DetailView {
+    /// The whole table with the differing cells highlighted
+    #[default]
+    Rows,
+    /// One line per column with the number of differences and the worst deviation
+    Columns,
+    /// Like `Rows`, but only the rows with differences keeping their row numbers - handy for huge tables
+    DiffRows,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone)]
+/// Comparison modes replacing the global ones for all columns whose header matches
+pub struct ColumnModeOverride {
+    /// Glob matched against the column header, e.g. `Pos*`
+    pub column: String,
+    /// The modes used for the matching columns instead of `comparison_modes`
+    pub modes: Vec<Mode>,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone)]
+/// An absolute tolerance in a unit, applied to all columns whose header declares a convertible unit like `Position x [cm]`
+pub struct UnitTolerance {
+    /// The maximum absolute deviation in `unit`
+    pub absolute: f64,
+    /// The unit of the tolerance, e.g. `mm`
+    pub unit: String,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone)]
+/// Columns whose cells hold JSON documents, compared structurally instead of as strings
+pub struct JsonColumns {
+    /// Globs matched against the column headers, e.g. `payload*`
+    pub columns: Vec<String>,
+    /// The options of the JSON comparison applied to every cell, e.g. `ignore_keys` and `sort_arrays`
+    #[serde(flatten)]
+    pub options: JsonConfig,
+}
+
 #[derive(JsonSchema, Deserialize, Serialize, Debug, Default, Clone)]
 /// Settings for the CSV comparison module
 pub struct CSVCompareConfig {
@@ -234,16 +657,252 @@ pub struct CSVCompareConfig {
     pub delimiters: Delimiters,
     /// How numerical values shall be compared, strings are always checked for identity
     pub comparison_modes: Vec<Mode>,
-    /// Any field matching the given regex is excluded from comparison
+    /// Whether a cell must be within tolerance of all `comparison_modes` or any of them - optional, default is all
+    #[serde(default)]
+    pub mode_combination: ModeCombination,
+    /// Any string field matching the given regex is excluded from comparison, in either nominal or actual
     pub exclude_field_regex: Option<String>,
     /// Preprocessing done to the csv files before beginning the comparison
     pub preprocessing: Option<Vec<Preprocessor>>,
+    /// Only compare the columns whose headers exist in both files, extra columns on either side are ignored.
+    /// Needs the `ExtractHeaders` preprocessor.
+    #[serde(default)]
+    pub compare_common_columns_only: bool,
+    /// Only compare this many rows, randomly sampled from all rows - for quick checks of very large files
+    pub sample_rows: Option<usize>,
+    /// The seed for sampling rows, the same seed always yields the same rows - optional, default is 0
+    #[serde(default)]
+    pub sample_seed: u64,
+    /// Groups of header names that are treated as equal, e.g. `[["Vol", "Volume"]]`
+    #[serde(default)]
+    pub header_synonyms: Vec<Vec<String>>,
+    /// The decimal separator of the actual file if it differs from the nominal one, defaults to `decimal_separator`
+    pub actual_decimal_separator: Option<char>,
+    /// Treat cells with only whitespace, including quoted literals like `" "`, as empty - optional, default is false
+    #[serde(default)]
+    pub whitespace_cells_as_empty: bool,
+    /// Validations of the actual table after preprocessing, independent of the nominal
+    #[serde(default)]
+    pub validations: Vec<Validation>,
+    /// How duplicate headers found by `ExtractHeaders` are handled - optional, default is to warn
+    #[serde(default)]
+    pub duplicate_headers: DuplicateHeaders,
+    /// Comparison modes for columns by header, the first matching override replaces `comparison_modes`.
+    /// Columns without header always use `comparison_modes` - optional
+    pub column_modes: Option<Vec<ColumnModeOverride>>,
+    /// Convert the actual quantity into the unit of the nominal one before comparing, e.g. `10 mm` equals `1 cm`.
+    /// Supports SI length, area, volume, mass and time units, not convertible units are reported - optional, default is false
+    #[serde(default)]
+    pub unit_conversion: bool,
+    /// Absolute tolerances for columns whose header ends with a unit in brackets, e.g. `Position x [mm]`.
+    /// The first tolerance convertible into the header unit replaces `comparison_modes` for that column,
+    /// `column_modes` take precedence. Needs the `ExtractHeaders` preprocessor - optional
+    #[serde(default)]
+    pub header_unit_tolerances: Vec<UnitTolerance>,
+    /// Treat cells with recognized boolean spellings as equal if they mean the same, e.g. `True` and `yes` - optional
+    pub boolean_normalization: Option<BooleanVocabulary>,
+    /// Match the rows of nominal and actual by the value of the column with this header instead of by their position.
+    /// Rows without counterpart are reported as missing. Needs the `ExtractHeaders` preprocessor - optional
+    pub align_by_column: Option<String>,
+    /// How strings are compared - optional, default is an exact match
+    #[serde(default)]
+    pub string_comparison: StringCompareOptions,
+    /// Only compare the structure: column count, headers and the inferred value type of each column.
+    /// Values and row counts are ignored - optional, default is false
+    #[serde(default)]
+    pub schema_only: bool,
+    /// How the differences are shown in the detail page of the report - optional, default is `Rows`
+    #[serde(default)]
+    pub detail_view: DetailView,
+    /// How tables with different row counts are handled - optional, default is `Error`
+    #[serde(default)]
+    pub row_count_mismatch: RowCountMismatch,
+    /// Match the columns of actual to the nominal ones by header instead of by their position.
+    /// Columns only present on one side are reported as missing. Duplicate headers are ambiguous and fail the comparison,
+    /// unless renamed by `duplicate_headers: Suffix`. Needs the `ExtractHeaders` preprocessor - optional
+    #[serde(default)]
+    pub match_columns_by_header: bool,
+    /// Treat the deleted markers of the preprocessors as equal to any value of the other side,
+    /// e.g. if a row was only deleted in one of the files - optional, default is false
+    #[serde(default)]
+    pub deleted_matches_anything: bool,
+    /// Only compare the names and order of the headers, ignoring all data.
+    /// Needs the `ExtractHeaders` preprocessor - optional, default is false
+    #[serde(default)]
+    pub headers_only: bool,
+    /// Compare the cells of these columns as JSON documents, e.g. ignoring the order of keys.
+    /// Cells not parsing as JSON on either side are compared as strings. Needs the `ExtractHeaders` preprocessor - optional
+    pub json_columns: Option<Box<JsonColumns>>,
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How strings are compared, by default they must be identical
+pub struct StringCompareOptions {
+    /// Ignore the casing, e.g. `Hello` equals `hello` - optional, default is false
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Ignore leading and trailing whitespace, e.g. `Hello` equals `Hello ` - optional, default is false
+    #[serde(default)]
+    pub trim_whitespace: bool,
+}
+
+impl StringCompareOptions {
+    pub(crate) fn equal(&self, nominal: &str, actual: &str) -> bool {
+        let (nominal, actual) = if self.trim_whitespace {
+            (nominal.trim(), actual.trim())
+        } else {
+            (nominal, actual)
+        };
+        if self.case_insensitive {
+            nominal.to_lowercase() == actual.to_lowercase()
+        } else {
+            nominal == actual
+        }
+    }
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, PartialEq)]
+/// The spellings of boolean values, matched case-insensitively
+pub struct BooleanVocabulary {
+    /// Spellings of true - optional, defaults to `true`, `yes`, `on` and `1`
+    #[serde(default = "BooleanVocabulary::default_truthy")]
+    pub truthy: Vec<String>,
+    /// Spellings of false - optional, defaults to `false`, `no`, `off` and `0`
+    #[serde(default = "BooleanVocabulary::default_falsy")]
+    pub falsy: Vec<String>,
+}
+
+impl BooleanVocabulary {
+    fn default_truthy() -> Vec<String> {
+        ["true", "yes", "on", "1"].map(str::to_owned).to_vec()
+    }
+
+    fn default_falsy() -> Vec<String> {
+        ["false", "no", "off", "0"].map(str::to_owned).to_vec()
+    }
+
+    /// The boolean meaning of the cell, `None` if it is no recognized spelling
+    pub(crate) fn normalize(&self, value: &Value) -> Option<bool> {
+        let text = value.as_str();
+        let text = text.trim();
+        let matches =
+            |vocabulary: &[String]| vocabulary.iter().any(|v| v.eq_ignore_ascii_case(text));
+        if matches(&self.truthy) {
+            Some(true)
+        } else if matches(&self.falsy) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for BooleanVocabulary {
+    fn default() -> Self {
+        BooleanVocabulary {
+            truthy: Self::default_truthy(),
+            falsy: Self::default_falsy(),
+        }
+    }
+}
+
+impl CSVCompareConfig {
+    /// Compiles the `exclude_field_regex`, done once per table comparison
+    pub(crate) fn exclude_regex(&self) -> Result<Option<Regex>, regex::Error> {
+        self.exclude_field_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+    }
+
+    /// Compiles the column globs of `column_modes`, done once per table comparison
+    pub(crate) fn column_mode_patterns(
+        &self,
+    ) -> Result<Vec<(glob::Pattern, &[Mode])>, glob::PatternError> {
+        self.column_modes
+            .iter()
+            .flatten()
+            .map(|o| Ok((glob::Pattern::new(&o.column)?, o.modes.as_slice())))
+            .collect()
+    }
+
+    /// The absolute tolerance mode for a column whose header declares a unit, converted into that unit
+    pub(crate) fn header_unit_mode(&self, header: &str) -> Option<Mode> {
+        let unit = header_unit(header)?;
+        self.header_unit_tolerances.iter().find_map(|tolerance| {
+            let quantity = Quantity {
+                value: tolerance.absolute,
+                unit: Some(tolerance.unit.clone()),
+            };
+            quantity
+                .converted_to(Some(unit))
+                .map(|converted| Mode::Absolute(converted.value))
+        })
+    }
+
+    /// Compiles the column globs of `json_columns`, done once per table comparison
+    pub(crate) fn json_column_patterns(&self) -> Result<Vec<glob::Pattern>, glob::PatternError> {
+        self.json_columns
+            .iter()
+            .flat_map(|json_columns| json_columns.columns.iter())
+            .map(|column| glob::Pattern::new(column))
+            .collect()
+    }
+
+    /// Maps a header to the first entry of its synonym group, or itself if it has no synonyms
+    pub(crate) fn canonical_header<'a>(&'a self, header: &'a str) -> &'a str {
+        self.header_synonyms
+            .iter()
+            .find(|group| group.iter().any(|h| h == header))
+            .and_then(|group| group.first())
+            .map(String::as_str)
+            .unwrap_or(header)
+    }
+
+    /// The delimiters for parsing the actual file, only the decimal separator may differ from the nominal ones
+    pub(crate) fn actual_delimiters(&self) -> Delimiters {
+        Delimiters {
+            decimal_separator: self
+                .actual_decimal_separator
+                .or(self.delimiters.decimal_separator),
+            ..self.delimiters.clone()
+        }
+    }
+
+    pub(crate) fn headers_equal(&self, nominal: &str, actual: &str) -> bool {
+        self.canonical_header(nominal) == self.canonical_header(actual)
+    }
+
+    /// Returns the sorted indices of the rows to compare if sampling is enabled and there are more rows than samples
+    pub(crate) fn sampled_rows(&self, row_count: usize) -> Option<Vec<usize>> {
+        let sample_count = self.sample_rows?;
+        if sample_count >= row_count {
+            return None;
+        }
+        // partial Fisher-Yates shuffle driven by splitmix64
+        let mut state = self.sample_seed;
+        let mut next_random = move || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        let mut indices: Vec<usize> = (0..row_count).collect();
+        for i in 0..sample_count {
+            let j = i + (next_random() % (row_count - i) as u64) as usize;
+            indices.swap(i, j);
+        }
+        indices.truncate(sample_count);
+        indices.sort_unstable();
+        Some(indices)
+    }
 }
 
 #[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
 /// Delimiter configuration for file parsing
 pub struct Delimiters {
-    /// The delimiters of the csv fields (typically comma, semicolon or pipe)
+    /// The delimiters of the csv fields (typically comma, semicolon, tab or pipe), in yaml a tab is written as `"\t"`
     pub field_delimiter: Option<char>,
     /// The decimal separator for floating point numbers (typically dot or comma)
     pub decimal_separator: Option<char>,
@@ -270,6 +929,31 @@ pub(crate) struct Column {
 }
 
 impl Column {
+    pub(crate) fn inferred_type(&self) -> ColumnType {
+        let deleted = Value::deleted();
+        self.rows
+            .iter()
+            .filter(|v| **v != deleted)
+            .filter(|v| !v.get_string().is_some_and(|s| is_blank(&s)))
+            .fold(ColumnType::Empty, |column_type, v| {
+                let cell_type = if v.get_quantity().is_some() {
+                    ColumnType::Quantity
+                } else {
+                    ColumnType::String
+                };
+                match column_type {
+                    ColumnType::Empty => cell_type,
+                    t if t == cell_type => t,
+                    _ => ColumnType::Mixed,
+                }
+            })
+    }
+
+    /// Whether all cells are deleted markers, e.g. after `DeleteColumnByName`
+    pub(crate) fn is_deleted(&self) -> bool {
+        !self.rows.is_empty() && self.rows.iter().all(Value::is_deleted)
+    }
+
     pub fn delete_contents(&mut self) {
         self.header = Some("DELETED".to_string());
         let row_count = self.rows.len();
@@ -279,9 +963,15 @@ impl Column {
 
 pub(crate) struct Table {
     pub columns: Vec<Column>,
+    /// The decimal separator the cells were parsed with, used when a preprocessor parses a cell again
+    pub decimal_separator: Option<char>,
 }
 
 impl Table {
+    fn headers(&self) -> Vec<Option<&str>> {
+        self.columns.iter().map(|c| c.header.as_deref()).collect()
+    }
+
     pub(crate) fn from_reader<R: Read + Seek>(
         input: R,
         config: &Delimiters,
@@ -310,7 +1000,10 @@ impl Table {
             }
         }
 
-        Ok(Table { columns: cols })
+        Ok(Table {
+            columns: cols,
+            decimal_separator: parser.delimiters().decimal_separator,
+        })
     }
 
     pub(crate) fn rows(&self) -> RowIterator {
@@ -371,43 +1064,475 @@ impl<'a> Iterator for RowIterator<'a> {
     }
 }
 
-pub(crate) fn compare_tables(
+fn key_column_index(table: &Table, key: &str, config: &CSVCompareConfig) -> Result<usize, Error> {
+    table
+        .columns
+        .iter()
+        .position(|c| {
+            c.header
+                .as_deref()
+                .is_some_and(|header| config.headers_equal(header, key))
+        })
+        .ok_or_else(|| {
+            Error::InvalidAccess(format!(
+                "Aligning rows by column '{key}' requested but column not found."
+            ))
+        })
+}
+
+/// The row indices of a nominal row and its actual counterpart, `None` if there is none
+type RowPair = (Option<usize>, Option<usize>);
+
+/// Rearranges the rows of both tables like an outer join on the key column:
+/// matched rows in nominal order, then the rows only present in actual.
+/// A row without counterpart gets a row of deleted markers on the other side.
+fn align_rows(
+    nominal: &mut Table,
+    actual: &mut Table,
+    key: &str,
+    config: &CSVCompareConfig,
+) -> Result<(), Error> {
+    let nominal_keys: Vec<String> = nominal.columns[key_column_index(nominal, key, config)?]
+        .rows
+        .iter()
+        .map(|v| v.as_str().into_owned())
+        .collect();
+    let actual_keys: Vec<String> = actual.columns[key_column_index(actual, key, config)?]
+        .rows
+        .iter()
+        .map(|v| v.as_str().into_owned())
+        .collect();
+
+    let mut unmatched_actual: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (row, key) in actual_keys.iter().enumerate() {
+        unmatched_actual.entry(key).or_default().push_back(row);
+    }
+    let mut pairs: Vec<RowPair> = nominal_keys
+        .iter()
+        .enumerate()
+        .map(|(row, key)| {
+            let actual_row = unmatched_actual
+                .get_mut(key.as_str())
+                .and_then(VecDeque::pop_front);
+            (Some(row), actual_row)
+        })
+        .collect();
+    let mut actual_only: Vec<usize> = unmatched_actual.into_values().flatten().collect();
+    actual_only.sort_unstable();
+    pairs.extend(actual_only.into_iter().map(|row| (None, Some(row))));
+
+    let rearrange = |table: &mut Table, side: fn(&RowPair) -> Option<usize>| {
+        for column in table.columns.iter_mut() {
+            column.rows = pairs
+                .iter()
+                .map(|pair| {
+                    side(pair)
+                        .and_then(|row| column.rows.get(row).cloned())
+                        .unwrap_or_else(Value::deleted)
+                })
+                .collect();
+        }
+    };
+    rearrange(nominal, |pair| pair.0);
+    rearrange(actual, |pair| pair.1);
+    Ok(())
+}
+
+/// The rows of the aligned tables whose key is a deleted marker on exactly one side
+fn find_missing_rows(
     nominal: &Table,
     actual: &Table,
+    key: &str,
     config: &CSVCompareConfig,
 ) -> Result<Vec<DiffType>, Error> {
-    if nominal.rows().len() != actual.rows().len() {
-        return Err(Error::UnequalRowCount(
-            nominal.rows().len(),
-            actual.rows().len(),
-        ));
+    let nominal_col = key_column_index(nominal, key, config)?;
+    let nominal_keys = &nominal.columns[nominal_col].rows;
+    let actual_keys = &actual.columns[key_column_index(actual, key, config)?].rows;
+    let deleted = Value::deleted();
+    Ok(nominal_keys
+        .iter()
+        .zip(actual_keys.iter())
+        .enumerate()
+        .filter_map(|(row, (nominal_key, actual_key))| {
+            let (key, side) = match (*nominal_key == deleted, *actual_key == deleted) {
+                (true, false) => (actual_key, Side::Nominal),
+                (false, true) => (nominal_key, Side::Actual),
+                _ => return None,
+            };
+            Some(DiffType::MissingRow {
+                key: key.as_str().into_owned(),
+                side,
+                position: Position {
+                    row,
+                    col: nominal_col,
+                },
+            })
+        })
+        .collect())
+}
+
+/// The unit in brackets at the end of a header, e.g. `mm` for `Position x [mm]`
+fn header_unit(header: &str) -> Option<&str> {
+    let unit = header.trim_end().strip_suffix(']')?;
+    let (_, unit) = unit.rsplit_once('[')?;
+    let unit = unit.trim();
+    (!unit.is_empty()).then_some(unit)
+}
+
+/// Warns about columns and rows only deleted on one side, these usually point to a preprocessor
+/// not matching both files, e.g. `DeleteColumnByName` with a header present in one file only
+fn warn_asymmetric_deletions(nominal: &Table, actual: &Table, config: &CSVCompareConfig) {
+    let consequence = if config.deleted_matches_anything {
+        "it is ignored"
+    } else {
+        "all its cells differ"
+    };
+    let deleted_side =
+        |nominal_deleted: bool, actual_deleted: bool| match (nominal_deleted, actual_deleted) {
+            (true, false) => Some(Side::Nominal),
+            (false, true) => Some(Side::Actual),
+            _ => None,
+        };
+    for (col, (col_nom, col_act)) in nominal
+        .columns
+        .iter()
+        .zip(actual.columns.iter())
+        .enumerate()
+    {
+        if let Some(side) = deleted_side(col_nom.is_deleted(), col_act.is_deleted()) {
+            warn!(
+                "Column {col} is only deleted in {side}, {consequence} - check the preprocessors"
+            );
+        }
+    }
+    let row_deleted = |row: &[&Value]| !row.is_empty() && row.iter().all(|v| v.is_deleted());
+    for (row, (row_nom, row_act)) in nominal.rows().zip(actual.rows()).enumerate() {
+        if let Some(side) = deleted_side(row_deleted(&row_nom), row_deleted(&row_act)) {
+            warn!("Row {row} is only deleted in {side}, {consequence} - check the preprocessors");
+        }
+    }
+}
+
+/// Compares the headers column by column, a column missing on one side has an empty header there
+fn compare_headers(nominal: &Table, actual: &Table, config: &CSVCompareConfig) -> Vec<DiffType> {
+    let headers = |table: &Table| -> Vec<String> {
+        table
+            .columns
+            .iter()
+            .map(|column| column.header.clone().unwrap_or_default())
+            .collect()
+    };
+    let (nominal_headers, actual_headers) = (headers(nominal), headers(actual));
+    if [nominal, actual]
+        .iter()
+        .any(|table| table.columns.iter().any(|column| column.header.is_none()))
+    {
+        warn!("Comparing headers only, but not all columns have headers - is `ExtractHeaders` missing?");
     }
+    nominal_headers
+        .into_iter()
+        .zip_longest(actual_headers)
+        .filter_map(|headers| {
+            let (nominal, actual) = headers.or_default();
+            (!config.headers_equal(&nominal, &actual))
+                .then_some(DiffType::UnequalHeader { nominal, actual })
+        })
+        .collect()
+}
 
+/// Compares column count, headers and inferred column types, ignoring all values
+fn compare_schema(nominal: &Table, actual: &Table, config: &CSVCompareConfig) -> Vec<DiffType> {
     let mut diffs = Vec::new();
-    for (col, (col_nom, col_act)) in nominal
+    if nominal.columns.len() != actual.columns.len() {
+        diffs.push(DiffType::UnequalColumnCount {
+            nominal: nominal.columns.len(),
+            actual: actual.columns.len(),
+        });
+    }
+    for (column, (col_nom, col_act)) in nominal
         .columns
         .iter()
         .zip(actual.columns.iter())
         .enumerate()
     {
         if let (Some(nom_header), Some(act_header)) = (&col_nom.header, &col_act.header) {
-            if nom_header != act_header {
-                diffs.extend(vec![DiffType::UnequalHeader {
+            if !config.headers_equal(nom_header, act_header) {
+                diffs.push(DiffType::UnequalHeader {
                     nominal: nom_header.to_owned(),
                     actual: act_header.to_owned(),
-                }]);
+                });
             }
         }
+        let (nominal_type, actual_type) = (col_nom.inferred_type(), col_act.inferred_type());
+        if nominal_type != actual_type {
+            diffs.push(DiffType::UnequalColumnType {
+                column,
+                nominal: nominal_type,
+                actual: actual_type,
+            });
+        }
+    }
+    diffs
+}
 
-        for (row, (val_nom, val_act)) in col_nom.rows.iter().zip(col_act.rows.iter()).enumerate() {
-            let position = Position { row, col };
-            let diffs_field = compare_values(val_nom, val_act, config, position)?;
-            diffs.extend(diffs_field);
+pub(crate) fn compare_tables(
+    nominal: &Table,
+    actual: &Table,
+    config: &CSVCompareConfig,
+) -> Result<Vec<DiffType>, Error> {
+    if config.headers_only {
+        return Ok(compare_headers(nominal, actual, config));
+    }
+    if config.schema_only {
+        return Ok(compare_schema(nominal, actual, config));
+    }
+    let (nominal_rows, actual_rows) = (nominal.rows().len(), actual.rows().len());
+    let mut surplus_rows = Vec::new();
+    if nominal_rows != actual_rows {
+        if config.row_count_mismatch == RowCountMismatch::Error {
+            return Err(Error::UnequalRowCount(nominal_rows, actual_rows));
         }
+        let (side, longer) = if nominal_rows > actual_rows {
+            (Side::Nominal, nominal)
+        } else {
+            (Side::Actual, actual)
+        };
+        let common_rows = nominal_rows.min(actual_rows);
+        warn!(
+            "Comparing the first {common_rows} rows only, {side} has {} surplus rows",
+            longer.rows().len() - common_rows
+        );
+        surplus_rows.push(DiffType::SurplusRows {
+            side,
+            count: longer.rows().len() - common_rows,
+        });
+        if config.row_count_mismatch == RowCountMismatch::ReportExtraRows {
+            surplus_rows.extend(longer.rows().enumerate().skip(common_rows).map(
+                |(row, fields)| {
+                    DiffType::ExtraRow {
+                        side,
+                        row,
+                        content: fields
+                            .iter()
+                            .map(|v| v.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    }
+                },
+            ));
+        }
+    }
+
+    let sampled_rows = config.sampled_rows(nominal_rows.min(actual_rows));
+    if let Some(sampled_rows) = sampled_rows.as_ref() {
+        info!(
+            "Comparing {} sampled rows of {}",
+            sampled_rows.len(),
+            nominal.rows().len()
+        );
+    }
+    warn_asymmetric_deletions(nominal, actual, config);
+    let exclude_regex = config.exclude_regex()?;
+    let column_mode_patterns = config.column_mode_patterns()?;
+    let json_column_patterns = config.json_column_patterns()?;
+    let mut missing_rows = Vec::new();
+    let compared_rows = match config.align_by_column.as_deref() {
+        Some(key) => {
+            missing_rows = find_missing_rows(nominal, actual, key, config)?;
+            let is_missing = |row: &usize| {
+                missing_rows.iter().any(
+                    |diff| matches!(diff, DiffType::MissingRow { position, .. } if position.row == *row),
+                )
+            };
+            let rows = sampled_rows
+                .clone()
+                .unwrap_or_else(|| (0..nominal.rows().len()).collect());
+            Some(rows.into_iter().filter(|row| !is_missing(row)).collect())
+        }
+        None => sampled_rows,
+    };
+
+    let missing_columns = if config.match_columns_by_header {
+        find_missing_columns(nominal, actual, config)
+    } else {
+        Vec::new()
+    };
+    let nominal_only_columns = missing_columns
+        .iter()
+        .filter(|diff| {
+            matches!(
+                diff,
+                DiffType::MissingColumn {
+                    side: Side::Actual,
+                    ..
+                }
+            )
+        })
+        .count();
+    let common_columns = nominal.columns.len() - nominal_only_columns;
+
+    let diffs: Vec<Vec<DiffType>> = nominal.columns[..common_columns]
+        .par_iter()
+        .zip(actual.columns.par_iter())
+        .enumerate()
+        .map(|(col, (col_nom, col_act))| {
+            let header = col_nom.header.as_deref();
+            let header_unit_modes: Vec<Mode> = header
+                .and_then(|header| config.header_unit_mode(header))
+                .into_iter()
+                .collect();
+            let modes = header
+                .and_then(|header| {
+                    column_mode_patterns
+                        .iter()
+                        .find(|(pattern, _)| pattern.matches(header))
+                })
+                .map(|(_, modes)| *modes)
+                .or((!header_unit_modes.is_empty()).then_some(header_unit_modes.as_slice()))
+                .unwrap_or(config.comparison_modes.as_slice());
+            let cells = match (header, config.json_columns.as_ref()) {
+                (Some(header), Some(json_columns))
+                    if json_column_patterns
+                        .iter()
+                        .any(|pattern| pattern.matches(header)) =>
+                {
+                    CellComparison::Json(&json_columns.options)
+                }
+                _ => CellComparison::Modes(modes),
+            };
+            compare_columns(
+                col_nom,
+                col_act,
+                col,
+                config,
+                cells,
+                exclude_regex.as_ref(),
+                compared_rows.as_deref(),
+            )
+        })
+        .collect();
+    let mut diffs: Vec<DiffType> = diffs.into_iter().flatten().collect();
+    diffs.extend(missing_columns);
+    diffs.extend(missing_rows);
+    diffs.extend(surplus_rows);
+    for validation in config.validations.iter() {
+        diffs.extend(validation.validate(actual)?);
     }
     Ok(diffs)
 }
 
+/// How the cells of a column are compared
+#[derive(Clone, Copy)]
+enum CellComparison<'a> {
+    /// as values, numbers with these modes
+    Modes(&'a [Mode]),
+    /// as JSON documents
+    Json(&'a JsonConfig),
+}
+
+fn compare_columns(
+    col_nom: &Column,
+    col_act: &Column,
+    col: usize,
+    config: &CSVCompareConfig,
+    cells: CellComparison,
+    exclude_regex: Option<&Regex>,
+    sampled_rows: Option<&[usize]>,
+) -> Vec<DiffType> {
+    let modes = match cells {
+        CellComparison::Modes(modes) => modes,
+        CellComparison::Json(_) => &[],
+    };
+    let column_max = if modes.iter().any(Mode::needs_column_max) {
+        col_nom
+            .rows
+            .iter()
+            .filter_map(|v| v.get_quantity())
+            .map(|q| q.value.abs())
+            .filter(|v| !v.is_nan())
+            .fold(0.0, f64::max)
+    } else {
+        0.0
+    };
+    let mut diffs = Vec::new();
+    let deleted_column =
+        config.deleted_matches_anything && (col_nom.is_deleted() || col_act.is_deleted());
+    if let (Some(nom_header), Some(act_header)) = (&col_nom.header, &col_act.header) {
+        if !deleted_column && !config.headers_equal(nom_header, act_header) {
+            diffs.push(DiffType::UnequalHeader {
+                nominal: nom_header.to_owned(),
+                actual: act_header.to_owned(),
+            });
+        }
+    }
+
+    let compare_cells = |val_nom: &Value, val_act: &Value, position: Position| match cells {
+        CellComparison::Modes(modes) => compare_values(
+            val_nom,
+            val_act,
+            config,
+            modes,
+            exclude_regex,
+            column_max,
+            position,
+        ),
+        CellComparison::Json(json_config) => {
+            compare_json_cells(val_nom, val_act, json_config, position)
+        }
+    };
+
+    if let Some(sampled_rows) = sampled_rows {
+        for &row in sampled_rows {
+            if let (Some(val_nom), Some(val_act)) = (col_nom.rows.get(row), col_act.rows.get(row)) {
+                diffs.extend(compare_cells(val_nom, val_act, Position { row, col }));
+            }
+        }
+    } else {
+        for (row, (val_nom, val_act)) in col_nom.rows.iter().zip(col_act.rows.iter()).enumerate() {
+            diffs.extend(compare_cells(val_nom, val_act, Position { row, col }));
+        }
+    }
+    diffs
+}
+
+/// Parses a cell as JSON document, a quoted literal is unquoted first
+fn parse_json_cell(cell: &Value) -> Option<serde_json::Value> {
+    let cell = cell.as_str();
+    let document = match cell.strip_prefix('"').and_then(|c| c.strip_suffix('"')) {
+        Some(unquoted) => unquoted.replace("\"\"", "\""),
+        None => cell.into_owned(),
+    };
+    serde_json::from_str(&document).ok()
+}
+
+fn compare_json_cells(
+    nominal: &Value,
+    actual: &Value,
+    config: &JsonConfig,
+    position: Position,
+) -> Vec<DiffType> {
+    let (Some(nominal_json), Some(actual_json)) =
+        (parse_json_cell(nominal), parse_json_cell(actual))
+    else {
+        if nominal.as_str() == actual.as_str() {
+            return Vec::new();
+        }
+        return vec![DiffType::UnequalStrings {
+            nominal: nominal.as_str().into_owned(),
+            actual: actual.as_str().into_owned(),
+            position,
+        }];
+    };
+    let difference = compare_json_values(&nominal_json, &actual_json, config);
+    if !difference.is_error {
+        return Vec::new();
+    }
+    vec![DiffType::UnequalJson {
+        differences: difference.detail.iter().map(ToString::to_string).join(" "),
+        position,
+    }]
+}
+
 fn both_quantity<'a>(
     actual: &'a Value,
     nominal: &'a Value,
@@ -429,52 +1554,308 @@ fn both_string(actual: &Value, nominal: &Value) -> Option<(String, String)> {
     None
 }
 
+/// Whether a cell is empty or only whitespace, a quoted literal is blank if its content is
+fn is_blank(cell: &str) -> bool {
+    let unquoted = cell
+        .strip_prefix('"')
+        .and_then(|c| c.strip_suffix('"'))
+        .unwrap_or(cell);
+    unquoted.trim().is_empty()
+}
+
 fn compare_values(
     nominal: &Value,
     actual: &Value,
     config: &CSVCompareConfig,
+    modes: &[Mode],
+    exclude_regex: Option<&Regex>,
+    column_max: f64,
     position: Position,
-) -> Result<Vec<DiffType>, Error> {
+) -> Vec<DiffType> {
+    if config.deleted_matches_anything && (nominal.is_deleted() || actual.is_deleted()) {
+        return Vec::new();
+    }
+    // excluded if the string of either side matches, also if the other side is no string
+    if let Some(exclude_regex) = exclude_regex {
+        let is_excluded = |v: &Value| v.get_string().is_some_and(|s| exclude_regex.is_match(&s));
+        if is_excluded(nominal) || is_excluded(actual) {
+            return Vec::new();
+        }
+    }
+    // boolean compare, only if both cells are recognized booleans
+    if let Some(vocabulary) = &config.boolean_normalization {
+        if let (Some(nominal_bool), Some(actual_bool)) =
+            (vocabulary.normalize(nominal), vocabulary.normalize(actual))
+        {
+            return if nominal_bool == actual_bool {
+                Vec::new()
+            } else {
+                vec![DiffType::UnequalStrings {
+                    nominal: nominal.as_str().into_owned(),
+                    actual: actual.as_str().into_owned(),
+                    position,
+                }]
+            };
+        }
+    }
     // float quantity compare
     if let Some((actual_float, nominal_float)) = both_quantity(actual, nominal) {
-        Ok(config
-            .comparison_modes
-            .iter()
-            .filter_map(|cm| {
-                if !cm.in_tolerance(nominal_float, actual_float) {
-                    Some(DiffType::OutOfTolerance {
+        let converted;
+        let actual_float = if config.unit_conversion {
+            match actual_float.converted_to(nominal_float.unit.as_deref()) {
+                Some(actual_converted) => {
+                    converted = actual_converted;
+                    &converted
+                }
+                None => {
+                    return vec![DiffType::IncompatibleUnits {
                         nominal: nominal_float.clone(),
                         actual: actual_float.clone(),
-                        mode: *cm,
                         position,
-                    })
-                } else {
-                    None
+                    }]
                 }
+            }
+        } else {
+            actual_float
+        };
+        let failed_modes: Vec<&Mode> = modes
+            .iter()
+            .filter(|cm| !cm.in_tolerance_in_column(nominal_float, actual_float, column_max))
+            .collect();
+        let reported_modes = match config.mode_combination {
+            ModeCombination::All => failed_modes,
+            ModeCombination::Any if failed_modes.len() < modes.len() => Vec::new(),
+            ModeCombination::Any => failed_modes
+                .into_iter()
+                .min_by(|a, b| {
+                    a.tolerance_ratio(nominal_float, actual_float, column_max)
+                        .total_cmp(&b.tolerance_ratio(nominal_float, actual_float, column_max))
+                })
+                .into_iter()
+                .collect(),
+        };
+        reported_modes
+            .into_iter()
+            .map(|cm| DiffType::OutOfTolerance {
+                nominal: nominal_float.clone(),
+                actual: actual_float.clone(),
+                mode: *cm,
+                deviation: cm.deviation(nominal_float, actual_float, column_max),
+                position,
             })
-            .collect())
+            .collect()
     } else if let Some((actual_string, nominal_string)) = both_string(actual, nominal) {
-        if let Some(exclude_regex) = config.exclude_field_regex.as_deref() {
-            let regex = Regex::new(exclude_regex)?;
-            if regex.is_match(nominal_string.as_str()) {
-                return Ok(Vec::new());
+        let equal = config
+            .string_comparison
+            .equal(&nominal_string, &actual_string)
+            || (config.whitespace_cells_as_empty
+                && is_blank(&nominal_string)
+                && is_blank(&actual_string));
+        if !equal {
+            vec![DiffType::UnequalStrings {
+                position,
+                nominal: nominal_string,
+                actual: actual_string,
+            }]
+        } else {
+            Vec::new()
+        }
+    } else {
+        vec![DiffType::DifferentValueTypes {
+            actual: actual.clone(),
+            nominal: nominal.clone(),
+            position,
+        }]
+    }
+}
+
+/// The headers only found in nominal and the ones only found in actual, matched respecting the configured synonyms.
+/// `None` if any column of either table has no header.
+fn one_sided_headers(
+    nominal: &Table,
+    actual: &Table,
+    config: &CSVCompareConfig,
+) -> Option<(Vec<String>, Vec<String>)> {
+    let nominal_headers = nominal.headers();
+    let actual_headers = actual.headers();
+    if nominal_headers
+        .iter()
+        .chain(actual_headers.iter())
+        .any(|h| h.is_none())
+    {
+        return None;
+    }
+    let contains = |headers: &[Option<&str>], header: &str| {
+        headers
+            .iter()
+            .flatten()
+            .any(|h| config.headers_equal(h, header))
+    };
+
+    let nominal_only: Vec<String> = nominal_headers
+        .iter()
+        .flatten()
+        .filter(|h| !contains(&actual_headers, h))
+        .map(|h| h.to_string())
+        .collect();
+    let actual_only: Vec<String> = actual_headers
+        .iter()
+        .flatten()
+        .filter(|h| !contains(&nominal_headers, h))
+        .map(|h| h.to_string())
+        .collect();
+    Some((nominal_only, actual_only))
+}
+
+/// Moves the actual columns into the order of their nominal counterparts, matched by header.
+/// Columns without counterpart are kept behind the matched ones, in their original order.
+fn order_columns_like_nominal(
+    nominal: &mut Table,
+    actual: &mut Table,
+    nominal_only: &[String],
+    config: &CSVCompareConfig,
+) {
+    let (mut matched, unmatched): (Vec<Column>, Vec<Column>) = std::mem::take(&mut nominal.columns)
+        .into_iter()
+        .partition(|c| !nominal_only.contains(c.header.as_ref().unwrap_or(&String::new())));
+    matched.extend(unmatched);
+    nominal.columns = matched;
+
+    let mut actual_columns = std::mem::take(&mut actual.columns);
+    for header in nominal.headers().into_iter().flatten() {
+        if let Some(position) = actual_columns.iter().position(|c| {
+            c.header
+                .as_deref()
+                .is_some_and(|h| config.headers_equal(header, h))
+        }) {
+            actual.columns.push(actual_columns.remove(position));
+        }
+    }
+    actual.columns.extend(actual_columns);
+}
+
+/// Drops all columns whose header is not present in both tables and brings the actual columns into nominal order.
+/// Headers are matched respecting the configured synonyms.
+/// Returns the headers only found in nominal and the ones only found in actual.
+fn retain_common_columns(
+    nominal: &mut Table,
+    actual: &mut Table,
+    config: &CSVCompareConfig,
+) -> (Vec<String>, Vec<String>) {
+    let Some((nominal_only, actual_only)) = one_sided_headers(nominal, actual, config) else {
+        warn!("Comparing common columns only requires headers for all columns - comparing all columns");
+        return (Vec::new(), Vec::new());
+    };
+    order_columns_like_nominal(nominal, actual, &nominal_only, config);
+    let common_columns = nominal.columns.len() - nominal_only.len();
+    nominal.columns.truncate(common_columns);
+    actual.columns.truncate(common_columns);
+    (nominal_only, actual_only)
+}
+
+/// The first header occurring more than once in the table, synonyms counting as the same header
+fn duplicate_header(table: &Table, config: &CSVCompareConfig) -> Option<String> {
+    let mut seen = HashSet::new();
+    table
+        .columns
+        .iter()
+        .filter_map(|column| column.header.as_deref())
+        .find(|header| !seen.insert(config.canonical_header(header)))
+        .map(str::to_owned)
+}
+
+/// The columns only present on one side, reported as missing on the other side, only with `match_columns_by_header`
+fn find_missing_columns(
+    nominal: &Table,
+    actual: &Table,
+    config: &CSVCompareConfig,
+) -> Vec<DiffType> {
+    let Some((nominal_only, actual_only)) = one_sided_headers(nominal, actual, config) else {
+        return Vec::new();
+    };
+    nominal_only
+        .into_iter()
+        .map(|header| DiffType::MissingColumn {
+            header,
+            side: Side::Actual,
+        })
+        .chain(
+            actual_only
+                .into_iter()
+                .map(|header| DiffType::MissingColumn {
+                    header,
+                    side: Side::Nominal,
+                }),
+        )
+        .collect()
+}
+
+pub(crate) fn prepare_tables<R: Read + Seek + Send>(
+    nominal: R,
+    actual: R,
+    config: &CSVCompareConfig,
+) -> Result<(Table, Table), Error> {
+    let tables: Result<Vec<Table>, Error> = [
+        (nominal, config.delimiters.clone()),
+        (actual, config.actual_delimiters()),
+    ]
+    .into_par_iter()
+    .map(|(r, delimiters)| Table::from_reader(r, &delimiters))
+    .collect();
+    let mut tables = tables?;
+    if let (Some(mut actual), Some(mut nominal)) = (tables.pop(), tables.pop()) {
+        if let Some(preprocessors) = config.preprocessing.as_ref() {
+            for preprocessor in preprocessors.iter() {
+                if preprocessor.applies_to(Side::Nominal) {
+                    preprocessor.process(&mut nominal)?;
+                }
+                if preprocessor.applies_to(Side::Actual) {
+                    preprocessor.process(&mut actual)?;
+                }
+                if matches!(preprocessor, Preprocessor::ExtractHeaders) {
+                    preprocessing::handle_duplicate_headers(
+                        &mut nominal,
+                        config.duplicate_headers,
+                    )?;
+                    preprocessing::handle_duplicate_headers(&mut actual, config.duplicate_headers)?;
+                }
+            }
+        }
+        if config.compare_common_columns_only {
+            let (nominal_only, actual_only) =
+                retain_common_columns(&mut nominal, &mut actual, config);
+            if !nominal_only.is_empty() {
+                warn!(
+                    "Ignoring columns only present in nominal: {:?}",
+                    nominal_only
+                );
+            }
+            if !actual_only.is_empty() {
+                warn!("Ignoring columns only present in actual: {:?}", actual_only);
+            }
+        }
+        if config.match_columns_by_header {
+            if let Some(header) = [&nominal, &actual]
+                .into_iter()
+                .find_map(|table| duplicate_header(table, config))
+            {
+                return Err(Error::DuplicateHeader(header));
+            }
+            match one_sided_headers(&nominal, &actual, config) {
+                Some((nominal_only, _)) => {
+                    order_columns_like_nominal(&mut nominal, &mut actual, &nominal_only, config)
+                }
+                None => warn!(
+                    "Matching columns by header requires headers for all columns - matching by position"
+                ),
             }
         }
-        if nominal_string != actual_string {
-            Ok(vec![DiffType::UnequalStrings {
-                position,
-                nominal: nominal_string,
-                actual: actual_string,
-            }])
-        } else {
-            Ok(Vec::new())
+        if let Some(key) = config.align_by_column.as_deref() {
+            align_rows(&mut nominal, &mut actual, key, config)?;
         }
+        Ok((nominal, actual))
     } else {
-        Ok(vec![DiffType::DifferentValueTypes {
-            actual: actual.clone(),
-            nominal: nominal.clone(),
-            position,
-        }])
+        Err(Error::UnterminatedLiteral)
     }
 }
 
@@ -483,23 +1864,9 @@ fn get_diffs_readers<R: Read + Seek + Send>(
     actual: R,
     config: &CSVCompareConfig,
 ) -> Result<(Table, Table, Vec<DiffType>), Error> {
-    let tables: Result<Vec<Table>, Error> = [nominal, actual]
-        .into_par_iter()
-        .map(|r| Table::from_reader(r, &config.delimiters))
-        .collect();
-    let mut tables = tables?;
-    if let (Some(mut actual), Some(mut nominal)) = (tables.pop(), tables.pop()) {
-        if let Some(preprocessors) = config.preprocessing.as_ref() {
-            for preprocessor in preprocessors.iter() {
-                preprocessor.process(&mut nominal)?;
-                preprocessor.process(&mut actual)?;
-            }
-        }
-        let comparison_result = compare_tables(&nominal, &actual, config)?;
-        Ok((nominal, actual, comparison_result))
-    } else {
-        Err(Error::UnterminatedLiteral)
-    }
+    let (nominal, actual) = prepare_tables(nominal, actual, config)?;
+    let comparison_result = compare_tables(&nominal, &actual, config)?;
+    Ok((nominal, actual, comparison_result))
 }
 
 pub(crate) fn compare_paths(
@@ -525,7 +1892,8 @@ pub(crate) fn compare_paths(
 mod tests {
     use super::*;
     use crate::csv::DiffType::{
-        DifferentValueTypes, OutOfTolerance, UnequalHeader, UnequalStrings,
+        DifferentValueTypes, IncompatibleUnits, OutOfTolerance, UnequalHeader, UnequalJson,
+        UnequalStrings,
     };
     use crate::csv::Preprocessor::ExtractHeaders;
     use std::io::Cursor;
@@ -556,6 +1924,56 @@ mod tests {
         assert!(msg.contains(format!("{POS_ROW}").as_str()));
     }
 
+    #[test]
+    fn out_of_tolerance_reports_deviation_per_mode() {
+        let (nominal, actual) = ("1;10;200\n", "1;12;220\n");
+        let mut config = CSVCompareConfig {
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            comparison_modes: vec![Mode::Absolute(1.0)],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        let OutOfTolerance { deviation, .. } = &diffs[0] else {
+            panic!("expected out of tolerance, got {}", diffs[0]);
+        };
+        assert_eq!(
+            *deviation,
+            Deviation {
+                absolute: 2.0,
+                relative: 0.2,
+                excess: 1.0
+            }
+        );
+        assert_eq!(
+            diffs[0].to_string(),
+            "Line: 0, Col: 1 -- Out of tolerance -- Expected 10, Found 12, Mode Absolute (tol: 1), deviation: 2 (relative: 0.2), exceeds tolerance by: 1"
+        );
+
+        config.comparison_modes = vec![Mode::Relative(0.05)];
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        let OutOfTolerance { deviation, .. } = &diffs[1] else {
+            panic!("expected out of tolerance, got {}", diffs[1]);
+        };
+        assert_eq!(
+            *deviation,
+            Deviation {
+                absolute: 20.0,
+                relative: 0.1,
+                excess: 0.05
+            }
+        );
+        assert_eq!(
+            diffs[1].to_string(),
+            "Line: 0, Col: 2 -- Out of tolerance -- Expected 200, Found 220, Mode Relative (tol: 0.05), deviation: 20 (relative: 0.1), exceeds tolerance by: 0.05"
+        );
+    }
+
     #[test]
     fn diff_types_readable_out_of_tolerance() {
         let string_unequal = OutOfTolerance {
@@ -568,9 +1986,15 @@ mod tests {
                 unit: Some("um".to_owned()),
             },
             mode: Mode::Absolute(11.0),
+            deviation: Deviation {
+                absolute: 2.0,
+                relative: 0.2,
+                excess: -9.0,
+            },
             position: mk_position(),
         };
         let msg = format!("{string_unequal}");
+        assert!(msg.contains("deviation: 2 (relative: 0.2), exceeds tolerance by: -9"));
         assert!(msg.contains("10 mm"));
         assert!(msg.contains("11"));
         assert!(msg.contains("12 um"));
@@ -620,6 +2044,7 @@ mod tests {
             comparison_modes: vec![Mode::Absolute(0.0), Mode::Relative(0.0)],
             delimiters: Delimiters::default(),
             preprocessing: None,
+            ..Default::default()
         };
 
         let actual = File::open("tests/csv/data/Annotations.csv").unwrap();
@@ -636,6 +2061,7 @@ mod tests {
             exclude_field_regex: Some(r"Surface".to_owned()),
             comparison_modes: vec![],
             delimiters: Delimiters::default(),
+            ..Default::default()
         };
 
         let actual = Table::from_reader(
@@ -674,6 +2100,7 @@ mod tests {
             exclude_field_regex: None,
             comparison_modes: vec![],
             delimiters: Delimiters::default(),
+            ..Default::default()
         };
 
         let mut actual = Table::from_reader(
@@ -711,6 +2138,7 @@ mod tests {
             exclude_field_regex: Some(r"Surface".to_owned()),
             comparison_modes: vec![],
             delimiters: Delimiters::default(),
+            ..Default::default()
         };
 
         let actual = File::open("tests/csv/data/DeviationHistogram.csv").unwrap();
@@ -739,6 +2167,7 @@ mod tests {
             exclude_field_regex: Some(r"Surface".to_owned()),
             comparison_modes: vec![Mode::Absolute(0.5)],
             delimiters: Delimiters::default(),
+            ..Default::default()
         };
 
         let actual = File::open("tests/csv/data/DeviationHistogram.csv").unwrap();
@@ -761,6 +2190,21 @@ mod tests {
         assert!(msg.contains("0.1"));
         assert!(msg.contains("Relative"));
 
+        let abs = Mode::DecimalPlaces(3);
+        let msg = format!("{abs}");
+        assert!(msg.contains('3'));
+        assert!(msg.contains("Decimal places"));
+
+        let abs = Mode::AbsoluteMagnitude(0.5);
+        let msg = format!("{abs}");
+        assert!(msg.contains("0.5"));
+        assert!(msg.contains("magnitude"));
+
+        let abs = Mode::RelativeToColumnMax(0.01);
+        let msg = format!("{abs}");
+        assert!(msg.contains("0.01"));
+        assert!(msg.contains("column max"));
+
         let abs = Mode::Ignore;
         let msg = format!("{abs}");
         assert!(msg.contains("Ignored"));
@@ -773,6 +2217,7 @@ mod tests {
             exclude_field_regex: None,
             comparison_modes: vec![Mode::Absolute(0.5)],
             delimiters: Delimiters::autodetect(),
+            ..Default::default()
         };
 
         let actual = File::open(
@@ -796,6 +2241,7 @@ mod tests {
             exclude_field_regex: Some(r"Surface".to_owned()),
             comparison_modes: vec![Mode::Relative(0.1)],
             delimiters: Delimiters::default(),
+            ..Default::default()
         };
 
         let actual = File::open("tests/csv/data/DeviationHistogram.csv").unwrap();
@@ -853,6 +2299,40 @@ mod tests {
         assert!(!rel_mode.in_tolerance(&Quantity::new(2.0, None), &Quantity::new(4.01, None)));
     }
 
+    #[test]
+    fn basic_compare_modes_test_decimal_places() {
+        let places_mode = Mode::DecimalPlaces(3);
+        assert!(
+            places_mode.in_tolerance(&Quantity::new(1.2341, None), &Quantity::new(1.2344, None))
+        );
+        assert!(places_mode.in_tolerance(&Quantity::new(-0.5, None), &Quantity::new(-0.5001, None)));
+        assert!(places_mode.in_tolerance(&Quantity::new(10.0, None), &Quantity::new(10.0, None)));
+
+        assert!(!places_mode.in_tolerance(&Quantity::new(1.234, None), &Quantity::new(1.235, None)));
+        assert!(!places_mode.in_tolerance(
+            &Quantity::new(1.234, Some("mm")),
+            &Quantity::new(1.234, Some("m"))
+        ));
+        assert!(Mode::DecimalPlaces(2)
+            .in_tolerance(&Quantity::new(1.234, None), &Quantity::new(1.231, None)));
+    }
+
+    #[test]
+    fn basic_compare_modes_test_absolute_magnitude() {
+        let magnitude_mode = Mode::AbsoluteMagnitude(0.5);
+        let absolute_mode = Mode::Absolute(0.5);
+        let minus_five = Quantity::new(-5.0, None);
+        let five = Quantity::new(5.0, None);
+        assert!(magnitude_mode.in_tolerance(&minus_five, &five));
+        assert!(!absolute_mode.in_tolerance(&minus_five, &five));
+        assert!(magnitude_mode.in_tolerance(&Quantity::new(-5.4, None), &five));
+        assert!(!magnitude_mode.in_tolerance(&Quantity::new(-5.6, None), &five));
+        assert!(!magnitude_mode.in_tolerance(
+            &Quantity::new(-5.0, Some("mm")),
+            &Quantity::new(5.0, Some("m"))
+        ));
+    }
+
     #[test]
     fn check_same_numbers_different_missmatch() {
         let rel_mode = Mode::Relative(1.0);
@@ -897,6 +2377,7 @@ mod tests {
             delimiters: Delimiters::default(),
             exclude_field_regex: None,
             comparison_modes: vec![Mode::Absolute(0.0)],
+            ..Default::default()
         };
         let (_, _, res) =
             get_diffs_readers(Cursor::new(str_with_bom), Cursor::new(str_no_bom), &cfg).unwrap();
@@ -916,6 +2397,7 @@ mod tests {
         let col_two = col.clone();
         Table {
             columns: vec![col, col_two],
+            decimal_separator: None,
         }
     }
 
@@ -962,6 +2444,7 @@ mod tests {
             delimiters: Delimiters::default(),
             exclude_field_regex: None,
             preprocessing: None,
+            ..Default::default()
         };
         let result = compare_paths("non_existing", "also_non_existing", &conf);
         assert!(matches!(result.unwrap_err(), Error::FileAccessFailed(_)));
@@ -999,4 +2482,860 @@ mod tests {
             assert!(mode.in_tolerance(&quantity1, &quantity2));
         }
     }
+
+    #[test]
+    fn common_columns_only_ignores_extra_columns() {
+        let nominal = "Name,Volume\nfirst,1.0\nsecond,2.0\n";
+        let actual = "Name,Debug info,Volume\nfirst,tmp_123,1.0\nsecond,tmp_456,2.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            ..Default::default()
+        };
+
+        let (_, _, diff) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(!diff.is_empty());
+
+        config.compare_common_columns_only = true;
+        let (nominal_table, actual_table, diff) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(nominal_table.columns.len(), 2);
+        assert_eq!(actual_table.headers(), vec![Some("Name"), Some("Volume")]);
+    }
+
+    #[test]
+    fn sampling_compares_only_sampled_rows() {
+        let nominal: String = (0..100).map(|i| format!("{i};a\n")).collect();
+        let actual: String = (0..100).map(|i| format!("{};b\n", i + 1)).collect();
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            sample_rows: Some(5),
+            sample_seed: 42,
+            ..Default::default()
+        };
+        let sampled = config.sampled_rows(100).unwrap();
+        assert_eq!(sampled.len(), 5);
+        assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(config.sampled_rows(100).unwrap(), sampled);
+        assert!(config.sampled_rows(5).is_none());
+
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(&nominal), Cursor::new(&actual), &config).unwrap();
+        assert_eq!(diffs.len(), 10);
+        assert!(diffs.iter().all(|d| match d {
+            OutOfTolerance { position, .. } | UnequalStrings { position, .. } =>
+                sampled.contains(&position.row),
+            _ => false,
+        }));
+
+        config.sample_seed = 7;
+        assert_ne!(config.sampled_rows(100).unwrap(), sampled);
+        config.sample_rows = None;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(&nominal), Cursor::new(&actual), &config).unwrap();
+        assert_eq!(diffs.len(), 200);
+    }
+
+    #[test]
+    fn header_synonyms_compare_equal() {
+        let nominal = "Name,Volume,Area\nblob,1.0,2.0\n";
+        let actual = "Name,Vol,Surface\nblob,1.0,2.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+
+        config.header_synonyms = vec![
+            vec!["Volume".to_string(), "Vol".to_string()],
+            vec!["Area".to_string(), "Surface".to_string()],
+        ];
+        assert!(config.headers_equal("Vol", "Volume"));
+        assert!(!config.headers_equal("Vol", "Area"));
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "Surface,Name,Vol,Extra\n2.0,blob,1.0,3.0\n";
+        config.compare_common_columns_only = true;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+    }
+
+    #[test]
+    fn parallel_and_sequential_column_compare_agree() {
+        let columns = 200;
+        let row = |offset: usize| -> String {
+            (0..columns)
+                .map(|c| {
+                    if c % 3 == 0 {
+                        format!("text{}", (c + offset) % 7)
+                    } else {
+                        format!("{}", c * offset)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let nominal: String = (0..20).map(|r| row(r) + "\n").collect();
+        let actual: String = (0..20).map(|r| row(r + (r % 2)) + "\n").collect();
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(1.0)],
+            exclude_field_regex: Some("text3".to_string()),
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        let (nominal, actual) =
+            prepare_tables(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+
+        let parallel = compare_tables(&nominal, &actual, &config).unwrap();
+        let exclude_regex = Regex::new("text3").unwrap();
+        let sequential: Vec<DiffType> = nominal
+            .columns
+            .iter()
+            .zip(actual.columns.iter())
+            .enumerate()
+            .flat_map(|(col, (n, a))| {
+                compare_columns(
+                    n,
+                    a,
+                    col,
+                    &config,
+                    CellComparison::Modes(&config.comparison_modes),
+                    Some(&exclude_regex),
+                    None,
+                )
+            })
+            .collect();
+
+        assert!(!parallel.is_empty());
+        assert_eq!(format!("{parallel:?}"), format!("{sequential:?}"));
+    }
+
+    #[test]
+    fn exclude_regex_compiled_per_table() {
+        let nominal = "Surface A;1\nSurface B;2\nVolume;3\n";
+        let actual = "Other A;1\nOther B;2\nOther;3\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            exclude_field_regex: Some("Surface".to_string()),
+            ..Default::default()
+        };
+        assert!(config.exclude_regex().unwrap().is_some());
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(&diffs[0], UnequalStrings { nominal, .. } if nominal == "Volume"));
+
+        // an invalid regex fails the table comparison up front, even without any string cells
+        config.exclude_field_regex = Some("(".to_string());
+        let numbers = "1;2\n3;4\n";
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(numbers), Cursor::new(numbers), &config),
+            Err(Error::RegexCompilationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn relative_to_column_max_tolerates_near_zero_values() {
+        let nominal = "1000.0\n0.001\n-500.0\n";
+        let actual = "1001.0\n0.002\n-500.5\n";
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Relative(0.01)],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(&diffs[0], OutOfTolerance { position, .. } if position.row == 1));
+
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::RelativeToColumnMax(0.01)],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "1001.0\n20.0\n-500.5\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+    }
+
+    #[test]
+    fn column_modes_override_global_modes_by_header() {
+        let nominal = "Pos X;Pos Y;Surface\n1.0;2.0;100.0\n";
+        let actual = "Pos X;Pos Y;Surface\n1.05;2.5;104.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            column_modes: Some(vec![
+                ColumnModeOverride {
+                    column: "Pos Y".to_string(),
+                    modes: vec![Mode::Absolute(1.0)],
+                },
+                ColumnModeOverride {
+                    column: "Surf*".to_string(),
+                    modes: vec![Mode::Relative(0.05)],
+                },
+                ColumnModeOverride {
+                    column: "*".to_string(),
+                    modes: vec![Mode::Ignore],
+                },
+            ]),
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        config.column_modes = Some(vec![ColumnModeOverride {
+            column: "Surf*".to_string(),
+            modes: vec![Mode::Relative(0.01)],
+        }]);
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(
+            matches!(&diffs[0], OutOfTolerance { mode: Mode::Absolute(_), position, .. } if position.col == 1)
+        );
+        assert!(
+            matches!(&diffs[1], OutOfTolerance { mode: Mode::Relative(_), position, .. } if position.col == 2)
+        );
+
+        // without headers the global modes apply
+        config.preprocessing = None;
+        let (nominal, actual) = ("1.0;2.0\n", "1.0;2.5\n");
+        config.column_modes = Some(vec![ColumnModeOverride {
+            column: "*".to_string(),
+            modes: vec![Mode::Ignore],
+        }]);
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+
+        config.column_modes = Some(vec![ColumnModeOverride {
+            column: "[".to_string(),
+            modes: vec![],
+        }]);
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config),
+            Err(Error::InvalidColumnPattern(_))
+        ));
+    }
+
+    #[test]
+    fn tab_separated_file_is_parsed() {
+        let table = Table::from_reader(
+            File::open("tests/csv/data/tab_separated.tsv").unwrap(),
+            &Delimiters::autodetect(),
+        )
+        .unwrap();
+        assert_eq!(table.columns.len(), 4);
+        assert_eq!(table.rows().len(), 4);
+        assert_eq!(table.columns[1].rows[1], Value::from_str("1.5", &None));
+
+        let config: CSVCompareConfig = serde_yaml::from_str(
+            "field_delimiter: \"\\t\"\ndecimal_separator: ','\ncomparison_modes: []",
+        )
+        .unwrap();
+        assert_eq!(config.delimiters.field_delimiter, Some('\t'));
+        let table = Table::from_reader(
+            File::open("tests/csv/data/tab_separated.tsv").unwrap(),
+            &config.delimiters,
+        )
+        .unwrap();
+        assert_eq!(table.columns.len(), 4);
+    }
+
+    #[test]
+    fn row_count_mismatch_compares_common_rows() {
+        let nominal = "1.0;a\n2.0;b\n";
+        let actual = "1.0;a\n2.5;b\n3.0;c\n4.0;d\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config),
+            Err(Error::UnequalRowCount(2, 4))
+        ));
+
+        config.row_count_mismatch = RowCountMismatch::CompareCommonPrefix;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(
+            matches!(&diffs[0], OutOfTolerance { position, .. } if position.row == 1 && position.col == 0)
+        );
+        assert!(matches!(
+            &diffs[1],
+            DiffType::SurplusRows {
+                side: Side::Actual,
+                count: 2
+            }
+        ));
+
+        config.row_count_mismatch = RowCountMismatch::ReportExtraRows;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(actual), Cursor::new(nominal), &config).unwrap();
+        assert_eq!(diffs.len(), 4);
+        assert!(matches!(
+            &diffs[1],
+            DiffType::SurplusRows {
+                side: Side::Nominal,
+                count: 2
+            }
+        ));
+        assert!(matches!(
+            &diffs[2],
+            DiffType::ExtraRow { side: Side::Nominal, row: 2, content } if content == "3, c"
+        ));
+        assert!(matches!(&diffs[3], DiffType::ExtraRow { row: 3, .. }));
+    }
+
+    #[test]
+    fn exclude_field_regex_matches_nominal_or_actual() {
+        let regex = Regex::new("^ID-").unwrap();
+        let config = CSVCompareConfig::default();
+        let compare = |nominal: Value, actual: Value| {
+            compare_values(
+                &nominal,
+                &actual,
+                &config,
+                &[],
+                Some(&regex),
+                0.0,
+                mk_position(),
+            )
+        };
+        let string = |s: &str| Value::String(s.to_string());
+        assert!(compare(string("ID-1"), string("ID-2")).is_empty());
+        assert!(compare(string(""), string("ID-2")).is_empty());
+        assert!(compare(string("ID-1"), string("other")).is_empty());
+        assert!(compare(Value::from_str("1.0", &None), string("ID-2")).is_empty());
+        assert_eq!(compare(string("a"), string("b")).len(), 1);
+    }
+
+    #[test]
+    fn final_newline_does_not_change_comparison() {
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            delimiters: Delimiters {
+                field_delimiter: Some(','),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        for (nominal, actual) in [
+            ("a\nb", "a\nb\n"),
+            ("a\nb\n", "a\nb"),
+            ("1,2\n3,4\n\n", "1,2\n3,4"),
+        ] {
+            let (nominal_table, actual_table, diffs) =
+                get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+            assert!(diffs.is_empty());
+            assert_eq!(nominal_table.rows().len(), actual_table.rows().len());
+        }
+    }
+
+    #[test]
+    fn headers_only_ignores_all_data() {
+        let nominal = "Id;Size;Name\n1;1.0;a\n2;2.0;b\n";
+        let actual = "Id;Size;Name\nx;big;7\n";
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            headers_only: true,
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "Id;Name;Size;Extra\n1;a;1.0;0\n2;b;2.0;0\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff, UnequalHeader { .. })));
+        assert!(matches!(
+            &diffs[2],
+            UnequalHeader { nominal, actual } if nominal.is_empty() && actual == "Extra"
+        ));
+    }
+
+    #[test]
+    fn json_cells_with_reordered_keys_are_equal() {
+        let nominal =
+            "Id;Payload\n1;\"{\"\"a\"\": 1, \"\"b\"\": [1, 2]}\"\n2;\"{\"\"x\"\": true}\"\n";
+        let actual =
+            "Id;Payload\n1;\"{\"\"b\"\": [2, 1], \"\"a\"\": 1}\"\n2;\"{\"\"x\"\": false}\"\n";
+        let config: CSVCompareConfig = serde_yaml::from_str(
+            r#"
+field_delimiter: ";"
+decimal_separator: "."
+comparison_modes: []
+preprocessing: [ExtractHeaders]
+json_columns:
+  columns: ["Pay*"]
+  sort_arrays: true
+"#,
+        )
+        .unwrap();
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(
+            &diffs[0],
+            UnequalJson { differences, position } if differences.contains("x->{true!=false}") && position.row == 1 && position.col == 1
+        ));
+
+        let config = CSVCompareConfig {
+            json_columns: None,
+            ..config
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff, UnequalStrings { .. })));
+    }
+
+    #[test]
+    fn schema_only_ignores_values_but_not_types() {
+        let nominal = "Id;Size;Name\n1;1.0;a\n2;2.0;b\n";
+        let actual = "Id;Size;Name\n7;9.5;x\n8;-3.0;\n9;1.0;y\n";
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            schema_only: true,
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "Id;Size;Name;Extra\n1;big;a;0\n2;2.0;b;0\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(matches!(
+            &diffs[0],
+            DiffType::UnequalColumnCount {
+                nominal: 3,
+                actual: 4
+            }
+        ));
+        assert!(matches!(
+            &diffs[1],
+            DiffType::UnequalColumnType {
+                column: 1,
+                nominal: ColumnType::Quantity,
+                actual: ColumnType::Mixed
+            }
+        ));
+    }
+
+    #[test]
+    fn string_comparison_options_relax_string_equality() {
+        let nominal = Value::String("Hello".to_string());
+        let actual = Value::String("hello ".to_string());
+        let diffs = |case_insensitive, trim_whitespace| {
+            let config = CSVCompareConfig {
+                string_comparison: StringCompareOptions {
+                    case_insensitive,
+                    trim_whitespace,
+                },
+                ..Default::default()
+            };
+            compare_values(&nominal, &actual, &config, &[], None, 0.0, mk_position())
+        };
+        assert!(matches!(
+            diffs(false, false).as_slice(),
+            [UnequalStrings { .. }]
+        ));
+        assert!(matches!(
+            diffs(true, false).as_slice(),
+            [UnequalStrings { .. }]
+        ));
+        assert!(matches!(
+            diffs(false, true).as_slice(),
+            [UnequalStrings { .. }]
+        ));
+        assert!(diffs(true, true).is_empty());
+    }
+
+    #[test]
+    fn align_by_column_matches_rows_by_key() {
+        let nominal = "Id;Size\nA;1.0\nB;2.0\nC;3.0\nD;4.0\n";
+        let actual = "Id;Size\nC;3.0\nE;5.0\nA;1.5\nD;4.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            align_by_column: Some("Id".to_string()),
+            ..Default::default()
+        };
+        let (nominal_table, actual_table, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(nominal_table.rows().len(), 5);
+        assert_eq!(actual_table.rows().len(), 5);
+        assert_eq!(diffs.len(), 3);
+        assert!(
+            matches!(&diffs[0], OutOfTolerance { position, .. } if position.row == 0 && position.col == 1)
+        );
+        assert!(matches!(
+            &diffs[1],
+            DiffType::MissingRow { key, side: Side::Actual, position } if key == "B" && position.row == 1
+        ));
+        assert!(matches!(
+            &diffs[2],
+            DiffType::MissingRow { key, side: Side::Nominal, position } if key == "E" && position.row == 4
+        ));
+
+        config.align_by_column = Some("Name".to_string());
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config),
+            Err(Error::InvalidAccess(_))
+        ));
+    }
+
+    #[test]
+    fn match_columns_by_header_reorders_actual_columns() {
+        let nominal = "Id;Size;Weight\nA;1.0;10.0\nB;2.0;20.0\n";
+        let actual = "Color;Weight;Id;Size\nred;10.0;A;1.0\nblue;20.5;B;2.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            match_columns_by_header: true,
+            ..Default::default()
+        };
+        let (_, actual_table, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(
+            actual_table.headers(),
+            vec![Some("Id"), Some("Size"), Some("Weight"), Some("Color")]
+        );
+        assert_eq!(diffs.len(), 2);
+        assert!(
+            matches!(&diffs[0], OutOfTolerance { position, .. } if position.row == 1 && position.col == 2)
+        );
+        assert!(matches!(
+            &diffs[1],
+            DiffType::MissingColumn { header, side: Side::Nominal } if header == "Color"
+        ));
+
+        config.match_columns_by_header = false;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.len() > 2);
+    }
+
+    #[test]
+    fn match_columns_by_header_rejects_duplicate_headers() {
+        let nominal = "Id;Size;Size\nA;1.0;2.0\n";
+        let actual = "Size;Id;Size\n2.0;A;1.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            match_columns_by_header: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config),
+            Err(Error::DuplicateHeader(header)) if header == "Size"
+        ));
+
+        config.duplicate_headers = DuplicateHeaders::Suffix;
+        let (_, actual_table, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(
+            actual_table.headers(),
+            vec![Some("Id"), Some("Size"), Some("Size_2")]
+        );
+        assert_eq!(diffs.len(), 2);
+    }
+
+    #[test]
+    fn deletion_in_one_file_only() {
+        // the header was renamed in actual, so the column is only deleted in nominal
+        let nominal = "Name;Volume\nfirst;1.0\nsecond;2.0\n";
+        let actual = "Name;Vol\nfirst;1.0\nsecond;2.0\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![
+                ExtractHeaders,
+                Preprocessor::DeleteColumnByName("Volume".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let (nominal_table, actual_table, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(nominal_table.columns[1].is_deleted());
+        assert!(!actual_table.columns[1].is_deleted());
+        assert_eq!(diffs.len(), 3);
+        assert!(matches!(&diffs[0], UnequalHeader { nominal, .. } if nominal == "DELETED"));
+        assert!(diffs[1..]
+            .iter()
+            .all(|d| matches!(d, DifferentValueTypes { nominal, .. } if nominal.is_deleted())));
+
+        config.deleted_matches_anything = true;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "Name;Vol\nfirst;1.0\nthird;3.0\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(matches!(diffs.as_slice(), [UnequalStrings { position, .. }] if position.row == 1));
+    }
+
+    #[test]
+    fn header_unit_tolerance_is_converted_into_header_unit() {
+        assert_eq!(header_unit("Position x [mm]"), Some("mm"));
+        assert_eq!(header_unit("Volume [ cm³ ] "), Some("cm³"));
+        assert_eq!(header_unit("Position x"), None);
+        assert_eq!(header_unit("Empty []"), None);
+
+        let nominal = "Position x [mm];Position y [cm];Name [-];Plain\n1.0;1.0;1.0;1.0\n";
+        let actual = "Position x [mm];Position y [cm];Name [-];Plain\n1.04;1.04;1.04;1.04\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.01)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            header_unit_tolerances: vec![UnitTolerance {
+                absolute: 0.05,
+                unit: "mm".to_string(),
+            }],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        // 0.05 mm is 0.005 cm, not convertible units and plain headers use the global modes
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs
+            .iter()
+            .all(|d| !matches!(d, OutOfTolerance { position, .. } if position.col == 0)));
+        assert!(matches!(
+            &diffs[0],
+            OutOfTolerance { mode: Mode::Absolute(tolerance), position, .. }
+                if position.col == 1 && (tolerance - 0.005).abs() < 1e-12
+        ));
+
+        config.column_modes = Some(vec![ColumnModeOverride {
+            column: "Position x*".to_string(),
+            modes: vec![Mode::Absolute(0.01)],
+        }]);
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 4);
+    }
+
+    #[test]
+    fn unit_conversion_compares_convertible_units() {
+        let nominal = "10 mm;2 cm²;1.5 µm;3 s\n";
+        let actual = "1 cm;200 mm²;1500 nm;3 mm\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Relative(1e-9)],
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 4);
+
+        config.unit_conversion = true;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(&diffs[0], IncompatibleUnits { position, .. } if position.col == 3));
+
+        let actual = "1.1 cm;200 mm²;1500 nm;3000 ms\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(
+            matches!(&diffs[0], OutOfTolerance { actual, position, .. } if position.col == 0 && actual.unit.as_deref() == Some("mm"))
+        );
+    }
+
+    #[test]
+    fn any_mode_combination_passes_if_one_mode_passes() {
+        let nominal = "1000.0;1.0\n";
+        let actual = "1005.0;1.5\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.1), Mode::Relative(0.01)],
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 3);
+
+        config.mode_combination = ModeCombination::Any;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        // 0.5 is 5 times the absolute tolerance but 50 times the relative one
+        assert!(matches!(
+            &diffs[0],
+            OutOfTolerance { mode: Mode::Absolute(_), position, .. } if position.col == 1
+        ));
+    }
+
+    #[test]
+    fn boolean_normalization_compares_meaning() {
+        let nominal = "True;1;no;Off;maybe\n";
+        let actual = "true;yes;0;1;Maybe\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 5);
+
+        config.boolean_normalization = Some(BooleanVocabulary::default());
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(matches!(&diffs[0], UnequalStrings { position, .. } if position.col == 3));
+        assert!(matches!(&diffs[1], UnequalStrings { position, .. } if position.col == 4));
+
+        config.boolean_normalization = Some(BooleanVocabulary {
+            truthy: vec!["maybe".to_owned(), "true".to_owned(), "1".to_owned()],
+            ..Default::default()
+        });
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(matches!(&diffs[0], DifferentValueTypes { position, .. } if position.col == 1));
+        assert!(matches!(&diffs[1], UnequalStrings { position, .. } if position.col == 3));
+    }
+
+    #[test]
+    fn decimal_separators_may_differ_per_file() {
+        let nominal = "a;b\n1.5;2.25\n-3.125;4\n";
+        let actual = "a;b\n1,5;2,25\n-3,125;4\n";
+        let mut config = CSVCompareConfig {
+            delimiters: Delimiters {
+                field_delimiter: Some(';'),
+                decimal_separator: Some('.'),
+            },
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(!diffs.is_empty());
+
+        config.actual_decimal_separator = Some(',');
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        config.delimiters = Delimiters::autodetect();
+        config.actual_decimal_separator = None;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+    }
+
+    #[test]
+    fn whitespace_cells_match_empty_cells_when_enabled() {
+        let nominal = "a,\" \",c\n1, ,\"\t\"\n";
+        let actual = "a,,c\n1,\"\",\n";
+        let mut config = CSVCompareConfig {
+            delimiters: Delimiters {
+                field_delimiter: Some(','),
+                decimal_separator: Some('.'),
+            },
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs.iter().all(|d| matches!(d, UnequalStrings { .. })));
+
+        config.whitespace_cells_as_empty = true;
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+
+        let actual = "a,x,c\n1,\"y\",\n";
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 2);
+    }
+
+    #[test]
+    fn rows_with_ignored_keys_are_dropped() {
+        let nominal = "id,value\nA,1\nTMP-1,5\nB,2\n";
+        let actual = "id,value\nA,1\nTMP-7,9\nTMP-8,3\nB,2\n";
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config),
+            Err(Error::UnequalRowCount(_, _))
+        ));
+
+        config.preprocessing = Some(vec![
+            ExtractHeaders,
+            Preprocessor::IgnoreRowsByKey {
+                column: "id".to_string(),
+                ignore_keys: vec!["^TMP-\\d+$".to_string()],
+            },
+        ]);
+        let (nominal_table, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert!(diffs.is_empty());
+        assert_eq!(nominal_table.columns[0].rows.len(), 2);
+    }
+
+    #[test]
+    fn validations_run_on_actual() {
+        let nominal = "time\n1\n2\n3\n";
+        let actual = "time\n1\n3\n2\n";
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(1.0)],
+            preprocessing: Some(vec![ExtractHeaders]),
+            validations: vec![Validation::AssertSortedBy {
+                column: "time".to_string(),
+                ascending: true,
+            }],
+            ..Default::default()
+        };
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(nominal), Cursor::new(actual), &config).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].to_string().contains("not sorted ascending"));
+
+        let (_, _, diffs) =
+            get_diffs_readers(Cursor::new(actual), Cursor::new(nominal), &config).unwrap();
+        assert!(diffs.is_empty());
+    }
 }
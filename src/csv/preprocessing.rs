@@ -1,6 +1,6 @@
 use crate::csv;
 use crate::csv::value::Value;
-use crate::csv::Table;
+use crate::csv::{Column, Side, Table};
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering::Equal;
@@ -17,6 +17,8 @@ pub enum Preprocessor {
     DeleteColumnByName(String),
     /// Sort rows by column with given name. Fails if no headers were extracted or column name is not found, or if any row has no numbers there
     SortByColumnName(String),
+    /// Sort rows lexicographically by the string representation of the column with given name. Fails if no headers were extracted or column name is not found
+    SortByColumnNameAlphabetical(String),
     /// Sort rows by column with given number. Fails if any row has no numbers there or if out of bounds.
     SortByColumnNumber(usize),
     /// Replace all fields in row with given number by a deleted marker
@@ -37,15 +39,88 @@ pub enum Preprocessor {
         /// row number
         row: usize,
     },
+    /// Remove all rows whose value in the key column matches any of the regexes, e.g. known-volatile IDs.
+    /// Unlike the delete preprocessors the rows are dropped entirely, so a row only present on one side is ignored as well.
+    /// Fails if no headers were extracted or the column name is not found
+    IgnoreRowsByKey {
+        /// name of the key column
+        column: String,
+        /// regexes matched against the key values
+        ignore_keys: Vec<String>,
+    },
+    /// Replace all fields of every column whose header matches none of the globs, e.g. `Pos*`, by a deleted marker.
+    /// Columns without header are deleted as well
+    KeepColumnsByName(Vec<String>),
+    /// Swap rows and columns, e.g. for key-value tables - use before `ExtractHeaders`, extracted headers are moved back into the first row.
+    /// Shorter columns are padded with deleted markers
+    Transpose,
+    /// Replace all matches of the regex in every cell, e.g. to mask timestamps or run IDs.
+    /// The cell is parsed again afterwards with the decimal separator of its file, so a number stays a number
+    ReplaceCellRegex {
+        /// regex matched against the text of each cell
+        pattern: String,
+        /// the replacement, capture groups can be referenced like `${1}`
+        replacement: String,
+    },
+    /// Arrange the columns in the order of the given headers, unlisted columns follow in their original order.
+    /// Fails if no headers were extracted or any of the headers is not found
+    ReorderColumns(Vec<String>),
+    /// Replace every quantity `x` in the column with given name by `scale * x + offset`, keeping its unit,
+    /// e.g. to compare a radius with a diameter. Strings are kept as they are.
+    /// Fails if no headers were extracted or column name is not found
+    TransformColumn {
+        /// column with given name
+        column: String,
+        /// factor the quantities are multiplied with - optional, default is 1
+        #[serde(default = "Preprocessor::default_scale")]
+        scale: f64,
+        /// added after scaling - optional, default is 0
+        #[serde(default)]
+        offset: f64,
+        /// the table that is transformed - optional, default is `Actual`
+        #[serde(default = "Preprocessor::default_side")]
+        side: Side,
+    },
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How duplicate headers found by `ExtractHeaders` are handled
+pub enum DuplicateHeaders {
+    /// Keep them and warn, operations by name use the first column with that header
+    #[default]
+    Warn,
+    /// Fail with an error naming the duplicate header
+    Error,
+    /// Rename the second and further occurrences by appending `_2`, `_3`, ...
+    Suffix,
 }
 
 impl Preprocessor {
+    fn default_scale() -> f64 {
+        1.0
+    }
+
+    fn default_side() -> Side {
+        Side::Actual
+    }
+
+    /// Whether the preprocessor is applied to the table of the given side, most are applied to both
+    pub(crate) fn applies_to(&self, side: Side) -> bool {
+        match self {
+            Preprocessor::TransformColumn { side: target, .. } => *target == side,
+            _ => true,
+        }
+    }
+
     pub(crate) fn process(&self, table: &mut Table) -> Result<(), csv::Error> {
         match self {
             Preprocessor::ExtractHeaders => extract_headers(table),
             Preprocessor::DeleteColumnByNumber(id) => delete_column_number(table, *id),
             Preprocessor::DeleteColumnByName(name) => delete_column_name(table, name.as_str()),
             Preprocessor::SortByColumnName(name) => sort_by_column_name(table, name.as_str()),
+            Preprocessor::SortByColumnNameAlphabetical(name) => {
+                sort_by_column_name_alphabetical(table, name.as_str())
+            }
             Preprocessor::SortByColumnNumber(id) => sort_by_column_id(table, *id),
             Preprocessor::DeleteRowByNumber(id) => delete_row_by_number(table, *id),
             Preprocessor::DeleteRowByRegex(regex) => delete_row_by_regex(table, regex),
@@ -55,6 +130,23 @@ impl Preprocessor {
             Preprocessor::DeleteCellByName { column, row } => {
                 delete_cell_by_column_name_and_row_number(table, column, *row)
             }
+            Preprocessor::IgnoreRowsByKey {
+                column,
+                ignore_keys,
+            } => ignore_rows_by_key(table, column, ignore_keys),
+            Preprocessor::KeepColumnsByName(patterns) => keep_columns_by_name(table, patterns),
+            Preprocessor::Transpose => transpose(table),
+            Preprocessor::ReplaceCellRegex {
+                pattern,
+                replacement,
+            } => replace_cell_regex(table, pattern, replacement),
+            Preprocessor::ReorderColumns(headers) => reorder_columns(table, headers),
+            Preprocessor::TransformColumn {
+                column,
+                scale,
+                offset,
+                ..
+            } => transform_column(table, column, *scale, *offset),
         }
     }
 }
@@ -68,6 +160,83 @@ fn delete_row_by_regex(table: &mut Table, regex: &str) -> Result<(), csv::Error>
     Ok(())
 }
 
+fn replace_cell_regex(
+    table: &mut Table,
+    pattern: &str,
+    replacement: &str,
+) -> Result<(), csv::Error> {
+    let regex = regex::Regex::new(pattern)?;
+    for value in table.columns.iter_mut().flat_map(|c| c.rows.iter_mut()) {
+        let text = value.as_str();
+        if regex.is_match(&text) {
+            let replaced = regex.replace_all(&text, replacement).into_owned();
+            *value = Value::from_str(&replaced, &table.decimal_separator);
+        }
+    }
+    Ok(())
+}
+
+fn transform_column(
+    table: &mut Table,
+    column: &str,
+    scale: f64,
+    offset: f64,
+) -> Result<(), csv::Error> {
+    let column = table
+        .columns
+        .iter_mut()
+        .find(|c| c.header.as_deref().unwrap_or_default() == column)
+        .ok_or_else(|| {
+            csv::Error::InvalidAccess(format!(
+                "Transforming column '{column}' requested but column not found."
+            ))
+        })?;
+    for value in column.rows.iter_mut() {
+        if let Value::Quantity(quantity) = value {
+            quantity.value = scale * quantity.value + offset;
+        }
+    }
+    Ok(())
+}
+
+fn ignore_rows_by_key(
+    table: &mut Table,
+    column: &str,
+    ignore_keys: &[String],
+) -> Result<(), csv::Error> {
+    let ignore_keys = ignore_keys
+        .iter()
+        .map(|key| regex::Regex::new(key))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key_column = table
+        .columns
+        .iter()
+        .find(|c| c.header.as_deref().unwrap_or_default() == column)
+        .ok_or_else(|| {
+            csv::Error::InvalidAccess(format!(
+                "Requested ignoring rows by key column '{column}' but column not found."
+            ))
+        })?;
+    let ignored: Vec<bool> = key_column
+        .rows
+        .iter()
+        .map(|v| {
+            let key = v.get_string().unwrap_or_else(|| v.to_string());
+            ignore_keys.iter().any(|r| r.is_match(key.as_str()))
+        })
+        .collect();
+    debug!(
+        "Ignoring {} rows by key",
+        ignored.iter().filter(|i| **i).count()
+    );
+    for col in table.columns.iter_mut() {
+        let mut ignored = ignored.iter();
+        col.rows
+            .retain(|_| !ignored.next().copied().unwrap_or(false));
+    }
+    Ok(())
+}
+
 fn delete_row_by_number(table: &mut Table, id: usize) -> Result<(), csv::Error> {
     if let Some(mut v) = table.rows_mut().nth(id) {
         v.iter_mut().for_each(|v| **v = Value::deleted())
@@ -149,29 +318,59 @@ fn sort_by_column_id(table: &mut Table, id: usize) -> Result<(), csv::Error> {
     Ok(())
 }
 
-fn sort_by_column_name(table: &mut Table, name: &str) -> Result<(), csv::Error> {
-    let sort_master_col = table
+/// The column with the given name and all its values as numbers, fails if any row has no number there
+pub(crate) fn quantities_of_column_by_name(
+    table: &Table,
+    name: &str,
+) -> Result<(usize, Vec<f64>), csv::Error> {
+    let (index, column) = table
         .columns
         .iter()
-        .find(|c| c.header.as_deref().unwrap_or_default() == name)
+        .enumerate()
+        .find(|(_, c)| c.header.as_deref().unwrap_or_default() == name)
         .ok_or_else(|| {
             csv::Error::InvalidAccess(format!(
-                "Requested format sorting by column'{name}' but column not found."
+                "Requested column '{name}' by name but column not found."
             ))
         })?;
-    let col_floats: Result<Vec<_>, csv::Error> = sort_master_col
+    let col_floats: Result<Vec<_>, csv::Error> = column
         .rows
         .iter()
         .map(|v| {
             v.get_quantity().map(|q| q.value).ok_or_else(|| {
                 csv::Error::UnexpectedValue(
                     v.clone(),
-                    "Expected quantity while trying to sort by column name".to_string(),
+                    format!("Expected quantity in column '{name}'"),
                 )
             })
         })
         .collect();
-    let permutation = get_permutation(&col_floats?);
+    Ok((index, col_floats?))
+}
+
+fn sort_by_column_name(table: &mut Table, name: &str) -> Result<(), csv::Error> {
+    let (_, col_floats) = quantities_of_column_by_name(table, name)?;
+    let permutation = get_permutation(&col_floats);
+    apply_permutation(table, permutation);
+    Ok(())
+}
+
+fn sort_by_column_name_alphabetical(table: &mut Table, name: &str) -> Result<(), csv::Error> {
+    let column = table
+        .columns
+        .iter()
+        .find(|c| c.header.as_deref().unwrap_or_default() == name)
+        .ok_or_else(|| {
+            csv::Error::InvalidAccess(format!(
+                "Column name sorting alphabetically by '{name}' requested but column not found."
+            ))
+        })?;
+    let keys: Vec<String> = column
+        .rows
+        .iter()
+        .map(|v| v.as_str().into_owned())
+        .collect();
+    let permutation = permutation::sort_by(&keys, |a, b| a.cmp(b));
     apply_permutation(table, permutation);
     Ok(())
 }
@@ -187,6 +386,46 @@ fn delete_column_name(table: &mut Table, name: &str) -> Result<(), csv::Error> {
     Ok(())
 }
 
+fn keep_columns_by_name(table: &mut Table, patterns: &[String]) -> Result<(), csv::Error> {
+    let patterns = patterns
+        .iter()
+        .map(|p| glob::Pattern::new(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    table
+        .columns
+        .iter_mut()
+        .filter(|col| {
+            !col.header
+                .as_deref()
+                .is_some_and(|header| patterns.iter().any(|p| p.matches(header)))
+        })
+        .for_each(|col| col.delete_contents());
+    Ok(())
+}
+
+fn reorder_columns(table: &mut Table, headers: &[String]) -> Result<(), csv::Error> {
+    let mut columns = std::mem::take(&mut table.columns);
+    let mut ordered = Vec::with_capacity(columns.len());
+    for header in headers {
+        match columns
+            .iter()
+            .position(|c| c.header.as_deref() == Some(header.as_str()))
+        {
+            Some(position) => ordered.push(columns.remove(position)),
+            None => {
+                ordered.append(&mut columns);
+                table.columns = ordered;
+                return Err(csv::Error::InvalidAccess(format!(
+                    "Reordering columns by '{header}' requested but column not found."
+                )));
+            }
+        }
+    }
+    ordered.append(&mut columns);
+    table.columns = ordered;
+    Ok(())
+}
+
 fn delete_column_number(table: &mut Table, id: usize) -> Result<(), csv::Error> {
     if let Some(col) = table.columns.get_mut(id) {
         col.delete_contents();
@@ -194,6 +433,74 @@ fn delete_column_number(table: &mut Table, id: usize) -> Result<(), csv::Error>
     Ok(())
 }
 
+/// Detects duplicate headers after extraction and handles them as configured
+pub(crate) fn handle_duplicate_headers(
+    table: &mut Table,
+    handling: DuplicateHeaders,
+) -> Result<(), csv::Error> {
+    let mut seen: Vec<(String, usize)> = Vec::new();
+    for col in table.columns.iter_mut() {
+        let Some(header) = col.header.as_ref() else {
+            continue;
+        };
+        let occurrence = match seen.iter_mut().find(|(h, _)| h == header) {
+            Some((_, count)) => {
+                *count += 1;
+                *count
+            }
+            None => {
+                seen.push((header.clone(), 1));
+                continue;
+            }
+        };
+        match handling {
+            DuplicateHeaders::Warn => {
+                warn!("Duplicate header '{header}', operations by name will use the first column")
+            }
+            DuplicateHeaders::Error => return Err(csv::Error::DuplicateHeader(header.clone())),
+            DuplicateHeaders::Suffix => {
+                let renamed = format!("{header}_{occurrence}");
+                debug!("Renaming duplicate header '{header}' to '{renamed}'");
+                col.header = Some(renamed);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn transpose(table: &mut Table) -> Result<(), csv::Error> {
+    debug!("Transposing table...");
+    let columns: Vec<Vec<Value>> = std::mem::take(&mut table.columns)
+        .into_iter()
+        .map(|col| {
+            col.header
+                .map(Value::String)
+                .into_iter()
+                .chain(col.rows)
+                .collect()
+        })
+        .collect();
+    let row_count = columns.iter().map(Vec::len).max().unwrap_or_default();
+    let mut transposed: Vec<Column> = (0..row_count)
+        .map(|_| Column {
+            header: None,
+            rows: Vec::with_capacity(columns.len()),
+        })
+        .collect();
+    for column in columns {
+        let padding = row_count - column.len();
+        for (new_column, value) in transposed.iter_mut().zip(
+            column
+                .into_iter()
+                .chain(std::iter::repeat_n(Value::deleted(), padding)),
+        ) {
+            new_column.rows.push(value);
+        }
+    }
+    table.columns = transposed;
+    Ok(())
+}
+
 fn extract_headers(table: &mut Table) -> Result<(), csv::Error> {
     debug!("Extracting headers...");
     let can_extract = table
@@ -219,7 +526,7 @@ fn extract_headers(table: &mut Table) -> Result<(), csv::Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::csv::{Column, Delimiters, Error};
+    use crate::csv::{Delimiters, Error};
     use std::fs::File;
 
     fn setup_table(delimiters: Option<Delimiters>) -> Table {
@@ -304,6 +611,179 @@ mod tests {
             .all(|v| *v == Value::deleted()));
     }
 
+    #[test]
+    fn reorder_columns_arranges_both_sides_canonically() {
+        use crate::csv::{get_diffs_readers, CSVCompareConfig, Mode};
+        let nominal = "Id;Size;Weight;Color\n1;2.0;3.0;red\n";
+        let actual = "Weight;Color;Id;Size\n3.0;red;1;2.0\n";
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(vec![
+                Preprocessor::ExtractHeaders,
+                Preprocessor::ReorderColumns(vec!["Size".to_owned(), "Id".to_owned()]),
+            ]),
+            ..Default::default()
+        };
+        let (nominal_table, actual_table, diffs) = get_diffs_readers(
+            std::io::Cursor::new(nominal),
+            std::io::Cursor::new(actual),
+            &config,
+        )
+        .unwrap();
+        assert!(diffs.is_empty());
+        assert_eq!(
+            nominal_table.headers(),
+            vec![Some("Size"), Some("Id"), Some("Weight"), Some("Color")]
+        );
+        assert_eq!(
+            actual_table.headers(),
+            vec![Some("Size"), Some("Id"), Some("Weight"), Some("Color")]
+        );
+
+        let mut table =
+            Table::from_reader(std::io::Cursor::new(nominal), &Delimiters::default()).unwrap();
+        extract_headers(&mut table).unwrap();
+        assert!(matches!(
+            reorder_columns(&mut table, &["Id".to_owned(), "Volume".to_owned()]),
+            Err(Error::InvalidAccess(_))
+        ));
+        assert_eq!(table.columns.len(), 4);
+    }
+
+    #[test]
+    fn keep_columns_by_name_deletes_all_others() {
+        let mut table = Table::from_reader(
+            File::open("tests/csv/data/Annotations.csv").unwrap(),
+            &Delimiters::default(),
+        )
+        .unwrap();
+        extract_headers(&mut table).unwrap();
+        keep_columns_by_name(&mut table, &["Pos*".to_owned(), "Type".to_owned()]).unwrap();
+        let kept: Vec<_> = table
+            .columns
+            .iter()
+            .filter_map(|c| c.header.as_deref())
+            .filter(|h| *h != "DELETED")
+            .collect();
+        assert_eq!(
+            kept,
+            vec!["Pos. x [mm]", "Pos. y [mm]", "Pos. z [mm]", "Type"]
+        );
+        assert!(table
+            .columns
+            .iter()
+            .filter(|c| c.header.as_deref() == Some("DELETED"))
+            .all(|c| c.rows.iter().all(|v| *v == Value::deleted())));
+
+        let mut table = setup_table(None);
+        keep_columns_by_name(&mut table, &["*".to_owned()]).unwrap();
+        assert!(table
+            .columns
+            .iter()
+            .all(|c| c.header.as_deref() == Some("DELETED")));
+        assert!(matches!(
+            keep_columns_by_name(&mut table, &["[".to_owned()]),
+            Err(Error::InvalidColumnPattern(_))
+        ));
+    }
+
+    #[test]
+    fn transpose_round_trip() {
+        let mut table = Table::from_reader(
+            File::open(
+                "tests/integ/data/display_of_status_message_in_cm_tables/expected/Volume1.csv",
+            )
+            .unwrap(),
+            &Delimiters::default(),
+        )
+        .unwrap();
+        let original: Vec<Vec<Value>> = table.columns.iter().map(|c| c.rows.clone()).collect();
+        transpose(&mut table).unwrap();
+        assert_eq!(table.columns.len(), original[0].len());
+        assert_eq!(
+            table.columns[1].rows,
+            vec![original[0][1].clone(), original[1][1].clone()]
+        );
+        transpose(&mut table).unwrap();
+        let round_trip: Vec<Vec<Value>> = table.columns.iter().map(|c| c.rows.clone()).collect();
+        assert_eq!(round_trip, original);
+
+        let mut ragged = Table {
+            columns: vec![
+                Column {
+                    header: Some("Key".to_owned()),
+                    rows: vec![Value::from_str("a", &None), Value::from_str("b", &None)],
+                },
+                Column {
+                    header: None,
+                    rows: vec![Value::from_str("1.5 mm", &None)],
+                },
+            ],
+            decimal_separator: None,
+        };
+        transpose(&mut ragged).unwrap();
+        assert_eq!(ragged.columns.len(), 3);
+        assert_eq!(
+            ragged.columns[0].rows,
+            vec![
+                Value::from_str("Key", &None),
+                Value::from_str("1.5 mm", &None)
+            ]
+        );
+        assert!(ragged.columns[0].rows[1].get_quantity().is_some());
+        assert_eq!(ragged.columns[2].rows[1], Value::deleted());
+    }
+
+    #[test]
+    fn replace_cell_regex_masks_volatile_ids() {
+        use crate::csv::{compare_tables, CSVCompareConfig, Mode};
+        let parse = |content: &str| {
+            Table::from_reader(std::io::Cursor::new(content), &Delimiters::default()).unwrap()
+        };
+        let mut nominal = parse("RUN-1234;2024-01-01 12:00;1.5\nRUN-1234;2024-01-01 12:01;2.5\n");
+        let mut actual = parse("RUN-9876;2024-03-07 08:30;1.5\nRUN-9876;2024-03-07 08:31;2.5\n");
+        let config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            ..Default::default()
+        };
+        assert_eq!(compare_tables(&nominal, &actual, &config).unwrap().len(), 4);
+
+        for table in [&mut nominal, &mut actual] {
+            replace_cell_regex(table, r"^RUN-\d+$", "RUN-ID").unwrap();
+            replace_cell_regex(table, r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}", "DATE").unwrap();
+            replace_cell_regex(table, r"^(\d)\.5$", "${1}.25").unwrap();
+        }
+        assert!(compare_tables(&nominal, &actual, &config)
+            .unwrap()
+            .is_empty());
+        assert_eq!(nominal.columns[1].rows[0], Value::from_str("DATE", &None));
+        assert_eq!(
+            nominal.columns[2].rows[1].get_quantity().map(|q| q.value),
+            Some(2.25)
+        );
+
+        assert!(matches!(
+            replace_cell_regex(&mut nominal, "(", ""),
+            Err(Error::RegexCompilationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn replace_cell_regex_keeps_comma_decimal_separator() {
+        let delimiters = Delimiters {
+            field_delimiter: Some(';'),
+            decimal_separator: Some(','),
+        };
+        let mut table =
+            Table::from_reader(std::io::Cursor::new("1,5 mm (run 7);2,5\n"), &delimiters).unwrap();
+        assert!(table.columns[0].rows[0].get_quantity().is_none());
+
+        replace_cell_regex(&mut table, r" \(run \d+\)$", "").unwrap();
+        let quantity = table.columns[0].rows[0].get_quantity().unwrap();
+        assert_eq!(quantity.value, 1.5);
+        assert_eq!(quantity.unit.as_deref(), Some("mm"));
+    }
+
     #[test]
     fn test_delete_row_by_id() {
         let mut table = setup_table(None);
@@ -358,6 +838,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transform_column_scales_actual_quantities() {
+        use crate::csv::{get_diffs_readers, CSVCompareConfig, Mode};
+        let nominal = "Name;Diameter\nsmall;2.0 mm\nlarge;9.0 mm\nunknown;n/a\n";
+        let actual = "Name;Diameter\nsmall;1.0 mm\nlarge;4.5 mm\nunknown;n/a\n";
+        let preprocessing: Vec<Preprocessor> = serde_yaml::from_str(
+            "- ExtractHeaders\n- !TransformColumn\n  column: Diameter\n  scale: 2.0\n",
+        )
+        .unwrap();
+        let mut config = CSVCompareConfig {
+            comparison_modes: vec![Mode::Absolute(0.0)],
+            preprocessing: Some(preprocessing),
+            ..Default::default()
+        };
+        let (_, actual_table, diffs) = get_diffs_readers(
+            std::io::Cursor::new(nominal),
+            std::io::Cursor::new(actual),
+            &config,
+        )
+        .unwrap();
+        assert!(diffs.is_empty());
+        assert_eq!(
+            actual_table.columns[1].rows[1],
+            Value::from_str("9.0 mm", &None)
+        );
+
+        config.preprocessing = Some(vec![
+            Preprocessor::ExtractHeaders,
+            Preprocessor::TransformColumn {
+                column: "Diameter".to_string(),
+                scale: 0.5,
+                offset: 1.0,
+                side: Side::Nominal,
+            },
+        ]);
+        let (nominal_table, _, diffs) = get_diffs_readers(
+            std::io::Cursor::new(nominal),
+            std::io::Cursor::new(actual),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(
+            nominal_table.columns[1].rows[0],
+            Value::from_str("2.0 mm", &None)
+        );
+
+        let mut table =
+            Table::from_reader(std::io::Cursor::new(nominal), &Delimiters::default()).unwrap();
+        extract_headers(&mut table).unwrap();
+        assert!(matches!(
+            transform_column(&mut table, "Radius", 2.0, 0.0),
+            Err(Error::InvalidAccess(_))
+        ));
+    }
+
+    #[test]
+    fn test_sort_by_name_alphabetical() {
+        let mut table = Table::from_reader(
+            std::io::Cursor::new("id,name\n3,beta\n1,alpha\n2,gamma\n4,Alpha\n"),
+            &Delimiters::default(),
+        )
+        .unwrap();
+        extract_headers(&mut table).unwrap();
+        sort_by_column_name_alphabetical(&mut table, "name").unwrap();
+        let names: Vec<_> = table.columns[1]
+            .rows
+            .iter()
+            .map(|v| v.as_str().into_owned())
+            .collect();
+        assert_eq!(names, vec!["Alpha", "alpha", "beta", "gamma"]);
+        let ids: Vec<_> = table.columns[0]
+            .rows
+            .iter()
+            .map(|v| v.get_quantity().unwrap().value)
+            .collect();
+        assert_eq!(ids, vec![4.0, 1.0, 3.0, 2.0]);
+
+        assert!(matches!(
+            sort_by_column_name_alphabetical(&mut table, "missing"),
+            Err(Error::InvalidAccess(_))
+        ));
+    }
+
     #[test]
     fn test_sort_by_id() {
         let mut table = setup_table(None);
@@ -386,6 +950,7 @@ mod tests {
         };
         let mut table = Table {
             columns: vec![column],
+            decimal_separator: None,
         };
         let order_by_name = sort_by_column_name(&mut table, "Field");
         assert!(matches!(
@@ -503,4 +1068,39 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn duplicate_headers_are_handled_as_configured() {
+        let content = "Value,Name,Value,Value\n1,a,2,3\n";
+        let headed_table = || {
+            let mut table =
+                Table::from_reader(std::io::Cursor::new(content), &Delimiters::default()).unwrap();
+            extract_headers(&mut table).unwrap();
+            table
+        };
+        let headers = |table: &Table| -> Vec<String> {
+            table
+                .columns
+                .iter()
+                .map(|c| c.header.clone().unwrap())
+                .collect()
+        };
+
+        let mut table = headed_table();
+        handle_duplicate_headers(&mut table, DuplicateHeaders::Warn).unwrap();
+        assert_eq!(headers(&table), vec!["Value", "Name", "Value", "Value"]);
+
+        let mut table = headed_table();
+        assert!(matches!(
+            handle_duplicate_headers(&mut table, DuplicateHeaders::Error),
+            Err(Error::DuplicateHeader(h)) if h == "Value"
+        ));
+
+        let mut table = headed_table();
+        handle_duplicate_headers(&mut table, DuplicateHeaders::Suffix).unwrap();
+        assert_eq!(headers(&table), vec!["Value", "Name", "Value_2", "Value_3"]);
+        delete_column_name(&mut table, "Value_2").unwrap();
+        assert_eq!(table.columns[2].header.as_deref(), Some("DELETED"));
+        assert_eq!(table.columns[3].header.as_deref(), Some("Value_3"));
+    }
 }
@@ -14,6 +14,8 @@ fn guess_format_from_line(
     if field_separator.is_none() {
         if line.find(';').is_some() {
             field_separator = Some(';');
+        } else if line.find('\t').is_some() {
+            field_separator = Some('\t');
         } else {
             let field_sep_regex = Regex::new(r"\w([,|])[\W\w]")?;
             let capture = field_sep_regex.captures_iter(line).next();
@@ -134,6 +136,23 @@ mod format_guessing_tests {
         assert_eq!(format, (Some(','), Some('.')));
     }
 
+    #[test]
+    fn format_detection_tab_separated() {
+        let format = guess_format_from_line("Pore 1\t1,5\t2,25\t0,003", None).unwrap();
+        assert_eq!(format, (Some('\t'), Some(',')));
+
+        let format =
+            guess_format_from_reader(&mut File::open("tests/csv/data/tab_separated.tsv").unwrap())
+                .unwrap();
+        assert_eq!(
+            format,
+            Delimiters {
+                field_delimiter: Some('\t'),
+                decimal_separator: Some(',')
+            }
+        );
+    }
+
     #[test]
     fn format_detection_from_file() {
         let format =
@@ -161,23 +161,15 @@ impl RowBuffer {
         self.0.into_iter()
     }
 
+    /// Drops the trailing rows without content, so a final newline and trailing blank lines
+    /// yield the same rows as a file ending directly after its last field
     fn trim_end(&mut self) {
-        'PopEmpty: loop {
-            if let Some(back) = self.0.last() {
-                if back.len() <= 1 {
-                    if let Some(first) = back.first() {
-                        if first.as_str().is_empty() {
-                            self.0.pop();
-                        } else {
-                            break 'PopEmpty;
-                        }
-                    } else {
-                        self.0.pop();
-                    }
-                } else {
-                    break 'PopEmpty;
-                }
-            }
+        while self
+            .0
+            .last()
+            .is_some_and(|row| row.len() <= 1 && row.iter().all(|v| v.as_str().is_empty()))
+        {
+            self.0.pop();
         }
     }
 }
@@ -260,6 +252,11 @@ impl<R: Read + Seek> Parser<R> {
         Some(Parser { reader, delimiters })
     }
 
+    /// The delimiters the file is parsed with, either given or guessed
+    pub(crate) fn delimiters(&self) -> &Delimiters {
+        &self.delimiters
+    }
+
     pub(crate) fn parse_to_rows(&mut self) -> Result<std::vec::IntoIter<Vec<Value>>, Error> {
         debug!(
             "Generating tokens with field delimiter: {:?}",
@@ -512,4 +509,42 @@ Nominal object,"""Scene""=>""Mesh 1""""#;
             assert_eq!(line.len(), 2);
         }
     }
+
+    fn parse_rows(input: &str) -> Vec<Vec<Value>> {
+        Parser::new(
+            Cursor::new(input),
+            Delimiters {
+                field_delimiter: Some(','),
+                decimal_separator: None,
+            },
+        )
+        .unwrap()
+        .parse_to_rows()
+        .unwrap()
+        .collect()
+    }
+
+    #[test]
+    fn final_newline_is_optional() {
+        for without_newline in [
+            "a\nb",
+            "a,1\nb,2",
+            "a,\"x\"\nb,\"y\"",
+            "a,1\nb,",
+            "a,1\nb,2 ",
+        ] {
+            let rows = parse_rows(without_newline);
+            assert_eq!(rows.len(), 2);
+            for ending in ["\n", "\r\n", "\n\n", "\n \n"] {
+                assert_eq!(parse_rows(&format!("{without_newline}{ending}")), rows);
+            }
+        }
+    }
+
+    #[test]
+    fn blank_input_has_no_rows() {
+        for input in ["", "\n", "\r\n\n"] {
+            assert!(parse_rows(input).is_empty());
+        }
+    }
 }
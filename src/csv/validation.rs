@@ -0,0 +1,196 @@
+use crate::csv;
+use crate::csv::preprocessing::quantities_of_column_by_name;
+use crate::csv::{DiffType, Position, Table};
+use regex::Regex;
+use schemars_derive::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone)]
+/// Validations of the actual table, independent of the nominal
+pub enum Validation {
+    /// Assert the column with the given name is sorted. Fails if no headers were extracted or column name is not found, or if any row has no numbers there
+    AssertSortedBy {
+        /// name of the column
+        column: String,
+        /// sorted ascending if true, descending if false
+        ascending: bool,
+    },
+    /// Assert every cell of the column with the given name matches the regex, e.g. `^[A-Z]{3}\d+$`.
+    /// Fails if no headers were extracted or the column name is not found, deleted cells are skipped
+    AssertColumnMatchesRegex {
+        /// name of the column
+        column: String,
+        /// the regex each cell must match
+        pattern: String,
+    },
+}
+
+impl Validation {
+    pub(crate) fn validate(&self, table: &Table) -> Result<Vec<DiffType>, csv::Error> {
+        match self {
+            Validation::AssertSortedBy { column, ascending } => {
+                assert_sorted_by(table, column, *ascending)
+            }
+            Validation::AssertColumnMatchesRegex { column, pattern } => {
+                assert_column_matches_regex(table, column, pattern)
+            }
+        }
+    }
+}
+
+fn assert_sorted_by(
+    table: &Table,
+    column: &str,
+    ascending: bool,
+) -> Result<Vec<DiffType>, csv::Error> {
+    let (col, values) = quantities_of_column_by_name(table, column)?;
+    let first_unsorted = values.windows(2).position(|pair| {
+        if ascending {
+            pair[0] > pair[1]
+        } else {
+            pair[0] < pair[1]
+        }
+    });
+    Ok(first_unsorted
+        .map(|row| DiffType::UnsortedColumn {
+            column: column.to_owned(),
+            ascending,
+            previous: values[row],
+            found: values[row + 1],
+            position: Position { row: row + 1, col },
+        })
+        .into_iter()
+        .collect())
+}
+
+fn assert_column_matches_regex(
+    table: &Table,
+    column: &str,
+    pattern: &str,
+) -> Result<Vec<DiffType>, csv::Error> {
+    let regex = Regex::new(pattern)?;
+    let (col, values) = table
+        .columns
+        .iter()
+        .enumerate()
+        .find(|(_, c)| c.header.as_deref() == Some(column))
+        .ok_or_else(|| {
+            csv::Error::InvalidAccess(format!(
+                "Requested column '{column}' by name but column not found."
+            ))
+        })?;
+    Ok(values
+        .rows
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| !value.is_deleted() && !regex.is_match(&value.as_str()))
+        .map(|(row, value)| DiffType::RegexMismatch {
+            column: column.to_owned(),
+            pattern: pattern.to_owned(),
+            value: value.as_str().into_owned(),
+            position: Position { row, col },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv::Preprocessor;
+    use std::io::Cursor;
+
+    fn headed_table(content: &str) -> Table {
+        let mut table =
+            Table::from_reader(Cursor::new(content), &csv::Delimiters::default()).unwrap();
+        Preprocessor::ExtractHeaders.process(&mut table).unwrap();
+        table
+    }
+
+    #[test]
+    fn sorted_column_passes() {
+        let table = headed_table("id,time\nb,1.0\na,2.5\nc,2.5\nd,10\n");
+        let validation = Validation::AssertSortedBy {
+            column: "time".to_string(),
+            ascending: true,
+        };
+        assert!(validation.validate(&table).unwrap().is_empty());
+
+        let table = headed_table("id,time\na,3\nb,2\nc,-1\n");
+        let validation = Validation::AssertSortedBy {
+            column: "time".to_string(),
+            ascending: false,
+        };
+        assert!(validation.validate(&table).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unsorted_column_reports_first_out_of_order_row() {
+        let table = headed_table("id,time\na,1\nb,3\nc,2\nd,0\n");
+        let validation = Validation::AssertSortedBy {
+            column: "time".to_string(),
+            ascending: true,
+        };
+        let diffs = validation.validate(&table).unwrap();
+        assert_eq!(diffs.len(), 1);
+        if let DiffType::UnsortedColumn {
+            position,
+            previous,
+            found,
+            ..
+        } = &diffs[0]
+        {
+            assert_eq!(position.row, 2);
+            assert_eq!(position.col, 1);
+            assert_eq!(*previous, 3.0);
+            assert_eq!(*found, 2.0);
+        } else {
+            unreachable!();
+        }
+
+        let missing = Validation::AssertSortedBy {
+            column: "missing".to_string(),
+            ascending: true,
+        };
+        assert!(missing.validate(&table).is_err());
+    }
+
+    #[test]
+    fn conforming_column_matches_regex() {
+        let table = headed_table("id,time\nABC1,1\nXYZ42,2\n");
+        let validation = Validation::AssertColumnMatchesRegex {
+            column: "id".to_string(),
+            pattern: r"^[A-Z]{3}\d+$".to_string(),
+        };
+        assert!(validation.validate(&table).unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_cells_are_reported() {
+        let table = headed_table("id,time\nABC1,1\nab2,2\nXYZ,3\n");
+        let validation = Validation::AssertColumnMatchesRegex {
+            column: "id".to_string(),
+            pattern: r"^[A-Z]{3}\d+$".to_string(),
+        };
+        let diffs = validation.validate(&table).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert!(matches!(
+            &diffs[0],
+            DiffType::RegexMismatch { value, position, .. } if value == "ab2" && position.row == 1 && position.col == 0
+        ));
+        assert!(matches!(
+            &diffs[1],
+            DiffType::RegexMismatch { value, position, .. } if value == "XYZ" && position.row == 2
+        ));
+
+        let missing = Validation::AssertColumnMatchesRegex {
+            column: "missing".to_string(),
+            pattern: ".*".to_string(),
+        };
+        assert!(missing.validate(&table).is_err());
+        let invalid = Validation::AssertColumnMatchesRegex {
+            column: "id".to_string(),
+            pattern: "(".to_string(),
+        };
+        assert!(invalid.validate(&table).is_err());
+    }
+}
@@ -49,6 +49,58 @@ fn next_down(val: f64) -> f64 {
     f64::from_bits(next_bits)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+}
+
+/// The dimension of a unit and its factor to the SI base unit of that dimension
+fn unit_factor(unit: &str) -> Option<(Dimension, f64)> {
+    let length = |unit: &str| match unit {
+        "nm" => Some(1e-9),
+        "um" | "µm" | "μm" => Some(1e-6),
+        "mm" => Some(1e-3),
+        "cm" => Some(1e-2),
+        "m" => Some(1.0),
+        "km" => Some(1e3),
+        _ => None,
+    };
+    let power = |unit: &str, suffixes: [&str; 2]| {
+        suffixes
+            .iter()
+            .find_map(|suffix| unit.strip_suffix(suffix))
+            .and_then(length)
+    };
+    if let Some(factor) = length(unit) {
+        return Some((Dimension::Length, factor));
+    }
+    if let Some(factor) = power(unit, ["²", "^2"]) {
+        return Some((Dimension::Area, factor.powi(2)));
+    }
+    if let Some(factor) = power(unit, ["³", "^3"]) {
+        return Some((Dimension::Volume, factor.powi(3)));
+    }
+    match unit {
+        "ng" => Some((Dimension::Mass, 1e-12)),
+        "ug" | "µg" | "μg" => Some((Dimension::Mass, 1e-9)),
+        "mg" => Some((Dimension::Mass, 1e-6)),
+        "g" => Some((Dimension::Mass, 1e-3)),
+        "kg" => Some((Dimension::Mass, 1.0)),
+        "t" => Some((Dimension::Mass, 1e3)),
+        "ns" => Some((Dimension::Time, 1e-9)),
+        "us" | "µs" | "μs" => Some((Dimension::Time, 1e-6)),
+        "ms" => Some((Dimension::Time, 1e-3)),
+        "s" => Some((Dimension::Time, 1.0)),
+        "min" => Some((Dimension::Time, 60.0)),
+        "h" => Some((Dimension::Time, 3600.0)),
+        _ => None,
+    }
+}
+
 impl Quantity {
     #[cfg(test)]
     pub(crate) fn new(value: f64, unit: Option<&str>) -> Self {
@@ -58,6 +110,19 @@ impl Quantity {
         }
     }
 
+    /// Converts the quantity into the given unit, `None` if the units are not convertible into each other
+    pub(crate) fn converted_to(&self, unit: Option<&str>) -> Option<Quantity> {
+        if self.unit.as_deref() == unit {
+            return Some(self.clone());
+        }
+        let (own_dimension, own_factor) = unit_factor(self.unit.as_deref()?)?;
+        let (dimension, factor) = unit_factor(unit?)?;
+        (own_dimension == dimension).then(|| Quantity {
+            value: self.value * own_factor / factor,
+            unit: unit.map(str::to_owned),
+        })
+    }
+
     /// This avoids the issue of `(a - b) > d` for `b = a + d` with small `d`
     pub(crate) fn minimal_diff(&self, rhs: &Quantity) -> f64 {
         let min = self.value.min(rhs.value);
@@ -103,6 +168,11 @@ impl Value {
         Value::from_str("DELETED", &None)
     }
 
+    /// Whether the value is the marker set by the deleting preprocessors
+    pub fn is_deleted(&self) -> bool {
+        matches!(self, Value::String(s) if s == "DELETED")
+    }
+
     fn get_numerical_value(field_split: &[&str]) -> Option<f64> {
         if field_split.len() == 1 || field_split.len() == 2 {
             return field_split.first().and_then(|s| s.parse::<f64>().ok());
@@ -178,4 +248,32 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn unit_conversion() {
+        let converted = Quantity::new(10.0, Some("mm"))
+            .converted_to(Some("cm"))
+            .unwrap();
+        assert_eq!(converted, Quantity::new(1.0, Some("cm")));
+        let converted = Quantity::new(2.0, Some("cm²"))
+            .converted_to(Some("mm^2"))
+            .unwrap();
+        assert!((converted.value - 200.0).abs() < 1e-9);
+        let converted = Quantity::new(1500.0, Some("nm"))
+            .converted_to(Some("µm"))
+            .unwrap();
+        assert!((converted.value - 1.5).abs() < 1e-12);
+
+        assert!(Quantity::new(1.0, Some("mm"))
+            .converted_to(Some("s"))
+            .is_none());
+        assert!(Quantity::new(1.0, Some("mm"))
+            .converted_to(Some("mm²"))
+            .is_none());
+        assert!(Quantity::new(1.0, Some("mm")).converted_to(None).is_none());
+        assert!(Quantity::new(1.0, Some("px"))
+            .converted_to(Some("mm"))
+            .is_none());
+        assert!(Quantity::new(1.0, None).converted_to(None).is_some());
+    }
 }
@@ -2,15 +2,174 @@ use crate::report::{DiffDetail, Difference};
 use crate::Error;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use tracing::{error, info};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+/// How the standard output of the external checker is interpreted
+pub enum ExternalOutputFormat {
+    /// Only the exit code decides, the output is shown as is
+    #[default]
+    Text,
+    /// The output is a JSON list of differences like `[{"message": "...", "severity": "warning", "location": "page 2"}]`,
+    /// `severity` is `error` (default) or `warning` and `location` is optional. A failing exit code is still an error
+    Json,
+}
+
+/// A difference in the JSON output of an external checker
+#[derive(Debug, Deserialize)]
+struct Finding {
+    message: String,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default)]
+    location: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Default)]
 pub struct ExternalConfig {
     /// The executable to call - will be started like: `#executable #(#extra_params)* #nominal #actual`
     executable: String,
     /// Extra parameters to pass
     extra_params: Vec<String>,
+    /// How often a failed invocation is retried before it is reported - optional, default is 0
+    retries: Option<u32>,
+    /// Seconds to wait between the attempts - optional, default is 0
+    retry_delay_secs: Option<u64>,
+    /// Exit codes that are retried, the executable failing to start is always retried - optional, default is none
+    retry_exit_codes: Option<Vec<i32>>,
+    /// Seconds after which a running executable is killed and the attempt counts as failed - optional, default is no timeout
+    timeout_secs: Option<u64>,
+    /// Environment variables set for the executable in addition to the inherited ones - optional
+    env: Option<HashMap<String, String>>,
+    /// The working directory of the executable - optional, default is the current one.
+    /// A relative `executable` path like `./tool` or `bin/tool` resolves against it, a bare name is still looked up in `PATH`
+    working_dir: Option<PathBuf>,
+    /// How the standard output is interpreted, `Json` turns each listed difference into a row of the report - optional, default is `Text`
+    #[serde(default)]
+    output_format: ExternalOutputFormat,
+}
+
+impl ExternalConfig {
+    fn is_retryable(&self, output: &std::io::Result<Output>) -> bool {
+        match output {
+            Err(_) => true,
+            Ok(output) => output.status.code().is_some_and(|code| {
+                self.retry_exit_codes
+                    .as_ref()
+                    .is_some_and(|codes| codes.contains(&code))
+            }),
+        }
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Runs the executable once, killing it if it exceeds the configured timeout
+fn run_once(nominal: &Path, actual: &Path, config: &ExternalConfig) -> std::io::Result<Output> {
+    let mut command = match &config.working_dir {
+        Some(working_dir) => {
+            let executable = Path::new(&config.executable);
+            let executable = if executable.is_relative() && executable.components().count() > 1 {
+                working_dir.join(executable)
+            } else {
+                executable.to_path_buf()
+            };
+            let mut command = std::process::Command::new(executable);
+            // the compared files stay relative to our working directory, not the one of the executable
+            command
+                .current_dir(working_dir)
+                .args(&config.extra_params)
+                .arg(std::path::absolute(nominal)?)
+                .arg(std::path::absolute(actual)?);
+            command
+        }
+        None => {
+            let mut command = std::process::Command::new(&config.executable);
+            command.args(&config.extra_params).arg(nominal).arg(actual);
+            command
+        }
+    };
+    if let Some(env) = &config.env {
+        command.envs(env);
+    }
+    let Some(timeout_secs) = config.timeout_secs else {
+        return command.output();
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // the pipes are drained while waiting, a full pipe would block the child otherwise
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Err(std::io::Error::new(
+                ErrorKind::TimedOut,
+                format!("timed out after {timeout_secs} s, the process was killed"),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Runs the executable until it succeeds, fails without being retryable or the retries are exhausted
+fn run_with_retries<P: AsRef<Path>>(
+    nominal: P,
+    actual: P,
+    config: &ExternalConfig,
+) -> (std::io::Result<Output>, u32) {
+    let max_attempts = config.retries.unwrap_or(0).saturating_add(1);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let output = run_once(nominal.as_ref(), actual.as_ref(), config);
+        let succeeded = output.as_ref().is_ok_and(|o| o.status.success());
+        if succeeded || attempts >= max_attempts || !config.is_retryable(&output) {
+            return (output, attempts);
+        }
+        warn!(
+            "External checker attempt {attempts} of {max_attempts} failed for file {}, retrying",
+            nominal.as_ref().to_string_lossy()
+        );
+        if let Some(delay) = config.retry_delay_secs {
+            std::thread::sleep(Duration::from_secs(delay));
+        }
+    }
 }
 
 pub(crate) fn compare_files<P: AsRef<Path>>(
@@ -20,30 +179,68 @@ pub(crate) fn compare_files<P: AsRef<Path>>(
 ) -> Result<Difference, Error> {
     let mut diff = Difference::new_for_file(&nominal, &actual);
     let compared_file_name = nominal.as_ref().to_string_lossy().into_owned();
-    let output = std::process::Command::new(&config.executable)
-        .args(&config.extra_params)
-        .arg(nominal.as_ref())
-        .arg(actual.as_ref())
-        .output();
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        info!("External stdout: {}", stdout.as_str());
-        info!("External stderr: {}", stderr.as_str());
-        if !output.status.success() {
-            let message = format!("External checker denied file {}", &compared_file_name);
-            error!("{}", &message);
-            diff.push_detail(DiffDetail::External { stdout, stderr });
-            diff.error();
-        };
-    } else {
-        let error_message = format!(
-            "External checker execution failed for file {}",
-            &compared_file_name
-        );
-        error!("{}", error_message);
-        diff.push_detail(DiffDetail::Error(error_message));
-        diff.error();
+    let (output, attempts) = run_with_retries(nominal.as_ref(), actual.as_ref(), config);
+    if attempts > 1 {
+        info!("External checker needed {attempts} attempts for file {compared_file_name}");
+    }
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            info!("External stdout: {}", stdout.as_str());
+            info!("External stderr: {}", stderr.as_str());
+            let findings = match config.output_format {
+                ExternalOutputFormat::Text => None,
+                ExternalOutputFormat::Json => match serde_json::from_str::<Vec<Finding>>(&stdout) {
+                    Ok(findings) => Some(findings),
+                    Err(e) => {
+                        let message = format!(
+                            "Could not parse the JSON output of the external checker for file {compared_file_name}, falling back to text (error: {e})"
+                        );
+                        warn!("{}", &message);
+                        diff.push_detail(DiffDetail::Error(message));
+                        diff.warn();
+                        None
+                    }
+                },
+            };
+            let mut reported_error = false;
+            for finding in findings.into_iter().flatten() {
+                let warning = finding.severity == Severity::Warning;
+                if warning {
+                    warn!("External checker warning: {}", &finding.message);
+                    diff.warn();
+                } else {
+                    error!("External checker error: {}", &finding.message);
+                    diff.error();
+                    reported_error = true;
+                }
+                diff.push_detail(DiffDetail::ExternalFinding {
+                    message: finding.message,
+                    location: finding.location,
+                    warning,
+                });
+            }
+            if !output.status.success() && !reported_error {
+                let message = format!("External checker denied file {}", &compared_file_name);
+                error!("{}", &message);
+                diff.push_detail(DiffDetail::External {
+                    stdout,
+                    stderr,
+                    attempts,
+                });
+                diff.error();
+            };
+        }
+        Err(e) => {
+            let error_message = format!(
+                "External checker execution failed for file {} after {attempts} attempts: {e}",
+                &compared_file_name
+            );
+            error!("{}", error_message);
+            diff.push_detail(DiffDetail::Error(error_message));
+            diff.fail();
+        }
     };
     Ok(diff)
 }
@@ -60,6 +257,7 @@ mod tests {
             &ExternalConfig {
                 extra_params: Vec::new(),
                 executable: "non_existent".to_owned(),
+                ..Default::default()
             },
         )
         .unwrap();
@@ -80,9 +278,193 @@ mod tests {
                     "--exit-with-error".to_owned(),
                 ],
                 executable: "cargo".to_owned(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(result.is_error);
+    }
+
+    #[test]
+    fn hanging_checker_is_killed_after_timeout() {
+        let start = Instant::now();
+        let result = compare_files(
+            Path::new("file1"),
+            Path::new("file2"),
+            &ExternalConfig {
+                extra_params: vec![
+                    "run".to_owned(),
+                    "--bin".to_owned(),
+                    "print_args".to_owned(),
+                    "--".to_owned(),
+                    "--sleep=20".to_owned(),
+                ],
+                executable: "cargo".to_owned(),
+                timeout_secs: Some(2),
+                ..Default::default()
             },
         )
         .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(15));
         assert!(result.is_error);
+        assert!(matches!(
+            result.detail.as_slice(),
+            [DiffDetail::Error(message)] if message.contains("timed out after 2 s")
+        ));
+    }
+
+    #[test]
+    fn checker_within_timeout_reports_output() {
+        let result = compare_files(
+            Path::new("file1"),
+            Path::new("file2"),
+            &ExternalConfig {
+                extra_params: vec![
+                    "run".to_owned(),
+                    "--bin".to_owned(),
+                    "print_args".to_owned(),
+                    "--".to_owned(),
+                    "--exit-with-error".to_owned(),
+                ],
+                executable: "cargo".to_owned(),
+                timeout_secs: Some(300),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(matches!(
+            result.detail.as_slice(),
+            [DiffDetail::External { stderr, .. }] if stderr.contains("setting error code to -1")
+        ));
+    }
+
+    #[test]
+    fn env_and_working_dir_are_passed_to_checker() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = std::path::absolute("Cargo.toml").unwrap();
+        let result = compare_files(
+            Path::new("file1"),
+            Path::new("file2"),
+            &ExternalConfig {
+                extra_params: vec![
+                    "run".to_owned(),
+                    "--manifest-path".to_owned(),
+                    manifest.to_string_lossy().into_owned(),
+                    "--bin".to_owned(),
+                    "print_args".to_owned(),
+                    "--".to_owned(),
+                    "--print-env=HAVOCOMPARE_LICENSE".to_owned(),
+                    "--print-cwd".to_owned(),
+                    "--exit-with-error".to_owned(),
+                ],
+                executable: "cargo".to_owned(),
+                env: Some(HashMap::from([(
+                    "HAVOCOMPARE_LICENSE".to_owned(),
+                    "license-server:1234".to_owned(),
+                )])),
+                working_dir: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let [DiffDetail::External { stdout, .. }] = result.detail.as_slice() else {
+            panic!("wrong diffdetail");
+        };
+        assert!(stdout.contains("HAVOCOMPARE_LICENSE=license-server:1234"));
+        let cwd = dir.path().canonicalize().unwrap();
+        assert!(stdout.contains(&format!("cwd={}", cwd.to_string_lossy())));
+        let nominal = std::path::absolute("file1").unwrap();
+        assert!(stdout.contains(nominal.to_string_lossy().as_ref()));
+    }
+
+    fn json_output_config(json: &str) -> ExternalConfig {
+        ExternalConfig {
+            extra_params: vec![
+                "run".to_owned(),
+                "--bin".to_owned(),
+                "print_args".to_owned(),
+                "--".to_owned(),
+                format!("--print-json={json}"),
+            ],
+            executable: "cargo".to_owned(),
+            output_format: ExternalOutputFormat::Json,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn json_output_becomes_structured_details() {
+        let config = json_output_config(
+            r#"[{"message": "image differs", "location": "page 2"}, {"message": "font substituted", "severity": "warning"}]"#,
+        );
+        let result = compare_files(Path::new("file1"), Path::new("file2"), &config).unwrap();
+        assert!(result.is_error);
+        assert!(result.is_warning);
+        let [first, second] = result.detail.as_slice() else {
+            panic!("wrong diffdetail");
+        };
+        assert!(matches!(
+            first,
+            DiffDetail::ExternalFinding { message, location: Some(location), warning: false }
+                if message == "image differs" && location == "page 2"
+        ));
+        assert_eq!(second.to_string(), "Warning: font substituted");
+
+        let only_warnings = json_output_config(r#"[{"message": "slow", "severity": "warning"}]"#);
+        let result = compare_files(Path::new("file1"), Path::new("file2"), &only_warnings).unwrap();
+        assert!(!result.is_error);
+        assert!(result.is_warning);
+    }
+
+    #[test]
+    fn unparsable_json_output_falls_back_to_text() {
+        let config = json_output_config("no json at all");
+        let result = compare_files(Path::new("file1"), Path::new("file2"), &config).unwrap();
+        assert!(!result.is_error);
+        assert!(result.is_warning);
+        assert!(matches!(
+            result.detail.as_slice(),
+            [DiffDetail::Error(message)] if message.contains("falling back to text")
+        ));
+    }
+
+    #[test]
+    fn flaky_checker_is_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let flaky_config = |marker: &str, retry_exit_codes: Option<Vec<i32>>| ExternalConfig {
+            extra_params: vec![
+                "run".to_owned(),
+                "--bin".to_owned(),
+                "print_args".to_owned(),
+                "--".to_owned(),
+                format!("--fail-once={}", dir.path().join(marker).to_string_lossy()),
+            ],
+            executable: "cargo".to_owned(),
+            retries: Some(2),
+            retry_delay_secs: Some(0),
+            retry_exit_codes,
+            ..Default::default()
+        };
+
+        let result = compare_files(
+            Path::new("file1"),
+            Path::new("file2"),
+            &flaky_config("not_retried", None),
+        )
+        .unwrap();
+        assert!(result.is_error);
+        assert!(matches!(
+            result.detail.as_slice(),
+            [DiffDetail::External { attempts: 1, .. }]
+        ));
+
+        let result = compare_files(
+            Path::new("file1"),
+            Path::new("file2"),
+            &flaky_config("retried", Some(vec![3])),
+        )
+        .unwrap();
+        assert!(!result.is_error);
+        assert!(result.detail.is_empty());
     }
 }
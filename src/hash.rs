@@ -3,16 +3,19 @@ use data_encoding::HEXLOWER;
 
 use crate::report::{DiffDetail, Difference};
 use schemars_derive::JsonSchema;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
+use tracing::error;
 use vg_errortools::fat_io_wrap_std;
 use vg_errortools::FatIOError;
 
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy)]
 pub enum HashFunction {
     Sha256,
+    Sha512,
 }
 
 #[derive(Debug, Error)]
@@ -24,39 +27,138 @@ pub enum Error {
     ReportingFailure(#[from] report::Error),
     #[error("File access failed {0}")]
     FileAccessProblem(#[from] FatIOError),
+    #[error("Line {0} of manifest {1} is not of the form '<hash>  <path>'")]
+    MalformedManifest(usize, String),
 }
 
 impl HashFunction {
-    fn hash_file(&self, mut file: impl Read) -> Result<[u8; 32], Error> {
+    fn hash_file(&self, file: impl Read) -> Result<Vec<u8>, Error> {
         match self {
-            Self::Sha256 => {
-                use sha2::{Digest, Sha256};
-                use std::io;
-
-                let mut hasher = Sha256::new();
-
-                let _ = io::copy(&mut file, &mut hasher)
-                    .map_err(|e| FatIOError::from_std_io_err(e, PathBuf::new()))?;
-                let hash_bytes = hasher.finalize();
-                Ok(hash_bytes.into())
-            }
+            Self::Sha256 => digest_file::<sha2::Sha256>(file),
+            Self::Sha512 => digest_file::<sha2::Sha512>(file),
         }
     }
 }
 
+fn digest_file<D: sha2::Digest + std::io::Write>(mut file: impl Read) -> Result<Vec<u8>, Error> {
+    let mut hasher = D::new();
+
+    let _ = std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| FatIOError::from_std_io_err(e, PathBuf::new()))?;
+    let hash_bytes = hasher.finalize();
+    Ok(hash_bytes.to_vec())
+}
+
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 /// Configuration options for the hash comparison module
 pub struct HashConfig {
     /// Which hash function to use
     pub function: HashFunction,
+    /// Verify the actual files against this checksum manifest instead of the nominal files, e.g. a `SHA256SUMS`
+    /// file as written by `sha256sum`. A relative path is resolved against the nominal folder - optional
+    #[serde(default)]
+    pub manifest: Option<PathBuf>,
 }
 
 impl Default for HashConfig {
     fn default() -> Self {
         HashConfig {
             function: HashFunction::Sha256,
+            manifest: None,
+        }
+    }
+}
+
+/// Reads a checksum manifest with lines of the form `<hex hash>  <relative path>`, as written by e.g. `sha256sum`.
+/// Empty lines and lines starting with `#` are skipped.
+fn read_manifest(manifest: &Path) -> Result<BTreeMap<String, String>, Error> {
+    let content = fat_io_wrap_std(manifest, &std::fs::read_to_string)?;
+    let mut entries = BTreeMap::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let malformed =
+            || Error::MalformedManifest(line_number + 1, manifest.to_string_lossy().to_string());
+        let (hash, path) = line.split_once(char::is_whitespace).ok_or_else(malformed)?;
+        // `*` marks binary mode in the coreutils format, `./` is added by e.g. `find . | xargs sha256sum`
+        let path = path.trim_start();
+        let path = path.strip_prefix('*').unwrap_or(path);
+        let path = path.strip_prefix("./").unwrap_or(path);
+        if path.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(malformed());
+        }
+        entries.insert(path.to_owned(), hash.to_lowercase());
+    }
+    Ok(entries)
+}
+
+/// Checks every file listed in the manifest against its hash in `actual_root`.
+/// Files listed but missing and `actual_files` not listed in the manifest are errors as well.
+pub fn verify_manifest(
+    manifest: &Path,
+    actual_root: &Path,
+    actual_files: Vec<PathBuf>,
+    function: HashFunction,
+) -> Result<Vec<Difference>, Error> {
+    let entries = read_manifest(manifest)?;
+    let manifest_root = manifest.parent().unwrap_or(Path::new(""));
+    let relative = |file: &Path| {
+        file.strip_prefix(actual_root)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let mut unlisted: BTreeMap<String, PathBuf> = actual_files
+        .into_iter()
+        .map(|file| (relative(&file), file))
+        .collect();
+
+    let mut differences = Vec::new();
+    for (path, expected) in entries {
+        unlisted.remove(&path);
+        let actual_file = actual_root.join(&path);
+        let mut difference = Difference::new_for_file(manifest_root.join(&path), &actual_file);
+        if actual_file.is_file() {
+            let actual =
+                HEXLOWER.encode(&function.hash_file(fat_io_wrap_std(&actual_file, &File::open)?)?);
+            if actual != expected {
+                error!(
+                    "Hash of {} does not match the manifest",
+                    actual_file.to_string_lossy()
+                );
+                difference.push_detail(DiffDetail::Hash {
+                    actual,
+                    nominal: expected,
+                });
+                difference.error();
+            }
+        } else {
+            let message = format!(
+                "Actual file {} listed in manifest {} is missing",
+                actual_file.to_string_lossy(),
+                manifest.to_string_lossy()
+            );
+            error!("{message}");
+            difference.push_detail(DiffDetail::Error(message));
+            difference.error();
         }
+        differences.push(difference);
+    }
+    for (path, actual_file) in unlisted {
+        let message = format!(
+            "Actual file {} is not listed in manifest {}",
+            actual_file.to_string_lossy(),
+            manifest.to_string_lossy()
+        );
+        error!("{message}");
+        let mut difference = Difference::new_for_file(manifest_root.join(path), actual_file);
+        difference.push_detail(DiffDetail::Error(message));
+        difference.error();
+        differences.push(difference);
     }
+    Ok(differences)
 }
 
 pub fn compare_files<P: AsRef<Path>>(
@@ -85,7 +187,7 @@ pub fn compare_files<P: AsRef<Path>>(
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::hash::HashFunction::Sha256;
+    use crate::hash::HashFunction::{Sha256, Sha512};
 
     #[test]
     fn identity() {
@@ -107,6 +209,15 @@ mod test {
         assert_eq!(HEXLOWER.encode(&f1), sum);
     }
 
+    #[test]
+    fn hash_pinning_sha512() {
+        let sum = "0337f5b7f8a25994ca644479e880cb4d452cebc985eb3b5e4a1c392270fd2d8ecac137d83bc8e87c3349bd2fae5f9f8d1c99ff4234ed4673d68ae943c4d28e12";
+        let f1 = Sha512
+            .hash_file(File::open("tests/integ/data/images/diff_100_DPI.png").unwrap())
+            .unwrap();
+        assert_eq!(HEXLOWER.encode(&f1), sum);
+    }
+
     #[test]
     fn identity_outer() {
         let file = "tests/integ.rs";
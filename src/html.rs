@@ -3,25 +3,77 @@ use crate::report::{DiffDetail, Difference};
 use regex::Regex;
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
-use strsim::normalized_damerau_levenshtein;
 use thiserror::Error;
-use tracing::error;
+use tracing::{error, warn};
 use vg_errortools::fat_io_wrap_std;
 use vg_errortools::FatIOError;
 
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+/// The string similarity metric used for comparing lines, all metrics are normalized to 0.0 = bad, 1.0 = identity
+pub enum TextSimilarityMetric {
+    /// Normalized Damerau-Levenshtein distance - handles transpositions but is slow on very long lines
+    #[default]
+    DamerauLevenshtein,
+    /// Normalized Levenshtein distance
+    Levenshtein,
+    /// Jaro similarity
+    Jaro,
+    /// Jaro-Winkler similarity, gives more weight to common prefixes
+    JaroWinkler,
+    /// Sørensen-Dice coefficient of the character bigrams - fast, ignores the order of the bigrams
+    SorensenDice,
+}
+
+impl TextSimilarityMetric {
+    pub(crate) fn similarity(&self, a: &str, b: &str) -> f64 {
+        match self {
+            TextSimilarityMetric::DamerauLevenshtein => {
+                strsim::normalized_damerau_levenshtein(a, b)
+            }
+            TextSimilarityMetric::Levenshtein => strsim::normalized_levenshtein(a, b),
+            TextSimilarityMetric::Jaro => strsim::jaro(a, b),
+            TextSimilarityMetric::JaroWinkler => strsim::jaro_winkler(a, b),
+            TextSimilarityMetric::SorensenDice => strsim::sorensen_dice(a, b),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 /// Plain text comparison config, also used for PDF
 pub struct HTMLCompareConfig {
-    /// Normalized Damerau-Levenshtein distance, 0.0 = bad, 1.0 = identity
+    /// Minimal similarity of each line as computed by `metric`, 0.0 = bad, 1.0 = identity
     pub threshold: f64,
+    /// Lines with a similarity between `threshold` and this value are reported as warning instead of passing silently - optional
+    #[serde(default)]
+    pub warn_threshold: Option<f64>,
     /// Lines matching any of the given regex will be excluded from comparison
     pub ignore_lines: Option<Vec<String>>,
+    /// The similarity metric to use, defaults to normalized Damerau-Levenshtein
+    #[serde(default)]
+    pub metric: TextSimilarityMetric,
+    /// Additionally report differences in line-ending style (LF / CRLF) and byte-order-mark presence - plain text only
+    #[serde(default)]
+    pub check_line_endings: bool,
+    /// Only compare the first lines of both files, combinable with `tail_lines` - plain text only, optional
+    #[serde(default)]
+    pub head_lines: Option<usize>,
+    /// Only compare the last lines of both files, combinable with `head_lines` - plain text only, optional
+    #[serde(default)]
+    pub tail_lines: Option<usize>,
 }
 
 impl HTMLCompareConfig {
+    /// Whether a passing similarity is below the warn threshold
+    pub(crate) fn is_warning(&self, similarity: f64) -> bool {
+        self.warn_threshold
+            .is_some_and(|warn_threshold| similarity < warn_threshold)
+    }
+
     pub(crate) fn get_ignore_list(&self) -> Result<Vec<Regex>, regex::Error> {
         let exclusion_list: Option<Result<Vec<_>, regex::Error>> = self
             .ignore_lines
@@ -39,11 +91,80 @@ impl Default for HTMLCompareConfig {
     fn default() -> Self {
         HTMLCompareConfig {
             threshold: 1.0,
+            warn_threshold: None,
             ignore_lines: None,
+            metric: TextSimilarityMetric::default(),
+            check_line_endings: false,
+            head_lines: None,
+            tail_lines: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineEndings {
+    None,
+    Lf,
+    CrLf,
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextFormat {
+    bom: bool,
+    line_endings: LineEndings,
+}
+
+impl TextFormat {
+    const UTF8_BOM: &'static [u8] = &[0xEF, 0xBB, 0xBF];
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let line_feeds = bytes.iter().filter(|b| **b == b'\n').count();
+        let crlfs = bytes.windows(2).filter(|w| *w == b"\r\n").count();
+        let line_endings = match (line_feeds, crlfs) {
+            (0, _) => LineEndings::None,
+            (_, 0) => LineEndings::Lf,
+            (lf, crlf) if lf == crlf => LineEndings::CrLf,
+            _ => LineEndings::Mixed,
+        };
+        TextFormat {
+            bom: bytes.starts_with(Self::UTF8_BOM),
+            line_endings,
         }
     }
 }
 
+impl Display for TextFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let line_endings = match self.line_endings {
+            LineEndings::None => "no line endings",
+            LineEndings::Lf => "LF line endings",
+            LineEndings::CrLf => "CRLF line endings",
+            LineEndings::Mixed => "mixed line endings",
+        };
+        let bom = if self.bom { "with BOM" } else { "without BOM" };
+        write!(f, "{line_endings}, {bom}")
+    }
+}
+
+fn compare_text_format(
+    nominal_path: &Path,
+    actual_path: &Path,
+    difference: &mut Difference,
+) -> Result<(), Error> {
+    let nominal = TextFormat::from_bytes(&fat_io_wrap_std(nominal_path, &std::fs::read)?);
+    let actual = TextFormat::from_bytes(&fat_io_wrap_std(actual_path, &std::fs::read)?);
+    if nominal != actual {
+        error!("Text format mismatch. Expected: '{nominal}' found '{actual}'");
+        difference.push_detail(DiffDetail::TextFormat {
+            nominal: nominal.to_string(),
+            actual: actual.to_string(),
+        });
+        difference.error();
+    }
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 /// Errors during html / plain text checking
 pub enum Error {
@@ -55,6 +176,32 @@ pub enum Error {
     FileAccessFailure(#[from] FatIOError),
 }
 
+/// The numbered lines to compare: all of them, or only the first `head_lines` and the last `tail_lines`.
+/// The middle is still read to find the end, but never kept or compared.
+fn select_lines<'a>(
+    mut lines: impl Iterator<Item = (usize, String)> + 'a,
+    config: &HTMLCompareConfig,
+) -> Box<dyn Iterator<Item = (usize, String)> + 'a> {
+    if config.head_lines.is_none() && config.tail_lines.is_none() {
+        return Box::new(lines);
+    }
+    let mut selected: Vec<_> = lines
+        .by_ref()
+        .take(config.head_lines.unwrap_or(0))
+        .collect();
+    if let Some(tail_lines) = config.tail_lines.filter(|tail_lines| *tail_lines > 0) {
+        let mut tail = VecDeque::with_capacity(tail_lines);
+        for line in lines {
+            if tail.len() == tail_lines {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+        selected.extend(tail);
+    }
+    Box::new(selected.into_iter())
+}
+
 pub fn compare_files<P: AsRef<Path>>(
     nominal_path: P,
     actual_path: P,
@@ -64,17 +211,22 @@ pub fn compare_files<P: AsRef<Path>>(
     let nominal = BufReader::new(fat_io_wrap_std(nominal_path.as_ref(), &File::open)?);
 
     let exclusion_list = config.get_ignore_list()?;
-    let mut difference = Difference::new_for_file(nominal_path, actual_path);
-    actual
+    let mut difference = Difference::new_for_file(nominal_path.as_ref(), actual_path.as_ref());
+    if config.check_line_endings {
+        compare_text_format(nominal_path.as_ref(), actual_path.as_ref(), &mut difference)?;
+    }
+    let actual_lines = actual
         .lines()
         .enumerate()
-        .filter_map(|l| l.1.ok().map(|a| (l.0, a)))
-        .zip(nominal.lines().map_while(Result::ok))
+        .filter_map(|l| l.1.ok().map(|a| (l.0, a)));
+    let nominal_lines = nominal.lines().map_while(Result::ok).enumerate();
+    select_lines(actual_lines, config)
+        .zip(select_lines(nominal_lines, config).map(|(_, n)| n))
         .filter(|((_, a), n)|
             exclusion_list.iter().all(|exc| !exc.is_match(a)) && exclusion_list.iter().all(|exc| !exc.is_match(n))
         )
         .for_each(|((l, a), n)| {
-            let distance = normalized_damerau_levenshtein(a.as_str(),n.as_str());
+            let distance = config.metric.similarity(a.as_str(), n.as_str());
             if  distance < config.threshold {
 
                 let error =  format!(
@@ -85,6 +237,10 @@ pub fn compare_files<P: AsRef<Path>>(
                 error!("{}" , &error);
                 difference.push_detail(DiffDetail::Text {actual: a, nominal: n, score: distance, line: l});
                 difference.error();
+            } else if config.is_warning(distance) {
+                warn!("Line {} of HTML-file close to threshold. Expected: '{}' found '{}' (diff: {}, threshold: {})", l, n, a, distance, config.threshold);
+                difference.push_detail(DiffDetail::Text {actual: a, nominal: n, score: distance, line: l});
+                difference.warn();
             }
         });
 
@@ -126,7 +282,8 @@ mod test {
                 "tests/html/html_changed.html",
                 &HTMLCompareConfig {
                     threshold: 0.9,
-                    ignore_lines: None
+                    ignore_lines: None,
+                    ..Default::default()
                 },
             )
             .unwrap()
@@ -142,11 +299,168 @@ mod test {
                 "tests/html/html_changed.html",
                 &HTMLCompareConfig {
                     threshold: 1.0,
-                    ignore_lines: Some(vec!["stylesheet".to_owned()])
+                    ignore_lines: Some(vec!["stylesheet".to_owned()]),
+                    ..Default::default()
                 },
             )
             .unwrap()
             .is_error
         );
     }
+
+    #[test]
+    fn test_metrics_give_different_results() {
+        let nominal = "the quick brown fox";
+        let actual = "teh quick brown fox";
+        let damerau = TextSimilarityMetric::DamerauLevenshtein.similarity(nominal, actual);
+        let levenshtein = TextSimilarityMetric::Levenshtein.similarity(nominal, actual);
+        assert!(damerau > levenshtein);
+        assert_eq!(
+            TextSimilarityMetric::JaroWinkler.similarity(nominal, nominal),
+            1.0
+        );
+
+        let strict_damerau = HTMLCompareConfig {
+            threshold: 0.95,
+            ..Default::default()
+        };
+        let strict_levenshtein = HTMLCompareConfig {
+            threshold: 0.95,
+            metric: TextSimilarityMetric::Levenshtein,
+            ..Default::default()
+        };
+        let result_damerau = compare_files(
+            "tests/html/test.html",
+            "tests/html/html_changed.html",
+            &strict_damerau,
+        )
+        .unwrap();
+        let result_levenshtein = compare_files(
+            "tests/html/test.html",
+            "tests/html/html_changed.html",
+            &strict_levenshtein,
+        )
+        .unwrap();
+        assert!(result_levenshtein.detail.len() >= result_damerau.detail.len());
+    }
+
+    #[test]
+    fn test_line_endings_and_bom() {
+        let dir = tempfile::tempdir().unwrap();
+        let lf = dir.path().join("lf.txt");
+        let crlf = dir.path().join("crlf.txt");
+        let bom = dir.path().join("bom.txt");
+        std::fs::write(&lf, "first line\nsecond line\n").unwrap();
+        std::fs::write(&crlf, "first line\r\nsecond line\r\n").unwrap();
+        std::fs::write(&bom, "\u{feff}first line\nsecond line\n").unwrap();
+
+        let lenient = HTMLCompareConfig::default();
+        assert!(!compare_files(&lf, &crlf, &lenient).unwrap().is_error);
+
+        let strict = HTMLCompareConfig {
+            check_line_endings: true,
+            ..Default::default()
+        };
+        assert!(!compare_files(&lf, &lf, &strict).unwrap().is_error);
+        let result = compare_files(&lf, &crlf, &strict).unwrap();
+        assert!(result.is_error);
+        assert_eq!(result.detail.len(), 1);
+        if let DiffDetail::TextFormat { nominal, actual } = result.detail.first().unwrap() {
+            assert_eq!(nominal, "LF line endings, without BOM");
+            assert_eq!(actual, "CRLF line endings, without BOM");
+        } else {
+            panic!("wrong diffdetail");
+        }
+
+        let result = compare_files(&lf, &bom, &strict).unwrap();
+        assert!(result.detail.iter().any(|d| matches!(
+            d,
+            DiffDetail::TextFormat { actual, .. } if actual == "LF line endings, with BOM"
+        )));
+
+        assert_eq!(
+            TextFormat::from_bytes(b"a\r\nb\n").line_endings,
+            LineEndings::Mixed
+        );
+        assert_eq!(TextFormat::from_bytes(b"a").line_endings, LineEndings::None);
+    }
+
+    #[test]
+    fn head_and_tail_lines_only_compare_the_slices() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal.log");
+        let actual = dir.path().join("actual.log");
+        std::fs::write(&nominal, "start\nconfig a\nwork 1\nwork 2\nsummary\ndone\n").unwrap();
+        std::fs::write(
+            &actual,
+            "start\nconfig b\nwork 7\nwork 8\nwork 9\nsummary\ndone\n",
+        )
+        .unwrap();
+
+        let slices = HTMLCompareConfig {
+            head_lines: Some(1),
+            tail_lines: Some(2),
+            ..Default::default()
+        };
+        assert!(!compare_files(&nominal, &actual, &slices).unwrap().is_error);
+        assert!(
+            compare_files(&nominal, &actual, &HTMLCompareConfig::default())
+                .unwrap()
+                .is_error
+        );
+
+        let head = HTMLCompareConfig {
+            head_lines: Some(2),
+            ..Default::default()
+        };
+        let result = compare_files(&nominal, &actual, &head).unwrap();
+        assert_eq!(result.detail.len(), 1);
+        assert!(matches!(
+            result.detail.first(),
+            Some(DiffDetail::Text { line: 1, .. })
+        ));
+
+        let tail = HTMLCompareConfig {
+            tail_lines: Some(3),
+            ..Default::default()
+        };
+        let result = compare_files(&nominal, &actual, &tail).unwrap();
+        assert_eq!(result.detail.len(), 1);
+        assert!(matches!(
+            result.detail.first(),
+            Some(DiffDetail::Text { line: 4, nominal, actual, .. }) if nominal == "work 2" && actual == "work 9"
+        ));
+    }
+
+    #[test]
+    fn warn_threshold_marks_close_lines_as_warning() {
+        let config = HTMLCompareConfig {
+            threshold: 0.9,
+            warn_threshold: Some(1.0),
+            ..Default::default()
+        };
+        let result = compare_files(
+            "tests/html/test.html",
+            "tests/html/html_changed.html",
+            &config,
+        )
+        .unwrap();
+        assert!(!result.is_error);
+        assert!(result.is_warning);
+        assert!(!result.detail.is_empty());
+
+        let config = HTMLCompareConfig {
+            threshold: 1.0,
+            warn_threshold: Some(1.0),
+            ..Default::default()
+        };
+        let result = compare_files(
+            "tests/html/test.html",
+            "tests/html/html_changed.html",
+            &config,
+        )
+        .unwrap();
+        assert!(result.is_error);
+        assert!(!result.is_warning);
+    }
 }
@@ -1,22 +1,131 @@
 use crate::report::DiffDetail;
 use crate::{get_file_name, report};
+use image::{DynamicImage, GrayImage, Luma, RgbaImage};
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tracing::error;
+use tracing::{error, warn};
+use vg_errortools::{fat_io_wrap_std, FatIOError};
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+/// The color space the channel values of an image are encoded in
+pub enum ColorSpace {
+    /// Standard sRGB transfer function, the usual encoding of 8 bit images
+    Srgb,
+    /// Linear light values without any transfer function
+    Linear,
+    /// Pure power law encoding with the given gamma, e.g. 2.2 - `encoded = linear^(1/gamma)`
+    Gamma(f64),
+}
+
+impl ColorSpace {
+    fn to_linear(self, value: f64) -> f64 {
+        match self {
+            ColorSpace::Srgb => {
+                if value <= 0.04045 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            ColorSpace::Linear => value,
+            ColorSpace::Gamma(gamma) => value.powf(gamma),
+        }
+    }
+
+    fn encode_linear(self, value: f64) -> f64 {
+        match self {
+            ColorSpace::Srgb => {
+                if value <= 0.0031308 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            ColorSpace::Linear => value,
+            ColorSpace::Gamma(gamma) => value.powf(1.0 / gamma),
+        }
+    }
+
+    fn conversion_table(self, target: ColorSpace) -> [u8; 256] {
+        let mut table = [0u8; 256];
+        for (value, entry) in table.iter_mut().enumerate() {
+            let converted = target.encode_linear(self.to_linear(value as f64 / 255.0));
+            *entry = (converted.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        table
+    }
+}
+
+#[derive(JsonSchema, Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+/// Color spaces of both images and the common color space they are converted to before comparison
+pub struct ColorConversion {
+    /// The color space of the nominal image
+    pub nominal: ColorSpace,
+    /// The color space of the actual image
+    pub actual: ColorSpace,
+    /// The color space both images are converted to
+    pub target: ColorSpace,
+}
+
+fn convert_color_space(image: &mut RgbaImage, source: ColorSpace, target: ColorSpace) {
+    if source == target {
+        return;
+    }
+    let table = source.conversion_table(target);
+    for pixel in image.pixels_mut() {
+        for channel in pixel.0.iter_mut().take(3) {
+            *channel = table[*channel as usize];
+        }
+    }
+}
 
 #[derive(JsonSchema, Deserialize, Serialize, Debug, Clone)]
 /// Image comparison config options
 pub struct ImageCompareConfig {
     /// Threshold for image comparison < 0.5 is very dissimilar, 1.0 is identical
     pub threshold: f64,
+    /// Convert both images into a common color space before comparison - optional
+    #[serde(default)]
+    pub color_conversion: Option<ColorConversion>,
+    /// Scores between `threshold` and this value are reported as warning instead of passing silently - optional
+    #[serde(default)]
+    pub warn_threshold: Option<f64>,
+    /// Compare the alpha channel separately with this threshold, `threshold` then only applies to the colors - optional
+    #[serde(default)]
+    pub alpha_threshold: Option<f64>,
+    /// Scale the differences in the diff image by this factor to make small differences visible, the score is not affected - optional
+    #[serde(default)]
+    pub diff_amplification: Option<f64>,
+    /// Crop this many pixels off every edge of both images before comparing, e.g. to ignore margin changes - optional
+    #[serde(default)]
+    pub crop_border: Option<u32>,
+    /// Write a diff image for failed and warned comparisons, disable to only get the scores - optional, default is true
+    #[serde(default = "ImageCompareConfig::default_generate_diff_image")]
+    pub generate_diff_image: bool,
+    /// Export the raw per pixel similarity values of failed and warned comparisons as csv grid - optional, default is false
+    #[serde(default)]
+    pub export_similarity_map: bool,
 }
 
 impl ImageCompareConfig {
     /// create an [`ImageCompareConfig`] given the threshold
     pub fn from_threshold(threshold: f64) -> Self {
-        ImageCompareConfig { threshold }
+        ImageCompareConfig {
+            threshold,
+            warn_threshold: None,
+            color_conversion: None,
+            alpha_threshold: None,
+            diff_amplification: None,
+            crop_border: None,
+            generate_diff_image: true,
+            export_similarity_map: false,
+        }
+    }
+
+    fn default_generate_diff_image() -> bool {
+        true
     }
 }
 
@@ -36,6 +145,119 @@ pub enum Error {
     ImageComparison(#[from] image_compare::CompareError),
     #[error("Problem processing file name {0}")]
     FileNameParsing(String),
+    #[error("Cropping a border of {0} pixels exceeds the image size of {1}x{2}")]
+    BorderTooLarge(u32, u32, u32),
+    #[error("Problem writing similarity map {0}")]
+    SimilarityMapWriting(#[from] FatIOError),
+    #[error("Could not create temporary output file {0}")]
+    TemporaryFile(#[from] std::io::Error),
+}
+
+fn crop_border(image: &RgbaImage, border: u32) -> Result<RgbaImage, Error> {
+    let (width, height) = image.dimensions();
+    let cropped_width = width.saturating_sub(border.saturating_mul(2));
+    let cropped_height = height.saturating_sub(border.saturating_mul(2));
+    if cropped_width == 0 || cropped_height == 0 {
+        return Err(Error::BorderTooLarge(border, width, height));
+    }
+    Ok(image::imageops::crop_imm(image, border, border, cropped_width, cropped_height).to_image())
+}
+
+fn alpha_channel(image: &RgbaImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        Luma([image.get_pixel(x, y).0[3]])
+    })
+}
+
+/// Scales the per pixel differences of a diff color map, similarity maps hold 255 for equal pixels instead of 0
+fn amplify_differences(map: &mut DynamicImage, factor: f64, similarity_map: bool) {
+    let amplify = |v: &mut u8| {
+        let difference = if similarity_map { 255 - *v } else { *v };
+        let amplified = (difference as f64 * factor).clamp(0.0, 255.0) as u8;
+        *v = if similarity_map {
+            255 - amplified
+        } else {
+            amplified
+        };
+    };
+    match map {
+        DynamicImage::ImageLuma8(gray) => gray.iter_mut().for_each(amplify),
+        DynamicImage::ImageRgb8(rgb) => rgb.iter_mut().for_each(amplify),
+        DynamicImage::ImageRgba8(rgba) => rgba.iter_mut().for_each(amplify),
+        _ => error!("Diff image format not supported for amplification"),
+    }
+}
+
+/// Writes the color map of the differences to `out_path`, amplified if configured
+fn save_diff_image(
+    mut color_map: DynamicImage,
+    similarity_map: bool,
+    config: &ImageCompareConfig,
+    out_path: &str,
+) -> Result<(), Error> {
+    if let Some(factor) = config.diff_amplification {
+        amplify_differences(&mut color_map, factor, similarity_map);
+    }
+    color_map.save(PathBuf::from(out_path))?;
+    Ok(())
+}
+
+/// Writes the similarity values of a color map to `out_path` as csv, one line per image row and
+/// one column per pixel and channel, e.g. `r,g,b,r,g,b,...` for rgb maps.
+/// `image_compare` only exposes the map quantized to 8 bit, so the values are in steps of 1/255.
+fn save_similarity_map(color_map: &DynamicImage, out_path: &str) -> Result<(), Error> {
+    fn rows<P: image::Pixel<Subpixel = u8>>(map: &image::ImageBuffer<P, Vec<u8>>) -> String {
+        map.rows()
+            .map(|row| {
+                row.flat_map(|pixel| pixel.channels().iter())
+                    .map(|v| (*v as f32 / 255.0).to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .fold(String::new(), |grid, row| grid + &row + "\n")
+    }
+    let grid = match color_map {
+        DynamicImage::ImageLuma8(gray) => rows(gray),
+        DynamicImage::ImageRgb8(rgb) => rows(rgb),
+        DynamicImage::ImageRgba8(rgba) => rows(rgba),
+        _ => rows(&color_map.to_rgba8()),
+    };
+    fat_io_wrap_std(out_path, &|path| std::fs::write(path, &grid))?;
+    Ok(())
+}
+
+/// A uniquely named file in the temp dir, kept until the report moves it into its detail folder
+fn temporary_output_path(nominal_file_name: &str, suffix: &str) -> Result<String, Error> {
+    let (_, path) = tempfile::Builder::new()
+        .prefix(nominal_file_name)
+        .suffix(suffix)
+        .tempfile()
+        .and_then(|file| file.keep().map_err(|e| e.error))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Writes the configured diff image and similarity map to the temp dir, returning the paths of the written files
+fn save_difference_maps(
+    color_map: DynamicImage,
+    is_similarity_map: bool,
+    config: &ImageCompareConfig,
+    nominal_file_name: &str,
+) -> Result<(Option<String>, Option<String>), Error> {
+    let similarity_map = if config.export_similarity_map {
+        let similarity_map_path = temporary_output_path(nominal_file_name, "similarity_map.csv")?;
+        save_similarity_map(&color_map, &similarity_map_path)?;
+        Some(similarity_map_path)
+    } else {
+        None
+    };
+    let diff_image = if config.generate_diff_image {
+        let out_path = temporary_output_path(nominal_file_name, "diff_image.png")?;
+        save_diff_image(color_map, is_similarity_map, config, &out_path)?;
+        Some(out_path)
+    } else {
+        None
+    };
+    Ok((diff_image, similarity_map))
 }
 
 pub fn compare_paths<P: AsRef<Path>>(
@@ -43,21 +265,49 @@ pub fn compare_paths<P: AsRef<Path>>(
     actual_path: P,
     config: &ImageCompareConfig,
 ) -> Result<report::Difference, Error> {
-    let nominal = image::open(nominal_path.as_ref())?.into_rgba8();
-    let actual = image::open(actual_path.as_ref())?.into_rgba8();
+    let mut nominal = image::open(nominal_path.as_ref())?.into_rgba8();
+    let mut actual = image::open(actual_path.as_ref())?.into_rgba8();
+    if let Some(border) = config.crop_border {
+        nominal = crop_border(&nominal, border)?;
+        actual = crop_border(&actual, border)?;
+    }
+    if let Some(conversion) = config.color_conversion {
+        convert_color_space(&mut nominal, conversion.nominal, conversion.target);
+        convert_color_space(&mut actual, conversion.actual, conversion.target);
+    }
 
-    let result = image_compare::rgba_hybrid_compare(&nominal, &actual)?;
+    let (result, alpha_result) = match config.alpha_threshold {
+        Some(_) => {
+            let alpha_result = image_compare::gray_similarity_structure(
+                &image_compare::Algorithm::RootMeanSquared,
+                &alpha_channel(&nominal),
+                &alpha_channel(&actual),
+            )?;
+            let nominal = DynamicImage::ImageRgba8(nominal).into_rgb8();
+            let actual = DynamicImage::ImageRgba8(actual).into_rgb8();
+            let result = image_compare::rgb_hybrid_compare(&nominal, &actual)?;
+            (result, Some(alpha_result))
+        }
+        None => (image_compare::rgba_hybrid_compare(&nominal, &actual)?, None),
+    };
+    let alpha_failed = alpha_result
+        .as_ref()
+        .zip(config.alpha_threshold)
+        .is_some_and(|(alpha, threshold)| alpha.score < threshold);
     let nominal_file_name =
         get_file_name(nominal_path.as_ref()).ok_or(Error::FileNameParsing(format!(
             "Could not extract filename from path {:?}",
             nominal_path.as_ref()
         )))?;
-    let out_path = (nominal_file_name + "diff_image.png").to_string();
     let mut result_diff = report::Difference::new_for_file(&nominal_path, &actual_path);
 
-    if result.score < config.threshold {
-        let color_map = result.image.to_color_map();
-        color_map.save(PathBuf::from(&out_path))?;
+    if result.score < config.threshold || alpha_failed {
+        let (color_map, is_similarity_map) = match &alpha_result {
+            Some(alpha) if result.score >= config.threshold => (alpha.image.to_color_map(), true),
+            _ => (result.image.to_color_map(), false),
+        };
+        let (diff_image, similarity_map) =
+            save_difference_maps(color_map, is_similarity_map, config, &nominal_file_name)?;
 
         let error_message = format!(
             "Diff for image {} was not met, expected {}, found {}",
@@ -66,18 +316,46 @@ pub fn compare_paths<P: AsRef<Path>>(
             result.score
         );
         error!("{}", &error_message);
+        if let (Some(alpha), Some(threshold)) = (&alpha_result, config.alpha_threshold) {
+            error!("Alpha channel expected {threshold}, found {}", alpha.score);
+        }
         result_diff.push_detail(DiffDetail::Image {
-            diff_image: out_path,
+            diff_image,
+            similarity_map,
             score: result.score,
+            alpha_score: alpha_result.map(|alpha| alpha.score),
         });
         result_diff.error();
+    } else if config
+        .warn_threshold
+        .is_some_and(|warn_threshold| result.score < warn_threshold)
+    {
+        let (diff_image, similarity_map) = save_difference_maps(
+            result.image.to_color_map(),
+            false,
+            config,
+            &nominal_file_name,
+        )?;
+        warn!(
+            "Image {} close to threshold, expected {}, found {}",
+            nominal_path.as_ref().to_string_lossy(),
+            config.threshold,
+            result.score
+        );
+        result_diff.push_detail(DiffDetail::Image {
+            diff_image,
+            similarity_map,
+            score: result.score,
+            alpha_score: alpha_result.map(|alpha| alpha.score),
+        });
+        result_diff.warn();
     }
     Ok(result_diff)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::image::{compare_paths, ImageCompareConfig};
+    use crate::image::*;
     use crate::report::DiffDetail;
 
     #[test]
@@ -85,7 +363,7 @@ mod test {
         let result = compare_paths(
             "tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg",
             "tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg",
-            &ImageCompareConfig { threshold: 1.0 },
+            &ImageCompareConfig::from_threshold(1.0),
         )
         .unwrap();
         assert!(!result.is_error);
@@ -96,24 +374,343 @@ mod test {
         let result = compare_paths(
             "tests/integ/data/images/expected/SaveImage_100DPI_default_size.jpg",
             "tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg",
-            &ImageCompareConfig { threshold: 1.0 },
+            &ImageCompareConfig::from_threshold(1.0),
         )
         .unwrap();
         assert!(result.is_error);
         if let DiffDetail::Image {
             score: _,
+            alpha_score: _,
             diff_image,
+            similarity_map: _,
         } = result.detail.first().unwrap()
         {
-            let img = image::open(diff_image).unwrap().into_rgb8();
+            let img = image::open(diff_image.as_ref().unwrap())
+                .unwrap()
+                .into_rgb8();
             let nom = image::open("tests/integ/data/images/diff_100_DPI.png")
                 .unwrap()
                 .into_rgb8();
             let diff_result = image_compare::rgb_hybrid_compare(&img, &nom)
                 .expect("Wrong dimensions of diff images!");
             assert_eq!(diff_result.score, 1.0);
+            std::fs::remove_file(diff_image.as_ref().unwrap()).unwrap();
         } else {
             unreachable!();
         }
     }
+
+    #[test]
+    fn gamma_shifted_images_match_after_conversion() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal_path = dir.path().join("gamma_nominal.png");
+        let actual_path = dir.path().join("gamma_actual.png");
+        let nominal = RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 255])
+        });
+        let mut actual = nominal.clone();
+        convert_color_space(&mut actual, ColorSpace::Srgb, ColorSpace::Linear);
+        nominal.save(&nominal_path).unwrap();
+        actual.save(&actual_path).unwrap();
+
+        let mut config = ImageCompareConfig::from_threshold(0.99);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(result.is_error);
+        for detail in result.detail {
+            if let DiffDetail::Image { diff_image, .. } = detail {
+                std::fs::remove_file(diff_image.as_ref().unwrap()).unwrap();
+            }
+        }
+
+        config.color_conversion = Some(ColorConversion {
+            nominal: ColorSpace::Srgb,
+            actual: ColorSpace::Linear,
+            target: ColorSpace::Linear,
+        });
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(!result.is_error);
+    }
+
+    #[test]
+    fn color_space_round_trip() {
+        let to_gamma = ColorSpace::Linear.conversion_table(ColorSpace::Gamma(2.2));
+        assert_eq!(to_gamma[0], 0);
+        assert_eq!(to_gamma[255], 255);
+        assert!(to_gamma[64] > 64);
+        let identity = ColorSpace::Srgb.conversion_table(ColorSpace::Srgb);
+        assert!(identity.iter().enumerate().all(|(i, v)| i == *v as usize));
+    }
+
+    #[test]
+    fn alpha_channel_has_own_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal_path = dir.path().join("alpha_nominal.png");
+        let actual_path = dir.path().join("alpha_actual.png");
+        let nominal = RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+        });
+        let actual = RgbaImage::from_fn(64, 64, |x, y| {
+            let alpha = if x < 8 && y < 8 { 0 } else { 255 };
+            image::Rgba([(x * 4) as u8 + 1, (y * 4) as u8, 128, alpha])
+        });
+        nominal.save(&nominal_path).unwrap();
+        actual.save(&actual_path).unwrap();
+
+        let mut config = ImageCompareConfig::from_threshold(0.9);
+        config.alpha_threshold = Some(1.0);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(result.is_error);
+        if let Some(DiffDetail::Image {
+            score,
+            alpha_score,
+            diff_image,
+            ..
+        }) = result.detail.first()
+        {
+            assert!(*score >= 0.9);
+            assert!(alpha_score.unwrap() < 1.0);
+            std::fs::remove_file(diff_image.as_ref().unwrap()).unwrap();
+        } else {
+            unreachable!();
+        }
+
+        let opaque = RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8 + 1, (y * 4) as u8, 128, 255])
+        });
+        opaque.save(&actual_path).unwrap();
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(!result.is_error);
+    }
+
+    #[test]
+    fn diff_amplification_only_changes_diff_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("amplification_nominal.jpg");
+        std::fs::copy(
+            "tests/integ/data/images/expected/SaveImage_100DPI_default_size.jpg",
+            &nominal,
+        )
+        .unwrap();
+        let actual = Path::new("tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg");
+        let diff_image = |result: &report::Difference| match result.detail.first() {
+            Some(DiffDetail::Image {
+                score,
+                diff_image: Some(diff_image),
+                ..
+            }) => {
+                let image = image::open(diff_image).unwrap().into_rgba8();
+                std::fs::remove_file(diff_image).unwrap();
+                (*score, image)
+            }
+            _ => unreachable!(),
+        };
+
+        let mut config = ImageCompareConfig::from_threshold(1.0);
+        let (score, plain) =
+            diff_image(&compare_paths(nominal.as_path(), actual, &config).unwrap());
+        config.diff_amplification = Some(10.0);
+        let (amplified_score, amplified) =
+            diff_image(&compare_paths(nominal.as_path(), actual, &config).unwrap());
+
+        assert_eq!(score, amplified_score);
+        assert_ne!(plain, amplified);
+        let intensity = |img: &RgbaImage| -> u64 {
+            img.pixels()
+                .flat_map(|p| p.0.iter().take(3))
+                .map(|v| *v as u64)
+                .sum()
+        };
+        assert!(intensity(&amplified) > intensity(&plain));
+    }
+
+    #[test]
+    fn score_between_thresholds_is_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal_path = dir.path().join("warn_band_nominal.png");
+        let actual_path = dir.path().join("warn_band_actual.png");
+        let nominal = RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+        });
+        let actual = RgbaImage::from_fn(64, 64, |x, y| {
+            let blue = if x < 4 && y < 4 { 0 } else { 128 };
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, blue, 255])
+        });
+        nominal.save(&nominal_path).unwrap();
+        actual.save(&actual_path).unwrap();
+
+        let mut config = ImageCompareConfig::from_threshold(0.5);
+        config.warn_threshold = Some(1.0);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(!result.is_error);
+        assert!(result.is_warning);
+        let Some(DiffDetail::Image {
+            score, diff_image, ..
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        assert!(*score >= 0.5 && *score < 1.0);
+        std::fs::remove_file(diff_image.as_ref().unwrap()).unwrap();
+
+        config.warn_threshold = Some(*score);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(!result.is_error && !result.is_warning);
+        assert!(result.detail.is_empty());
+    }
+
+    #[test]
+    fn disabled_diff_image_keeps_verdict() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("no_diff_image_nominal.jpg");
+        let actual = dir.path().join("no_diff_image_actual.jpg");
+        let data = "tests/integ/data/images";
+        std::fs::copy(
+            format!("{data}/expected/SaveImage_100DPI_default_size.jpg"),
+            &nominal,
+        )
+        .unwrap();
+        std::fs::copy(
+            format!("{data}/actual/SaveImage_100DPI_default_size.jpg"),
+            &actual,
+        )
+        .unwrap();
+        let mut config = ImageCompareConfig::from_threshold(1.0);
+        config.generate_diff_image = false;
+        let result = compare_paths(&nominal, &actual, &config).unwrap();
+        assert!(result.is_error);
+        let Some(DiffDetail::Image {
+            score, diff_image, ..
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        assert!(*score < 1.0);
+        assert!(diff_image.is_none());
+
+        config.threshold = *score;
+        let result = compare_paths(&nominal, &actual, &config).unwrap();
+        assert!(!result.is_error);
+    }
+
+    #[test]
+    fn failing_comparison_leaves_working_directory_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("working_directory_nominal.jpg");
+        std::fs::copy(
+            "tests/integ/data/images/expected/SaveImage_100DPI_default_size.jpg",
+            &nominal,
+        )
+        .unwrap();
+        let actual = Path::new("tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg");
+        let leaked_files = || {
+            std::fs::read_dir(".")
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with("working_directory_nominal")
+                })
+                .count()
+        };
+
+        let mut config = ImageCompareConfig::from_threshold(1.0);
+        config.export_similarity_map = true;
+        let result = compare_paths(nominal.as_path(), actual, &config).unwrap();
+        assert!(result.is_error);
+        assert_eq!(leaked_files(), 0);
+        let Some(DiffDetail::Image {
+            diff_image: Some(diff_image),
+            similarity_map: Some(similarity_map),
+            ..
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        let cwd = std::env::current_dir().unwrap();
+        for output in [diff_image, similarity_map] {
+            let output = Path::new(output);
+            assert!(output.is_file());
+            assert_ne!(output.parent().unwrap(), cwd);
+            std::fs::remove_file(output).unwrap();
+        }
+    }
+
+    #[test]
+    fn exported_similarity_map_matches_image_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal_path = dir.path().join("similarity_map_nominal.png");
+        let actual_path = dir.path().join("similarity_map_actual.png");
+        let nominal = RgbaImage::from_fn(24, 16, |x, y| {
+            image::Rgba([(x * 8) as u8, (y * 8) as u8, 128, 255])
+        });
+        let actual = RgbaImage::from_fn(24, 16, |x, y| {
+            let blue = if x < 4 && y < 4 { 0 } else { 128 };
+            image::Rgba([(x * 8) as u8, (y * 8) as u8, blue, 255])
+        });
+        nominal.save(&nominal_path).unwrap();
+        actual.save(&actual_path).unwrap();
+
+        let mut config = ImageCompareConfig::from_threshold(1.0);
+        config.generate_diff_image = false;
+        config.export_similarity_map = true;
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(result.is_error);
+        let Some(DiffDetail::Image {
+            similarity_map: Some(similarity_map),
+            ..
+        }) = result.detail.first()
+        else {
+            panic!("no similarity map exported");
+        };
+        let grid = std::fs::read_to_string(similarity_map).unwrap();
+        std::fs::remove_file(similarity_map).unwrap();
+
+        let rows: Vec<Vec<f32>> = grid
+            .lines()
+            .map(|row| row.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 16);
+        assert!(rows.iter().all(|row| row.len() == 24 * 4));
+        assert!(rows.iter().flatten().all(|v| (0.0..=1.0).contains(v)));
+        assert!(rows[0][2] > 0.0);
+        assert_eq!(rows[15][23 * 4 + 2], 0.0);
+    }
+
+    #[test]
+    fn border_only_difference_passes_after_cropping() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal_path = dir.path().join("crop_border_nominal.png");
+        let actual_path = dir.path().join("crop_border_actual.png");
+        let nominal = RgbaImage::from_fn(32, 32, |x, y| {
+            image::Rgba([(x * 8) as u8, (y * 8) as u8, 128, 255])
+        });
+        let actual = RgbaImage::from_fn(32, 32, |x, y| {
+            if x < 2 || y < 2 || x >= 30 || y >= 30 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                *nominal.get_pixel(x, y)
+            }
+        });
+        nominal.save(&nominal_path).unwrap();
+        actual.save(&actual_path).unwrap();
+
+        let mut config = ImageCompareConfig::from_threshold(1.0);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(result.is_error);
+        if let Some(DiffDetail::Image { diff_image, .. }) = result.detail.first() {
+            std::fs::remove_file(diff_image.as_ref().unwrap()).unwrap();
+        }
+
+        config.crop_border = Some(2);
+        let result = compare_paths(&nominal_path, &actual_path, &config).unwrap();
+        assert!(!result.is_error);
+
+        config.crop_border = Some(16);
+        assert!(matches!(
+            compare_paths(&nominal_path, &actual_path, &config),
+            Err(Error::BorderTooLarge(16, 32, 32))
+        ));
+    }
 }
@@ -4,54 +4,437 @@ use itertools::Itertools;
 use regex::Regex;
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::path::Path;
-use tracing::error;
+use tracing::{debug, error, info};
 
-#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Default)]
 /// configuration for the json compare module
 pub struct JsonConfig {
     #[serde(default)]
     ignore_keys: Vec<String>,
     #[serde(default)]
     sort_arrays: bool,
+    /// list the paths suppressed by `ignore_keys` and `ignore_paths` in the report detail
+    #[serde(default)]
+    report_ignored_keys: bool,
+    /// compare arrays only up to the shorter length, trailing extra elements are ignored
+    #[serde(default)]
+    compare_array_prefix: bool,
+    /// numbers deviating at most by this absolute tolerance are equal, arrays are compared element-wise
+    #[serde(default)]
+    numeric_tolerance: Option<f64>,
+    /// match object keys ignoring their casing, keys only differing by casing within one object are reported as collision
+    #[serde(default)]
+    case_insensitive_keys: bool,
+    /// full paths of ignored values and everything below them, e.g. `.metadata.timestamp` or `.items[*].id` - `[*]` matches any array index
+    #[serde(default)]
+    ignore_paths: Vec<String>,
+    /// only compare these full paths and everything below them, all other differences are ignored - same syntax as `ignore_paths`
+    #[serde(default)]
+    only_paths: Option<Vec<String>>,
+    /// match the elements of arrays of objects by the value of this key instead of their index, e.g. `id`.
+    /// The elements are addressed as `[id=<value>]` in paths - arrays with an element lacking the key or with duplicate values are compared by index
+    #[serde(default)]
+    array_key: Option<String>,
+}
+
+/// Lowercases all object keys, returns the keys colliding with an already lowercased key of the same object.
+/// Of colliding keys only the first one is kept.
+fn lowercase_keys(value: &mut Value, path: &str) -> Vec<String> {
+    let mut collisions = Vec::new();
+    match value {
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                collisions.extend(lowercase_keys(value, &format!("{path}[{index}]")));
+            }
+        }
+        Value::Object(map) => {
+            let mut lowercased = serde_json::Map::new();
+            let mut original_keys: Vec<(String, String)> = Vec::new();
+            for (key, mut value) in std::mem::take(map) {
+                let lower = key.to_lowercase();
+                if let Some((_, existing)) = original_keys.iter().find(|(l, _)| *l == lower) {
+                    collisions.push(format!("{path}->'{existing}' and '{key}'"));
+                    continue;
+                }
+                collisions.extend(lowercase_keys(&mut value, &format!("{path}->{lower}")));
+                original_keys.push((lower.clone(), key));
+                lowercased.insert(lower, value);
+            }
+            *map = lowercased;
+        }
+        _ => {}
+    }
+    collisions
+}
+
+/// Replaces all actual numbers within tolerance of the nominal number at the same path by the nominal one,
+/// so only deviations beyond the tolerance are reported. Arrays are compared element by element,
+/// elements beyond the shorter length are left for the regular diff to report as extra.
+fn snap_numbers_within_tolerance(nominal: &Value, actual: &mut Value, tolerance: f64, path: &str) {
+    match (nominal, actual) {
+        (Value::Number(nominal_number), actual @ Value::Number(_)) => {
+            if let (Some(n), Some(a)) = (nominal_number.as_f64(), actual.as_f64()) {
+                if (n - a).abs() <= tolerance {
+                    *actual = Value::Number(nominal_number.clone());
+                }
+            }
+        }
+        (Value::Array(nominal), Value::Array(actual)) => {
+            if nominal.len() != actual.len() {
+                info!(
+                    "Array '{}' has {} elements in nominal and {} in actual, comparing the first {} element-wise",
+                    path,
+                    nominal.len(),
+                    actual.len(),
+                    nominal.len().min(actual.len())
+                );
+            }
+            for (index, (nominal, actual)) in nominal.iter().zip(actual.iter_mut()).enumerate() {
+                snap_numbers_within_tolerance(
+                    nominal,
+                    actual,
+                    tolerance,
+                    &format!("{path}[{index}]"),
+                );
+            }
+        }
+        (Value::Object(nominal), Value::Object(actual)) => {
+            for (key, nominal) in nominal.iter() {
+                if let Some(actual) = actual.get_mut(key) {
+                    snap_numbers_within_tolerance(
+                        nominal,
+                        actual,
+                        tolerance,
+                        &format!("{path}->{key}"),
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
 }
+
+/// Truncates all arrays found at the same path in both values to their common length
+fn truncate_arrays_to_prefix(nominal: &mut Value, actual: &mut Value, path: &str) {
+    match (nominal, actual) {
+        (Value::Array(nominal), Value::Array(actual)) => {
+            let common_length = nominal.len().min(actual.len());
+            if nominal.len() != actual.len() {
+                info!(
+                    "Ignoring {} extra trailing elements of array '{}' in {}",
+                    nominal.len().abs_diff(actual.len()),
+                    path,
+                    if nominal.len() > actual.len() {
+                        "nominal"
+                    } else {
+                        "actual"
+                    }
+                );
+            }
+            nominal.truncate(common_length);
+            actual.truncate(common_length);
+            for (index, (nominal, actual)) in nominal.iter_mut().zip(actual.iter_mut()).enumerate()
+            {
+                truncate_arrays_to_prefix(nominal, actual, &format!("{path}[{index}]"));
+            }
+        }
+        (Value::Object(nominal), Value::Object(actual)) => {
+            for (key, nominal) in nominal.iter_mut() {
+                if let Some(actual) = actual.get_mut(key) {
+                    truncate_arrays_to_prefix(nominal, actual, &format!("{path}->{key}"));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+/// The elements of the array keyed by the value of `key`, `None` if an element lacks it or a value is not unique
+fn keyed_elements(values: &[Value], key: &str) -> Option<serde_json::Map<String, Value>> {
+    let mut keyed = serde_json::Map::new();
+    for value in values {
+        let key_value = match value.get(key)? {
+            Value::String(key_value) => key_value.clone(),
+            key_value => key_value.to_string(),
+        };
+        if keyed
+            .insert(format!("[{key}={key_value}]"), value.clone())
+            .is_some()
+        {
+            return None;
+        }
+    }
+    Some(keyed)
+}
+
+/// Replaces all arrays of objects found at the same path in both values by objects holding the elements under
+/// the value of `key`, so elements are matched by key instead of index
+fn key_arrays_by(nominal: &mut Value, actual: &mut Value, key: &str, path: &str) {
+    if let (Value::Array(nominal_values), Value::Array(actual_values)) = (&*nominal, &*actual) {
+        match (
+            keyed_elements(nominal_values, key),
+            keyed_elements(actual_values, key),
+        ) {
+            (Some(nominal_keyed), Some(actual_keyed)) => {
+                *nominal = Value::Object(nominal_keyed);
+                *actual = Value::Object(actual_keyed);
+            }
+            _ => {
+                debug!(
+                    "Array '{}' is compared by index, not all elements have a unique '{}'",
+                    path, key
+                );
+            }
+        }
+    }
+    match (nominal, actual) {
+        (Value::Array(nominal), Value::Array(actual)) => {
+            for (index, (nominal, actual)) in nominal.iter_mut().zip(actual.iter_mut()).enumerate()
+            {
+                key_arrays_by(nominal, actual, key, &format!("{path}[{index}]"));
+            }
+        }
+        (Value::Object(nominal), Value::Object(actual)) => {
+            for (child, nominal) in nominal.iter_mut() {
+                if let Some(actual) = actual.get_mut(child) {
+                    key_arrays_by(nominal, actual, key, &format!("{path}->{child}"));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 impl JsonConfig {
     pub(crate) fn get_ignore_list(&self) -> Result<Vec<Regex>, regex::Error> {
         self.ignore_keys.iter().map(|v| Regex::new(v)).collect()
     }
+
+    /// The `ignore_paths` as regexes matching the path itself and all paths below it
+    pub(crate) fn get_ignore_path_list(&self) -> Result<Vec<(&str, Regex)>, regex::Error> {
+        path_regexes(&self.ignore_paths)
+    }
+
+    /// The `only_paths` as regexes matching the path itself and all paths below it, `None` if all paths are compared
+    pub(crate) fn get_only_path_list(&self) -> Result<Option<Vec<(&str, Regex)>>, regex::Error> {
+        self.only_paths.as_deref().map(path_regexes).transpose()
+    }
+}
+
+fn path_regexes(paths: &[String]) -> Result<Vec<(&str, Regex)>, regex::Error> {
+    paths
+        .iter()
+        .map(|path| {
+            let pattern = regex::escape(path).replace(r"\[\*\]", r"\[[^\]]+\]");
+            Ok((
+                path.as_str(),
+                Regex::new(&format!(r"^{pattern}(?:[.\[]|$)"))?,
+            ))
+        })
+        .collect()
+}
+
+/// Converts a key of `json_diff_ng` like `items->[l: 3]->id->` into a dotted path like `.items[3].id`
+fn dotted_path(key: &str) -> String {
+    let key = key.trim_end_matches("->");
+    if key.is_empty() {
+        return String::new();
+    }
+    key.split("->")
+        .map(|segment| {
+            let index = segment
+                .strip_prefix("[l: ")
+                .and_then(|rest| rest.split_once(']'))
+                .map(|(index, _)| index)
+                .filter(|index| index.chars().all(|c| c.is_ascii_digit()));
+            match index {
+                Some(index) => format!("[{index}]"),
+                None if segment.starts_with('[') => segment.to_owned(),
+                None => format!(".{segment}"),
+            }
+        })
+        .collect()
 }
 
 pub(crate) fn compare_files<P: AsRef<Path>>(
     nominal: P,
     actual: P,
     config: &JsonConfig,
+) -> Result<Difference, Error> {
+    compare_parsed_files(nominal, actual, config, "JSON", |content| {
+        serde_json::from_str::<Value>(content).map_err(|e| e.to_string())
+    })
+}
+
+/// Compares YAML files by converting both sides to JSON values, so all options of the JSON comparison apply
+pub(crate) fn compare_yaml_files<P: AsRef<Path>>(
+    nominal: P,
+    actual: P,
+    config: &JsonConfig,
+) -> Result<Difference, Error> {
+    compare_parsed_files(nominal, actual, config, "YAML", |content| {
+        serde_yaml::from_str::<Value>(content).map_err(|e| e.to_string())
+    })
+}
+
+fn compare_parsed_files<P: AsRef<Path>>(
+    nominal: P,
+    actual: P,
+    config: &JsonConfig,
+    format: &str,
+    parse: impl Fn(&str) -> Result<Value, String>,
 ) -> Result<Difference, Error> {
     let mut diff = Difference::new_for_file(&nominal, &actual);
     let compared_file_name = nominal.as_ref().to_string_lossy().into_owned();
 
     let nominal = vg_errortools::fat_io_wrap_std(&nominal, &std::fs::read_to_string)?;
     let actual = vg_errortools::fat_io_wrap_std(&actual, &std::fs::read_to_string)?;
-    let ignores = config.get_ignore_list()?;
 
-    let json_diff = json_diff::process::compare_jsons(&nominal, &actual, config.sort_arrays);
-    let json_diff = match json_diff {
-        Ok(diff) => diff,
+    let values = parse(&nominal).and_then(|nominal| Ok((nominal, parse(&actual)?)));
+    let (nominal, actual) = match values {
+        Ok(values) => values,
         Err(e) => {
             let error_message =
-                format!("JSON deserialization failed for {compared_file_name} (error: {e})");
+                format!("{format} deserialization failed for {compared_file_name} (error: {e})");
             error!("{}", error_message);
             diff.push_detail(DiffDetail::Error(error_message));
-            diff.error();
+            diff.fail();
             return Ok(diff);
         }
     };
+    compare_values(&nominal, &actual, config, &mut diff)?;
+    Ok(diff)
+}
+
+/// Compare two already parsed JSON values following the given config.
+/// The returned [`Difference`] has no file paths set.
+///
+/// ```
+/// use havocompare::{compare_json_values, DiffDetail, JsonConfig};
+/// use serde_json::json;
+///
+/// let nominal = json!({"name": "Keisuke", "age": 21});
+/// let actual = json!({"name": "Takumi", "age": 21, "car": "AE86"});
+/// let difference = compare_json_values(&nominal, &actual, &JsonConfig::default());
+/// assert!(difference.is_error);
+/// let Some(DiffDetail::Json { differences, left, right, .. }) = difference.detail.first() else {
+///     panic!("expected a json difference");
+/// };
+/// assert_eq!(differences.trim(), r#"name->{"Keisuke"!="Takumi"}"#);
+/// assert!(left.is_empty());
+/// assert_eq!(right.trim(), "car");
+///
+/// let identical = compare_json_values(&nominal, &nominal, &JsonConfig::default());
+/// assert!(!identical.is_error);
+/// assert!(identical.detail.is_empty());
+/// ```
+pub fn compare_json_values(nominal: &Value, actual: &Value, config: &JsonConfig) -> Difference {
+    let mut diff = Difference::default();
+    if let Err(e) = compare_values(nominal, actual, config, &mut diff) {
+        let error_message = format!("JSON comparison failed (error: {e})");
+        error!("{}", error_message);
+        diff.push_detail(DiffDetail::Error(error_message));
+        diff.fail();
+    }
+    diff
+}
+
+fn compare_values(
+    nominal: &Value,
+    actual: &Value,
+    config: &JsonConfig,
+    diff: &mut Difference,
+) -> Result<(), Error> {
+    let ignores = config.get_ignore_list()?;
+    let ignored_paths = config.get_ignore_path_list()?;
+    let only_paths = config.get_only_path_list()?;
+    let json_diff = if config.compare_array_prefix
+        || config.numeric_tolerance.is_some()
+        || config.case_insensitive_keys
+        || config.array_key.is_some()
+    {
+        let mut nominal = nominal.clone();
+        let mut actual = actual.clone();
+        if config.case_insensitive_keys {
+            for (value, side) in [(&mut nominal, "nominal"), (&mut actual, "actual")] {
+                for collision in lowercase_keys(value, "") {
+                    let error_message =
+                        format!("Keys only differing by casing collide in {side}: {collision}");
+                    error!("{}", error_message);
+                    diff.push_detail(DiffDetail::Error(error_message));
+                    diff.error();
+                }
+            }
+        }
+        if let Some(array_key) = config.array_key.as_deref() {
+            let array_key = if config.case_insensitive_keys {
+                array_key.to_lowercase()
+            } else {
+                array_key.to_owned()
+            };
+            key_arrays_by(&mut nominal, &mut actual, &array_key, "");
+        }
+        if config.compare_array_prefix {
+            truncate_arrays_to_prefix(&mut nominal, &mut actual, "");
+        }
+        if let Some(tolerance) = config.numeric_tolerance {
+            snap_numbers_within_tolerance(&nominal, &mut actual, tolerance, "");
+        }
+        json_diff::process::match_json(&nominal, &actual, config.sort_arrays)
+    } else {
+        json_diff::process::match_json(nominal, actual, config.sort_arrays)
+    };
+    let mut ignored = Vec::new();
     let filtered_diff: Vec<_> = json_diff
         .all_diffs()
         .into_iter()
-        .filter(|(_d, v)| !ignores.iter().any(|excl| excl.is_match(v.get_key())))
+        .filter(|(_d, v)| {
+            let key = v.get_key();
+            if let Some(excl) = ignores.iter().find(|excl| excl.is_match(key)) {
+                let path = key.trim_end_matches("->");
+                debug!("ignore pattern '{}' suppressed '{}'", excl.as_str(), path);
+                ignored.push(format!("{}: {}", excl.as_str(), path));
+                return false;
+            }
+            if ignored_paths.is_empty() && only_paths.is_none() {
+                return true;
+            }
+            let path = dotted_path(key);
+            if let Some((ignored_path, _)) = ignored_paths
+                .iter()
+                .find(|(_, regex)| regex.is_match(&path))
+            {
+                debug!("ignored path '{}' suppressed '{}'", ignored_path, path);
+                ignored.push(format!("{ignored_path}: {path}"));
+                return false;
+            }
+            match &only_paths {
+                Some(only_paths) if !only_paths.iter().any(|(_, regex)| regex.is_match(&path)) => {
+                    debug!("path '{}' is not listed in only_paths", path);
+                    false
+                }
+                _ => true,
+            }
+        })
         .collect();
+    let ignored = if config.report_ignored_keys {
+        ignored.join("\n")
+    } else {
+        String::new()
+    };
 
-    if !filtered_diff.is_empty() {
+    if filtered_diff.is_empty() {
+        if !ignored.is_empty() {
+            diff.push_detail(DiffDetail::Json {
+                differences: String::new(),
+                left: String::new(),
+                right: String::new(),
+                root_mismatch: None,
+                ignored,
+            });
+        }
+    } else {
         for (d_type, key) in filtered_diff.iter() {
             error!("{d_type}: {key}");
         }
@@ -95,12 +478,13 @@ pub(crate) fn compare_files<P: AsRef<Path>>(
             left,
             right,
             root_mismatch,
+            ignored,
         });
 
         diff.error();
     }
 
-    Ok(diff)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -113,10 +497,7 @@ mod test {
 
     #[test]
     fn no_filter() {
-        let cfg = JsonConfig {
-            ignore_keys: vec![],
-            sort_arrays: false,
-        };
+        let cfg = JsonConfig::default();
         let result = compare_files(
             "tests/integ/data/json/expected/guy.json",
             "tests/integ/data/json/actual/guy.json",
@@ -128,6 +509,7 @@ mod test {
             left,
             right,
             root_mismatch,
+            ..
         } = result.detail.first().unwrap()
         {
             let differences = trim_split(differences);
@@ -144,11 +526,66 @@ mod test {
         }
     }
 
+    #[test]
+    fn yaml_is_compared_like_json() {
+        let json = compare_files(
+            "tests/integ/data/json/expected/guy.json",
+            "tests/integ/data/json/actual/guy.json",
+            &JsonConfig::default(),
+        )
+        .unwrap();
+        let yaml = compare_yaml_files(
+            "tests/integ/data/yaml/expected/guy.yml",
+            "tests/integ/data/yaml/actual/guy.yml",
+            &JsonConfig::default(),
+        )
+        .unwrap();
+        assert!(yaml.is_error);
+        let (
+            DiffDetail::Json {
+                differences: json_differences,
+                left: json_left,
+                ..
+            },
+            DiffDetail::Json {
+                differences: yaml_differences,
+                left: yaml_left,
+                ..
+            },
+        ) = (json.detail.first().unwrap(), yaml.detail.first().unwrap())
+        else {
+            panic!("wrong diffdetail");
+        };
+        let mut json_differences = trim_split(json_differences);
+        let mut yaml_differences = trim_split(yaml_differences);
+        json_differences.sort();
+        yaml_differences.sort();
+        assert_eq!(json_differences, yaml_differences);
+        assert_eq!(json_left, yaml_left);
+    }
+
+    #[test]
+    fn malformed_yaml_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("broken.yml");
+        std::fs::write(&broken, "name: [unclosed").unwrap();
+        let result = compare_yaml_files(
+            broken.as_path(),
+            Path::new("tests/integ/data/yaml/actual/guy.yml"),
+            &JsonConfig::default(),
+        )
+        .unwrap();
+        assert!(result.is_error);
+        assert!(
+            matches!(result.detail.first(), Some(DiffDetail::Error(message)) if message.starts_with("YAML deserialization failed"))
+        );
+    }
+
     #[test]
     fn filter_works() {
         let cfg = JsonConfig {
             ignore_keys: vec!["name".to_string(), "brother(s?)".to_string()],
-            sort_arrays: false,
+            ..Default::default()
         };
         let result = compare_files(
             "tests/integ/data/json/expected/guy.json",
@@ -161,6 +598,7 @@ mod test {
             left,
             right,
             root_mismatch,
+            ..
         } = result.detail.first().unwrap()
         {
             let differences = trim_split(differences);
@@ -174,4 +612,253 @@ mod test {
             panic!("wrong diffdetail");
         }
     }
+
+    #[test]
+    fn over_broad_ignore_is_reported() {
+        let cfg = JsonConfig {
+            ignore_keys: vec!["a".to_string()],
+            report_ignored_keys: true,
+            ..Default::default()
+        };
+        let result = compare_files(
+            "tests/integ/data/json/expected/guy.json",
+            "tests/integ/data/json/actual/guy.json",
+            &cfg,
+        )
+        .unwrap();
+        if let DiffDetail::Json {
+            differences,
+            ignored,
+            ..
+        } = result.detail.first().unwrap()
+        {
+            assert!(differences.is_empty());
+            let ignored = trim_split(ignored);
+            assert!(ignored.contains(&"a: name"));
+            assert!(ignored.contains(&"a: age"));
+            assert!(ignored.contains(&"a: car"));
+            assert_eq!(ignored.len(), 3);
+        } else {
+            panic!("wrong diffdetail");
+        }
+        assert!(result.is_error);
+    }
+
+    #[test]
+    fn array_prefix_ignores_trailing_elements() {
+        let nominal = serde_json::json!({"pages": [1, 2, {"items": ["a"]}], "total": 3});
+        let actual = serde_json::json!({"pages": [1, 2, {"items": ["a", "b"]}, 4, 5], "total": 3});
+        let mut cfg = JsonConfig::default();
+        assert!(compare_json_values(&nominal, &actual, &cfg).is_error);
+
+        cfg.compare_array_prefix = true;
+        assert!(!compare_json_values(&nominal, &actual, &cfg).is_error);
+
+        let actual = serde_json::json!({"pages": [1, 3, {"items": ["a"]}, 4], "total": 3});
+        assert!(compare_json_values(&nominal, &actual, &cfg).is_error);
+    }
+
+    #[test]
+    fn numeric_arrays_within_tolerance() {
+        let nominal = serde_json::json!({"measurements": [1.0, 2.5, -3.25], "count": 3});
+        let actual = serde_json::json!({"measurements": [1.0004, 2.4999, -3.2501], "count": 3});
+        let mut cfg = JsonConfig::default();
+        assert!(compare_json_values(&nominal, &actual, &cfg).is_error);
+
+        cfg.numeric_tolerance = Some(0.001);
+        assert!(!compare_json_values(&nominal, &actual, &cfg).is_error);
+
+        let actual = serde_json::json!({"measurements": [1.0004, 2.6, -3.2501], "count": 3});
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        if let DiffDetail::Json { differences, .. } = result.detail.first().unwrap() {
+            assert_eq!(
+                trim_split(differences),
+                vec!["measurements->[l: 1]->{2.5!=2.6}"]
+            );
+        } else {
+            panic!("wrong diffdetail");
+        }
+
+        let actual = serde_json::json!({"measurements": [1.0004, 2.4999], "count": 3});
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        if let DiffDetail::Json {
+            differences, left, ..
+        } = result.detail.first().unwrap()
+        {
+            assert!(differences.is_empty());
+            assert!(left.contains("-3.25"));
+        } else {
+            panic!("wrong diffdetail");
+        }
+    }
+
+    #[test]
+    fn case_insensitive_keys_ignore_casing_but_report_collisions() {
+        let nominal = serde_json::json!({"UserId": 7, "Items": [{"Name": "a"}]});
+        let actual = serde_json::json!({"userId": 7, "items": [{"name": "a"}]});
+        let mut cfg = JsonConfig::default();
+        assert!(compare_json_values(&nominal, &actual, &cfg).is_error);
+
+        cfg.case_insensitive_keys = true;
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(!result.is_error);
+        assert!(result.detail.is_empty());
+
+        let actual = serde_json::json!({"userId": 7, "items": [{"name": "a", "NAME": "b"}]});
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        assert!(matches!(
+            result.detail.first(),
+            Some(DiffDetail::Error(message)) if message.contains("actual") && message.contains("items[0]->'name' and 'NAME'")
+        ));
+    }
+
+    #[test]
+    fn ignore_paths_only_match_full_paths() {
+        let nominal = serde_json::json!({
+            "id": 1,
+            "metadata": {"timestamp": "monday", "id": 5},
+            "items": [{"id": 10, "name": "a"}, {"id": 11, "name": "b"}]
+        });
+        let actual = serde_json::json!({
+            "id": 1,
+            "metadata": {"timestamp": "tuesday", "id": 6},
+            "items": [{"id": 20, "name": "a"}, {"id": 21, "name": "b"}, {"id": 22, "name": "c"}]
+        });
+        let mut cfg = JsonConfig {
+            ignore_paths: vec![".metadata.timestamp".to_owned(), ".items[*].id".to_owned()],
+            report_ignored_keys: true,
+            ..Default::default()
+        };
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        if let DiffDetail::Json {
+            differences,
+            ignored,
+            ..
+        } = result.detail.first().unwrap()
+        {
+            let mut differences = trim_split(differences);
+            differences.sort();
+            assert_eq!(
+                differences,
+                vec![
+                    "items->[l: 2]->{null!={\"id\":22,\"name\":\"c\"}}",
+                    "metadata->id->{5!=6}"
+                ]
+            );
+            let mut ignored = trim_split(ignored);
+            ignored.sort();
+            assert_eq!(
+                ignored,
+                vec![
+                    ".items[*].id: .items[0].id",
+                    ".items[*].id: .items[1].id",
+                    ".metadata.timestamp: .metadata.timestamp"
+                ]
+            );
+        } else {
+            panic!("wrong diffdetail");
+        }
+
+        cfg.ignore_paths = vec![".metadata".to_owned(), ".items".to_owned()];
+        assert!(!compare_json_values(&nominal, &actual, &cfg).is_error);
+        cfg.ignore_paths = vec![".meta".to_owned(), ".items[*].id".to_owned()];
+        assert!(compare_json_values(&nominal, &actual, &cfg).is_error);
+    }
+
+    #[test]
+    fn only_paths_restrict_comparison_to_listed_paths() {
+        let nominal = serde_json::json!({
+            "id": 1,
+            "metadata": {"timestamp": "monday", "id": 5},
+            "items": [{"id": 10, "name": "a"}, {"id": 11, "name": "b"}]
+        });
+        let actual = serde_json::json!({
+            "id": 1,
+            "metadata": {"timestamp": "tuesday", "id": 6},
+            "items": [{"id": 20, "name": "a"}, {"id": 21, "name": "x"}, {"id": 22, "name": "c"}]
+        });
+        let mut cfg = JsonConfig {
+            only_paths: Some(vec![".items[*].name".to_owned(), ".metadata.id".to_owned()]),
+            ..Default::default()
+        };
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        let Some(DiffDetail::Json { differences, .. }) = result.detail.first() else {
+            panic!("wrong diffdetail");
+        };
+        let mut differences = trim_split(differences);
+        differences.sort();
+        assert_eq!(
+            differences,
+            vec![
+                "items->[l: 1]->name->{\"b\"!=\"x\"}",
+                "metadata->id->{5!=6}"
+            ]
+        );
+
+        cfg.only_paths = Some(vec![".id".to_owned(), ".metadata.timestamp".to_owned()]);
+        cfg.ignore_paths = vec![".metadata.timestamp".to_owned()];
+        assert!(!compare_json_values(&nominal, &actual, &cfg).is_error);
+        cfg.only_paths = Some(vec![]);
+        assert!(!compare_json_values(&nominal, &actual, &cfg).is_error);
+    }
+
+    #[test]
+    fn array_key_matches_elements_by_key() {
+        let nominal = serde_json::json!({
+            "items": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}, {"id": 3, "name": "c"}],
+            "tags": ["x", "y"]
+        });
+        let actual = serde_json::json!({
+            "items": [{"id": 0, "name": "new"}, {"id": 1, "name": "a"}, {"id": 3, "name": "changed"}],
+            "tags": ["x", "y"]
+        });
+        let by_index = compare_json_values(&nominal, &actual, &JsonConfig::default());
+        let Some(DiffDetail::Json { differences, .. }) = by_index.detail.first() else {
+            panic!("wrong diffdetail");
+        };
+        assert!(trim_split(differences).len() > 1);
+
+        let mut cfg = JsonConfig {
+            array_key: Some("id".to_owned()),
+            ..Default::default()
+        };
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        assert!(result.is_error);
+        let Some(DiffDetail::Json {
+            differences,
+            left,
+            right,
+            ..
+        }) = result.detail.first()
+        else {
+            panic!("wrong diffdetail");
+        };
+        assert_eq!(
+            trim_split(differences),
+            vec!["items->[id=3]->name->{\"c\"!=\"changed\"}"]
+        );
+        assert_eq!(left, "items->[id=2]");
+        assert_eq!(right, "items->[id=0]");
+
+        cfg.ignore_paths = vec![".items[*].name".to_owned()];
+        let result = compare_json_values(&nominal, &actual, &cfg);
+        let Some(DiffDetail::Json { differences, .. }) = result.detail.first() else {
+            panic!("wrong diffdetail");
+        };
+        assert!(differences.is_empty());
+    }
+
+    #[test]
+    fn dotted_paths_of_json_diff_keys() {
+        assert_eq!(dotted_path("items->[l: 3]->id->"), ".items[3].id");
+        assert_eq!(dotted_path("items->[id=7]->name->"), ".items[id=7].name");
+        assert_eq!(dotted_path("[l: 0]->[l: 2]-\"c\""), "[0][2]");
+        assert_eq!(dotted_path("metadata->timestamp"), ".metadata.timestamp");
+        assert_eq!(dotted_path(""), "");
+    }
 }
@@ -14,31 +14,42 @@ pub mod csv;
 
 pub use csv::CSVCompareConfig;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+mod binary;
+pub use binary::BinaryConfig;
 mod hash;
 pub use hash::HashConfig;
 mod html;
 mod image;
-pub use crate::image::ImageCompareConfig;
+pub use crate::image::{ColorConversion, ColorSpace, ImageCompareConfig};
 mod external;
 mod pdf;
 mod properties;
+mod remote;
+pub use crate::remote::is_remote;
 mod report;
+mod sidecar;
 
 mod json;
-pub use crate::json::JsonConfig;
+pub use crate::json::{compare_json_values, JsonConfig};
 
 use crate::external::ExternalConfig;
-pub use crate::html::HTMLCompareConfig;
+pub use crate::html::{HTMLCompareConfig, TextSimilarityMetric};
 use crate::properties::PropertiesConfig;
-use crate::report::{DiffDetail, Difference};
+pub use crate::report::{
+    DiffDetail, Difference, ErrorKind, ExcludedFile, ReportFormat, RuleDifferences,
+};
+use data_encoding::HEXLOWER;
+use rayon::prelude::*;
 use schemars::schema_for;
 use schemars_derive::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tracing::{debug, error, info, span};
+use tracing::{debug, error, info, span, warn};
 use vg_errortools::{fat_io_wrap_std, FatIOError};
 
 #[derive(Error, Debug)]
@@ -66,6 +77,12 @@ pub enum Error {
     /// An error occurred during writing json
     #[error("Serde error, writing json: {0}")]
     SerdeJsonFail(#[from] serde_json::Error),
+    /// An error occurred during reading a json config
+    #[error("Serde error, loading a json: {0}")]
+    SerdeJsonConfigFail(serde_json::Error),
+    /// The config file extension is none of the supported formats
+    #[error("Unsupported config format of {0}, expected a .yml, .yaml or .json file")]
+    UnsupportedConfigFormat(String),
     /// A problem happened while accessing a file
     #[error("File access failed {0}")]
     FileAccessError(#[from] FatIOError),
@@ -77,6 +94,22 @@ pub enum Error {
     /// Different number of files matched pattern in actual and nominal
     #[error("Different number of files matched pattern in actual {0} and nominal {1}")]
     DifferentNumberOfFiles(usize, usize),
+
+    /// No files matched the patterns of a rule requiring matches
+    #[error("No files matched the patterns of rule {0}")]
+    NoMatchingFiles(String),
+
+    /// A file name exists more than once on one side while pairing files by name
+    #[error("File name of {0} is not unique, cannot pair files by name")]
+    AmbiguousPairing(String),
+
+    /// Fetching a remote nominal failed
+    #[error("Could not fetch remote nominal: {0}")]
+    RemoteNominalError(#[from] remote::Error),
+
+    /// An error occurred in the hash module, e.g. reading a manifest
+    #[error("Hash module error {0}")]
+    HashModuleError(#[from] hash::Error),
 }
 
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
@@ -91,6 +124,8 @@ pub enum ComparisonMode {
     PlainText(HTMLCompareConfig),
     /// Compare using file hashes
     Hash(HashConfig),
+    /// Compare byte by byte, reporting the first differing offset
+    Binary(BinaryConfig),
     /// PDF text compare
     PDFText(HTMLCompareConfig),
     /// Compare file-properties
@@ -99,6 +134,9 @@ pub enum ComparisonMode {
     /// Compare JSON files
     Json(JsonConfig),
 
+    /// Compare YAML files structurally, using the options of the JSON comparison
+    Yaml(JsonConfig),
+
     /// Run external comparison executable
     External(ExternalConfig),
 }
@@ -112,19 +150,171 @@ fn get_file_name(path: &Path) -> Option<Cow<str>> {
 pub struct ConfigurationFile {
     /// A list of all rules to be checked on run
     pub rules: Vec<Rule>,
+    /// Skip the module comparison for byte-identical files and record them as matching - optional, default is false.
+    /// Beware that this changes semantics for modules that intentionally normalize, e.g. a CSV with an illegal format.
+    #[serde(default)]
+    pub skip_identical_files: bool,
+    /// Skip malformed rules with a warning instead of failing to load the whole file - optional, default is false.
+    #[serde(default)]
+    pub lenient_rules: bool,
+    /// Write the report as folder, as `.tar.gz` archive next to the folder or both - optional, default is folder
+    #[serde(default)]
+    pub report_format: ReportFormat,
+    /// Additionally write `diffs_flat.json`, a flat list of all differences across rules - optional, default is false
+    #[serde(default)]
+    pub flat_diffs_json: bool,
+    /// Copy the compared CSV files into the report and render the CSV details from the copies - optional, default is false
+    #[serde(default)]
+    pub embed_csv_sources: bool,
+    /// Additionally write `report.json` to this exact path, e.g. for downstream tools - optional.
+    /// Not part of the config hash since it does not affect the results.
+    #[serde(default, skip_serializing)]
+    pub json_out: Option<PathBuf>,
+    /// Write a summary of the run in Prometheus text format to this path, e.g. for a node exporter - optional.
+    /// Not part of the config hash since it does not affect the results.
+    #[serde(default, skip_serializing)]
+    pub metrics_out: Option<PathBuf>,
+    /// Process the rules in parallel, the results are still reported in rule order - optional, default is false.
+    /// Not part of the config hash since it does not affect the results.
+    #[serde(default, skip_serializing)]
+    pub parallel_rules: bool,
+    /// List the files matching the include patterns of a rule which were not compared, with the reason, in the report - optional, default is false
+    #[serde(default)]
+    pub report_excluded_files: bool,
+    /// Inline the images of the image details as base64 into the html instead of copying them next to it,
+    /// making every detail page a single file at the cost of its size - optional, default is false
+    #[serde(default)]
+    pub inline_images: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The file formats a [`ConfigurationFile`] can be read from
+pub enum ConfigFormat {
+    /// YAML, the default
+    #[default]
+    Yaml,
+    /// JSON
+    Json,
+}
+
+impl ConfigFormat {
+    /// The format by file extension: `.json` is JSON, `.yml`, `.yaml` and no extension are YAML.
+    /// Other extensions, e.g. `.toml`, are rejected instead of being misread as YAML
+    pub fn from_path(path: impl AsRef<Path>) -> Result<ConfigFormat, Error> {
+        let path = path.as_ref();
+        let Some(extension) = path.extension() else {
+            return Ok(ConfigFormat::Yaml);
+        };
+        let extension = extension.to_string_lossy().to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "yml" | "yaml" => Ok(ConfigFormat::Yaml),
+            _ => Err(Error::UnsupportedConfigFormat(
+                path.to_string_lossy().to_string(),
+            )),
+        }
+    }
 }
 
 impl ConfigurationFile {
-    /// creates a [`ConfigurationFile`] file struct from anything implementing `Read`
+    /// creates a [`ConfigurationFile`] file struct from anything implementing `Read` providing YAML
     pub fn from_reader(reader: impl Read) -> Result<ConfigurationFile, Error> {
-        let config: ConfigurationFile = serde_yaml::from_reader(reader)?;
+        Self::from_reader_with_format(reader, ConfigFormat::Yaml)
+    }
+
+    /// creates a [`ConfigurationFile`] file struct from anything implementing `Read` providing the given format
+    pub fn from_reader_with_format(
+        reader: impl Read,
+        format: ConfigFormat,
+    ) -> Result<ConfigurationFile, Error> {
+        match format {
+            ConfigFormat::Yaml => Self::from_yaml_reader(reader),
+            ConfigFormat::Json => Self::from_json_reader(reader),
+        }
+    }
+
+    fn from_yaml_reader(reader: impl Read) -> Result<ConfigurationFile, Error> {
+        let mut value: serde_yaml::Value = serde_yaml::from_reader(reader)?;
+        let lenient = value
+            .get("lenient_rules")
+            .and_then(serde_yaml::Value::as_bool)
+            .unwrap_or(false);
+        if !lenient {
+            let config: ConfigurationFile = serde_yaml::from_value(value)?;
+            return Ok(config);
+        }
+
+        let rules = value
+            .as_mapping_mut()
+            .and_then(|m| m.insert("rules".into(), serde_yaml::Value::Sequence(Vec::new())));
+        let mut config: ConfigurationFile = serde_yaml::from_value(value)?;
+        if let Some(serde_yaml::Value::Sequence(rules)) = rules {
+            config.push_wellformed_rules(rules.into_iter().map(|rule| {
+                let rule_name = rule
+                    .get("name")
+                    .and_then(serde_yaml::Value::as_str)
+                    .map(str::to_owned);
+                (rule_name, serde_yaml::from_value::<Rule>(rule))
+            }));
+        }
+        Ok(config)
+    }
+
+    fn from_json_reader(reader: impl Read) -> Result<ConfigurationFile, Error> {
+        let mut value: serde_json::Value =
+            serde_json::from_reader(reader).map_err(Error::SerdeJsonConfigFail)?;
+        let lenient = value
+            .get("lenient_rules")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+        if !lenient {
+            return serde_json::from_value(value).map_err(Error::SerdeJsonConfigFail);
+        }
+
+        let rules = value
+            .as_object_mut()
+            .and_then(|m| m.insert("rules".into(), serde_json::Value::Array(Vec::new())));
+        let mut config: ConfigurationFile =
+            serde_json::from_value(value).map_err(Error::SerdeJsonConfigFail)?;
+        if let Some(serde_json::Value::Array(rules)) = rules {
+            config.push_wellformed_rules(rules.into_iter().map(|rule| {
+                let rule_name = rule
+                    .get("name")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_owned);
+                (rule_name, serde_json::from_value::<Rule>(rule))
+            }));
+        }
         Ok(config)
     }
 
-    /// creates a [`ConfigurationFile`] from anything path-convertible
+    /// Adds the rules that could be deserialized, malformed ones are skipped with a warning
+    fn push_wellformed_rules<E: std::fmt::Display>(
+        &mut self,
+        rules: impl Iterator<Item = (Option<String>, Result<Rule, E>)>,
+    ) {
+        for (index, (rule_name, rule)) in rules.enumerate() {
+            match rule {
+                Ok(rule) => self.rules.push(rule),
+                Err(e) => {
+                    let rule_name = rule_name.as_deref().unwrap_or("<unnamed>");
+                    warn!("Skipping malformed rule #{index} '{rule_name}': {e}")
+                }
+            }
+        }
+    }
+
+    /// A stable hash of the fully resolved configuration, identical configurations yield identical hashes
+    pub fn config_hash(&self) -> Result<String, Error> {
+        let resolved = serde_json::to_vec(self)?;
+        Ok(HEXLOWER.encode(&Sha256::digest(resolved)))
+    }
+
+    /// creates a [`ConfigurationFile`] from anything path-convertible, the format is chosen by [`ConfigFormat::from_path`]
     pub fn from_file(file: impl AsRef<Path>) -> Result<ConfigurationFile, Error> {
+        let format = ConfigFormat::from_path(file.as_ref())?;
         let config_reader = fat_io_wrap_std(file, &File::open)?;
-        Self::from_reader(BufReader::new(config_reader))
+        Self::from_reader_with_format(BufReader::new(config_reader), format)
     }
 }
 
@@ -137,11 +327,320 @@ pub struct Rule {
     pub pattern_include: Vec<String>,
     /// A list of glob-patterns to exclude - optional
     pub pattern_exclude: Option<Vec<String>>,
+    /// Maps nominal file names to actual file names before pairing them - optional
+    pub name_transform: Option<NameTransform>,
+    /// Only compare file pairs whose nominal file content matches this filter - optional
+    pub content_filter: Option<ContentFilter>,
+    /// Fail the rule if its patterns match no files at all - optional, default is false but recommended
+    #[serde(default)]
+    pub require_matches: bool,
+    /// Regex on the nominal path (relative to the nominal folder) grouping related files, e.g. a mesh and its sidecar.
+    /// The first capture group (or the whole match) is the group key. If one file of a group fails, the whole group fails - optional
+    pub group_by: Option<String>,
+    /// How the files found in nominal and actual are paired, ignored if `name_transform` is given - optional
+    #[serde(default)]
+    pub pairing: Pairing,
+    /// Fail every file pair whose actual file was not modified after its nominal counterpart,
+    /// e.g. to assert outputs were regenerated - optional, default is false
+    #[serde(default)]
+    pub require_newer_actual: bool,
+    /// Report empty directories matching the patterns, e.g. with `**/*`, which are missing on the other side.
+    /// Only applies to `RelativePath` and `SortedPaths` pairing without `name_transform` - optional, default is false
+    #[serde(default)]
+    pub compare_empty_directories: bool,
     /// How these files shall be compared
     #[serde(flatten)]
     pub file_type: ComparisonMode,
 }
 
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+/// How the files found in nominal and actual are paired for comparison
+pub enum Pairing {
+    /// Pair the files by their path relative to the nominal and actual folder.
+    /// Files missing on either side are reported as errors
+    #[default]
+    RelativePath,
+    /// Pair the files by the sorted order of their paths, the number of files must match
+    SortedPaths,
+    /// Pair the files by their file name only, ignoring the folder structure - e.g. for tools flattening their output.
+    /// Files missing on either side are reported as errors, fails if a file name is not unique on either side
+    Basename,
+    /// Pair the files by their relative path, but only compare the files present on both sides.
+    /// The files only present on one side are listed in the report without failing the rule
+    Intersection,
+}
+
+/// A failed difference for a file or directory without counterpart on the other side
+fn missing_counterpart(nominal: PathBuf, actual: PathBuf, message: String) -> Difference {
+    error!("{message}");
+    let mut difference = Difference::new_for_file(nominal, actual);
+    difference.push_detail(DiffDetail::Error(message));
+    difference.error();
+    difference
+}
+
+/// All directories without any entries below `root`, found by walking the tree explicitly
+fn empty_directories(root: &Path) -> Result<Vec<PathBuf>, FatIOError> {
+    let mut empty = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut has_entries = false;
+        for entry in fat_io_wrap_std(&dir, &std::fs::read_dir)? {
+            let path = entry
+                .map_err(|e| FatIOError::from_std_io_err(e, dir.clone()))?
+                .path();
+            has_entries = true;
+            if path.is_dir() {
+                pending.push(path);
+            }
+        }
+        if !has_entries && dir != root {
+            empty.push(dir);
+        }
+    }
+    empty.sort();
+    Ok(empty)
+}
+
+/// Reports empty directories matching the rule's patterns which do not exist on the other side
+fn compare_empty_directories(
+    nominal_root: &Path,
+    actual_root: &Path,
+    rule: &Rule,
+) -> Result<Vec<Difference>, Error> {
+    let patterns = |root: &Path, patterns: &[String]| -> Result<Vec<glob::Pattern>, Error> {
+        patterns
+            .iter()
+            .map(|p| Ok(glob::Pattern::new(&root.join(p).to_string_lossy())?))
+            .collect()
+    };
+    let matching_empty_directories = |root: &Path| -> Result<Vec<PathBuf>, Error> {
+        let include = patterns(root, &rule.pattern_include)?;
+        let exclude = patterns(root, rule.pattern_exclude.as_deref().unwrap_or_default())?;
+        Ok(empty_directories(root)?
+            .into_iter()
+            .filter(|dir| {
+                include.iter().any(|p| p.matches_path(dir))
+                    && !exclude.iter().any(|p| p.matches_path(dir))
+            })
+            .collect())
+    };
+
+    let mut unmatched = Vec::new();
+    for nominal_dir in matching_empty_directories(nominal_root)? {
+        let actual_dir = actual_root.join(
+            nominal_dir
+                .strip_prefix(nominal_root)
+                .unwrap_or(&nominal_dir),
+        );
+        if !actual_dir.is_dir() {
+            let message = format!(
+                "Actual directory {} is missing for empty nominal directory {}",
+                actual_dir.to_string_lossy(),
+                nominal_dir.to_string_lossy()
+            );
+            unmatched.push(missing_counterpart(nominal_dir, actual_dir, message));
+        }
+    }
+    for actual_dir in matching_empty_directories(actual_root)? {
+        let nominal_dir =
+            nominal_root.join(actual_dir.strip_prefix(actual_root).unwrap_or(&actual_dir));
+        if !nominal_dir.is_dir() {
+            let message = format!(
+                "Nominal directory {} is missing for empty actual directory {}",
+                nominal_dir.to_string_lossy(),
+                actual_dir.to_string_lossy()
+            );
+            unmatched.push(missing_counterpart(nominal_dir, actual_dir, message));
+        }
+    }
+    Ok(unmatched)
+}
+
+/// Nominal and actual paths of files, one entry per file pair
+type FilePairs = Vec<(PathBuf, PathBuf)>;
+
+/// Splits the files into pairs with the same path relative to their roots and the files of either side without a counterpart,
+/// the latter together with the path their counterpart would have
+fn split_by_relative_path(
+    nominal_root: &Path,
+    nominal_files: Vec<PathBuf>,
+    actual_root: &Path,
+    actual_files: Vec<PathBuf>,
+) -> (FilePairs, FilePairs, FilePairs) {
+    let relative = |root: &Path, file: &Path| file.strip_prefix(root).unwrap_or(file).to_path_buf();
+    let mut actual_by_path: BTreeMap<PathBuf, PathBuf> = actual_files
+        .into_iter()
+        .map(|file| (relative(actual_root, &file), file))
+        .collect();
+
+    let mut pairs = Vec::new();
+    let mut nominal_only = Vec::new();
+    for nominal_file in nominal_files {
+        let relative_path = relative(nominal_root, &nominal_file);
+        match actual_by_path.remove(&relative_path) {
+            Some(actual_file) => pairs.push((nominal_file, actual_file)),
+            None => nominal_only.push((nominal_file, actual_root.join(&relative_path))),
+        }
+    }
+    let actual_only = actual_by_path
+        .into_iter()
+        .map(|(relative_path, actual_file)| (nominal_root.join(relative_path), actual_file))
+        .collect();
+    (pairs, nominal_only, actual_only)
+}
+
+/// Failed differences for the files of either side without a counterpart
+fn missing_counterparts(nominal_only: FilePairs, actual_only: FilePairs) -> Vec<Difference> {
+    let mut unmatched = Vec::new();
+    for (nominal_file, actual_file) in nominal_only {
+        let message = format!(
+            "Actual file {} is missing for nominal {}",
+            actual_file.to_string_lossy(),
+            nominal_file.to_string_lossy()
+        );
+        unmatched.push(missing_counterpart(nominal_file, actual_file, message));
+    }
+    for (nominal_file, actual_file) in actual_only {
+        let message = format!(
+            "Nominal file {} is missing for actual {}",
+            nominal_file.to_string_lossy(),
+            actual_file.to_string_lossy()
+        );
+        unmatched.push(missing_counterpart(nominal_file, actual_file, message));
+    }
+    unmatched
+}
+
+/// Pairs the files by their path relative to the roots, files without counterpart are returned as failed differences
+fn pair_by_relative_path(
+    nominal_root: &Path,
+    nominal_files: Vec<PathBuf>,
+    actual_root: &Path,
+    actual_files: Vec<PathBuf>,
+) -> (Vec<(PathBuf, PathBuf)>, Vec<Difference>) {
+    let (pairs, nominal_only, actual_only) =
+        split_by_relative_path(nominal_root, nominal_files, actual_root, actual_files);
+    (pairs, missing_counterparts(nominal_only, actual_only))
+}
+
+/// Pairs the files by their file name, ignoring the folders. Files without counterpart are returned as failed differences,
+/// a file name existing more than once on either side is an error
+fn pair_by_basename(
+    nominal_root: &Path,
+    nominal_files: Vec<PathBuf>,
+    actual_root: &Path,
+    actual_files: Vec<PathBuf>,
+) -> Result<(FilePairs, Vec<Difference>), Error> {
+    let file_name = |file: &Path| {
+        file.file_name()
+            .map(|name| name.to_os_string())
+            .ok_or_else(|| Error::FilePathParsingFails(file.to_string_lossy().to_string()))
+    };
+    let mut actual_by_name = BTreeMap::new();
+    for actual_file in actual_files {
+        let name = file_name(&actual_file)?;
+        if let Some(previous) = actual_by_name.insert(name, actual_file) {
+            return Err(Error::AmbiguousPairing(
+                previous.to_string_lossy().to_string(),
+            ));
+        }
+    }
+
+    let mut nominal_names = BTreeSet::new();
+    let mut pairs = Vec::new();
+    let mut nominal_only = Vec::new();
+    for nominal_file in nominal_files {
+        let name = file_name(&nominal_file)?;
+        if !nominal_names.insert(name.clone()) {
+            return Err(Error::AmbiguousPairing(
+                nominal_file.to_string_lossy().to_string(),
+            ));
+        }
+        match actual_by_name.remove(&name) {
+            Some(actual_file) => {
+                debug!(
+                    "Paired {} with {}",
+                    nominal_file.to_string_lossy(),
+                    actual_file.to_string_lossy()
+                );
+                pairs.push((nominal_file, actual_file));
+            }
+            None => nominal_only.push((nominal_file, actual_root.join(&name))),
+        }
+    }
+    let actual_only = actual_by_name
+        .into_iter()
+        .map(|(name, actual_file)| (nominal_root.join(name), actual_file))
+        .collect();
+    Ok((pairs, missing_counterparts(nominal_only, actual_only)))
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+/// A regex replacement applied to the nominal file path (relative to the nominal folder) to get the actual file path
+pub struct NameTransform {
+    /// The regex matching (parts of) the nominal relative path
+    pub pattern: String,
+    /// The replacement, capture groups can be referenced like `${1}`
+    pub replacement: String,
+}
+
+impl NameTransform {
+    fn pair_files(
+        &self,
+        nominal_root: &Path,
+        nominal_files: Vec<PathBuf>,
+        actual_root: &Path,
+    ) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+        let regex = regex::Regex::new(&self.pattern)?;
+        nominal_files
+            .into_iter()
+            .map(|nominal_file| {
+                let relative_path = nominal_file.strip_prefix(nominal_root).map_err(|_| {
+                    Error::FilePathParsingFails(nominal_file.to_string_lossy().to_string())
+                })?;
+                let relative_path = relative_path.to_string_lossy();
+                let actual_file = actual_root.join(
+                    regex
+                        .replace(&relative_path, self.replacement.as_str())
+                        .as_ref(),
+                );
+                debug!(
+                    "Paired {} with {}",
+                    nominal_file.to_string_lossy(),
+                    actual_file.to_string_lossy()
+                );
+                Ok((nominal_file, actual_file))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+/// Selects files by a regex matched against the beginning of their content
+pub struct ContentFilter {
+    /// The regex to search for, e.g. a marker in the header line
+    pub pattern: String,
+    /// How many bytes from the start of the file are searched - optional, default is 1024
+    #[serde(default = "ContentFilter::default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl ContentFilter {
+    fn default_max_bytes() -> u64 {
+        1024
+    }
+
+    fn matches(&self, regex: &regex::Regex, file: &Path) -> Result<bool, FatIOError> {
+        let mut head = Vec::new();
+        fat_io_wrap_std(file, &File::open)?
+            .take(self.max_bytes)
+            .read_to_end(&mut head)
+            .map_err(|e| FatIOError::from_std_io_err(e, file.to_path_buf()))?;
+        Ok(regex.is_match(&String::from_utf8_lossy(&head)))
+    }
+}
+
 fn glob_files(
     path: impl AsRef<Path>,
     patterns: &[impl AsRef<str>],
@@ -193,6 +692,9 @@ pub fn compare_files(
             ComparisonMode::Hash(conf) => {
                 hash::compare_files(nominal.as_ref(), actual.as_ref(), conf).map_err(|e| e.into())
             }
+            ComparisonMode::Binary(conf) => {
+                binary::compare_files(nominal.as_ref(), actual.as_ref(), conf).map_err(|e| e.into())
+            }
             ComparisonMode::PDFText(conf) => {
                 pdf::compare_files(nominal.as_ref(), actual.as_ref(), conf).map_err(|e| e.into())
             }
@@ -207,6 +709,10 @@ pub fn compare_files(
             ComparisonMode::Json(conf) => {
                 json::compare_files(nominal.as_ref(), actual.as_ref(), conf).map_err(|e| e.into())
             }
+            ComparisonMode::Yaml(conf) => {
+                json::compare_yaml_files(nominal.as_ref(), actual.as_ref(), conf)
+                    .map_err(|e| e.into())
+            }
         }
     };
     let compare_result = match compare_result {
@@ -215,7 +721,7 @@ pub fn compare_files(
             let e = e.to_string();
             error!("Problem comparing the files {}", &e);
             let mut d = Difference::new_for_file(nominal, actual);
-            d.error();
+            d.fail();
             d.push_detail(DiffDetail::Error(e));
             d
         }
@@ -223,6 +729,8 @@ pub fn compare_files(
 
     if compare_result.is_error {
         error!("Files didn't match");
+    } else if compare_result.is_warning {
+        warn!("Files matched with warnings");
     } else {
         debug!("Files matched");
     }
@@ -230,6 +738,15 @@ pub fn compare_files(
     compare_result
 }
 
+fn files_are_identical(nominal: &Path, actual: &Path) -> Result<bool, FatIOError> {
+    let nominal_len = fat_io_wrap_std(nominal, &std::fs::metadata)?.len();
+    let actual_len = fat_io_wrap_std(actual, &std::fs::metadata)?.len();
+    if nominal_len != actual_len {
+        return Ok(false);
+    }
+    Ok(binary::first_difference(nominal, actual)?.is_none())
+}
+
 fn get_files(
     path: impl AsRef<Path>,
     patterns_include: &[impl AsRef<str>],
@@ -240,14 +757,137 @@ fn get_files(
     Ok(filter_exclude(files_include, files_exclude))
 }
 
+/// The files matching the include patterns of the rule which are skipped due to the exclude patterns or the content filter
+fn excluded_files(nominal: &Path, actual: &Path, rule: &Rule) -> Result<Vec<ExcludedFile>, Error> {
+    let exclude_patterns = rule.pattern_exclude.as_deref().unwrap_or_default();
+    let mut excluded = BTreeSet::new();
+    for root in [nominal, actual] {
+        let included = glob_files(root, &rule.pattern_include)?;
+        for pattern in exclude_patterns {
+            for file in glob_files(root, &[pattern])? {
+                if !file.is_dir() && included.contains(&file) {
+                    excluded.insert(ExcludedFile {
+                        relative_file_path: relative_path(&file, root),
+                        reason: format!("Matches exclude pattern '{pattern}'"),
+                    });
+                }
+            }
+        }
+    }
+    if let Some(filter) = &rule.content_filter {
+        let regex = regex::Regex::new(&filter.pattern)?;
+        for file in get_files(nominal, &rule.pattern_include, exclude_patterns)? {
+            if !file.is_dir() && !filter.matches(&regex, &file).unwrap_or(true) {
+                excluded.insert(ExcludedFile {
+                    relative_file_path: relative_path(&file, nominal),
+                    reason: format!("Content does not match filter '{}'", filter.pattern),
+                });
+            }
+        }
+    }
+    // a file is listed once, even if excluded by several patterns
+    let mut excluded: Vec<ExcludedFile> = excluded.into_iter().collect();
+    excluded.dedup_by(|a, b| a.relative_file_path == b.relative_file_path);
+    Ok(excluded)
+}
+
+fn relative_path(file: &Path, root: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn file_matches_patterns(file: &Path, rule: &Rule) -> Result<bool, glob::PatternError> {
+    let file_name = match file.file_name() {
+        Some(file_name) => Path::new(file_name),
+        None => return Ok(false),
+    };
+    let matches_any = |patterns: &[String]| -> Result<bool, glob::PatternError> {
+        for pattern in patterns {
+            if glob::Pattern::new(pattern)?.matches_path(file_name) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+    let excluded = match rule.pattern_exclude.as_deref() {
+        Some(exclude) => matches_any(exclude)?,
+        None => false,
+    };
+    Ok(!excluded && matches_any(&rule.pattern_include)?)
+}
+
+/// Compares two single files if the rule's patterns match the nominal file name
+fn process_rule_for_files(
+    nominal: &Path,
+    actual: &Path,
+    rule: &Rule,
+    skip_identical_files: bool,
+    compare_results: &mut Vec<Difference>,
+) -> Result<bool, Error> {
+    if !file_matches_patterns(nominal, rule)? {
+        debug!("Rule does not apply to file {}", nominal.to_string_lossy());
+        return Ok(true);
+    }
+    info!("Nominal and actual are files, comparing them directly");
+    let compare_result = compare_pair(nominal, actual, rule, skip_identical_files);
+    let okay = !compare_result.is_error;
+    compare_results.push(compare_result);
+    Ok(okay)
+}
+
+/// Compares a file pair with the rule's module, byte-identical files are only recorded if `skip_identical_files` is set
+fn compare_pair(
+    nominal: &Path,
+    actual: &Path,
+    rule: &Rule,
+    skip_identical_files: bool,
+) -> Difference {
+    let freshness = rule
+        .require_newer_actual
+        .then(|| properties::actual_newer_than_nominal(nominal, actual));
+    let mut compare_result = if skip_identical_files
+        && files_are_identical(nominal, actual).unwrap_or_else(|e| {
+            debug!("Could not check files for identity: {e}");
+            false
+        }) {
+        debug!(
+            "Skipping byte-identical files {} | {}",
+            nominal.to_string_lossy(),
+            actual.to_string_lossy()
+        );
+        Difference::new_for_file(nominal, actual)
+    } else {
+        compare_files(nominal, actual, &rule.file_type)
+    };
+    if let Some(freshness) = freshness {
+        compare_result.join(freshness);
+    }
+    compare_result
+}
+
+/// Compares the files matched by the rule, the files only present on one side with [`Pairing::Intersection`]
+/// are added to `one_sided` instead of being compared
 fn process_rule(
     nominal: impl AsRef<Path>,
     actual: impl AsRef<Path>,
     rule: &Rule,
+    skip_identical_files: bool,
     compare_results: &mut Vec<Difference>,
+    one_sided: &mut Vec<ExcludedFile>,
 ) -> Result<bool, Error> {
     let _file_span = span!(tracing::Level::INFO, "Rule").entered();
     info!("Name: {}", rule.name.as_str());
+    if nominal.as_ref().is_file() && actual.as_ref().is_file() {
+        return process_rule_for_files(
+            nominal.as_ref(),
+            actual.as_ref(),
+            rule,
+            skip_identical_files,
+            compare_results,
+        );
+    }
     if !nominal.as_ref().is_dir() {
         error!(
             "Nominal folder {} is not a folder",
@@ -265,9 +905,58 @@ fn process_rule(
 
     let exclude_patterns = rule.pattern_exclude.as_deref().unwrap_or_default();
 
-    let nominal_cleaned_paths =
-        get_files(nominal.as_ref(), &rule.pattern_include, exclude_patterns)?;
-    let actual_cleaned_paths = get_files(actual.as_ref(), &rule.pattern_include, exclude_patterns)?;
+    if let ComparisonMode::Hash(HashConfig {
+        manifest: Some(manifest),
+        function,
+    }) = &rule.file_type
+    {
+        let actual_files: Vec<_> =
+            get_files(actual.as_ref(), &rule.pattern_include, exclude_patterns)?
+                .into_iter()
+                .filter(|p| !p.is_dir())
+                .collect();
+        if rule.require_matches && actual_files.is_empty() {
+            return Err(Error::NoMatchingFiles(rule.name.clone()));
+        }
+        info!(
+            "Verifying {} actual files against manifest {}",
+            actual_files.len(),
+            manifest.to_string_lossy()
+        );
+        let differences = hash::verify_manifest(
+            &nominal.as_ref().join(manifest),
+            actual.as_ref(),
+            actual_files,
+            *function,
+        )?;
+        let all_okay = differences.iter().all(|d| !d.is_error);
+        compare_results.extend(differences);
+        return Ok(all_okay);
+    }
+
+    // directories are never compared as files, empty ones are checked for existence separately
+    let nominal_cleaned_paths: Vec<_> =
+        get_files(nominal.as_ref(), &rule.pattern_include, exclude_patterns)?
+            .into_iter()
+            .filter(|p| !p.is_dir())
+            .collect();
+    let actual_cleaned_paths: Vec<_> =
+        get_files(actual.as_ref(), &rule.pattern_include, exclude_patterns)?
+            .into_iter()
+            .filter(|p| !p.is_dir())
+            .collect();
+    // layouts paired by name or intersection intentionally differ in their directory structure
+    let compares_directories = rule.compare_empty_directories
+        && rule.name_transform.is_none()
+        && matches!(rule.pairing, Pairing::RelativePath | Pairing::SortedPaths);
+    if rule.compare_empty_directories && !compares_directories {
+        warn!("Ignoring compare_empty_directories, it requires relative path or sorted path pairing without name_transform");
+    }
+    let unmatched_directories = if compares_directories {
+        compare_empty_directories(nominal.as_ref(), actual.as_ref(), rule)?
+    } else {
+        Vec::new()
+    };
 
     info!(
         "Found {} files matching includes in actual, {} files in nominal",
@@ -277,23 +966,168 @@ fn process_rule(
     let actual_files = actual_cleaned_paths.len();
     let nominal_files = nominal_cleaned_paths.len();
 
-    if actual_files != nominal_files {
+    // pairing by path or name reports the files without counterpart instead
+    let pairs_by_name = rule.name_transform.is_none()
+        && matches!(
+            rule.pairing,
+            Pairing::RelativePath | Pairing::Intersection | Pairing::Basename
+        );
+    if actual_files != nominal_files && !pairs_by_name {
         return Err(Error::DifferentNumberOfFiles(actual_files, nominal_files));
     }
 
+    if rule.require_matches && nominal_files == 0 {
+        return Err(Error::NoMatchingFiles(rule.name.clone()));
+    }
+
+    let mut unmatched = Vec::new();
+    let pairs = match &rule.name_transform {
+        Some(transform) => {
+            transform.pair_files(nominal.as_ref(), nominal_cleaned_paths, actual.as_ref())?
+        }
+        None => match rule.pairing {
+            Pairing::RelativePath => {
+                let (pairs, missing) = pair_by_relative_path(
+                    nominal.as_ref(),
+                    nominal_cleaned_paths,
+                    actual.as_ref(),
+                    actual_cleaned_paths,
+                );
+                unmatched = missing;
+                pairs
+            }
+            Pairing::Intersection => {
+                let (pairs, nominal_only, actual_only) = split_by_relative_path(
+                    nominal.as_ref(),
+                    nominal_cleaned_paths,
+                    actual.as_ref(),
+                    actual_cleaned_paths,
+                );
+                info!(
+                    "Comparing {} files present on both sides, skipping {} nominal-only and {} actual-only files",
+                    pairs.len(),
+                    nominal_only.len(),
+                    actual_only.len()
+                );
+                let only_present_in = |file: &Path, root: &Path, side: &str| ExcludedFile {
+                    relative_file_path: relative_path(file, root),
+                    reason: format!("Only present in {side}"),
+                };
+                one_sided.extend(nominal_only.iter().map(|(nominal_file, _)| {
+                    only_present_in(nominal_file, nominal.as_ref(), "nominal")
+                }));
+                one_sided.extend(actual_only.iter().map(|(_, actual_file)| {
+                    only_present_in(actual_file, actual.as_ref(), "actual")
+                }));
+                one_sided.sort();
+                pairs
+            }
+            Pairing::SortedPaths => nominal_cleaned_paths
+                .into_iter()
+                .zip(actual_cleaned_paths)
+                .collect(),
+            Pairing::Basename => {
+                let (pairs, missing) = pair_by_basename(
+                    nominal.as_ref(),
+                    nominal_cleaned_paths,
+                    actual.as_ref(),
+                    actual_cleaned_paths,
+                )?;
+                unmatched = missing;
+                pairs
+            }
+        },
+    };
+
+    let pairs = match &rule.content_filter {
+        Some(filter) => {
+            let regex = regex::Regex::new(&filter.pattern)?;
+            pairs
+                .into_iter()
+                .filter(|(n, _)| {
+                    let selected = filter.matches(&regex, n).unwrap_or_else(|e| {
+                        debug!("Could not apply content filter: {e}");
+                        true
+                    });
+                    if !selected {
+                        info!("Skipping {} due to content filter", n.to_string_lossy());
+                    }
+                    selected
+                })
+                .collect()
+        }
+        None => pairs,
+    };
+
+    let group_keys = match &rule.group_by {
+        Some(group_by) => {
+            let regex = regex::Regex::new(group_by)?;
+            pairs
+                .iter()
+                .map(|(n, _)| {
+                    let relative_path = n.strip_prefix(nominal.as_ref()).unwrap_or(n);
+                    let relative_path = relative_path.to_string_lossy();
+                    regex.captures(&relative_path).and_then(|captures| {
+                        captures
+                            .get(1)
+                            .or_else(|| captures.get(0))
+                            .map(|key| key.as_str().to_owned())
+                    })
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let first_result = compare_results.len();
     let mut all_okay = true;
-    nominal_cleaned_paths
-        .into_iter()
-        .zip(actual_cleaned_paths)
-        .for_each(|(n, a)| {
-            let compare_result = compare_files(n, a, &rule.file_type);
-            all_okay &= !compare_result.is_error;
-            compare_results.push(compare_result);
-        });
+    pairs.into_iter().for_each(|(n, a)| {
+        let compare_result = compare_pair(&n, &a, rule, skip_identical_files);
+        all_okay &= !compare_result.is_error;
+        compare_results.push(compare_result);
+    });
+
+    if let Some(rule_results) = compare_results.get_mut(first_result..) {
+        fail_incomplete_groups(rule_results, &group_keys);
+    }
+
+    all_okay &= unmatched.is_empty() && unmatched_directories.is_empty();
+    compare_results.extend(unmatched);
+    compare_results.extend(unmatched_directories);
 
     Ok(all_okay)
 }
 
+/// Marks all files of a group as failed if any file of the group failed
+fn fail_incomplete_groups(results: &mut [Difference], group_keys: &[Option<String>]) {
+    let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, key) in group_keys.iter().enumerate() {
+        if let Some(key) = key {
+            groups.entry(key.as_str()).or_default().push(index);
+        }
+    }
+
+    for (key, members) in groups {
+        let failed: Vec<String> = members
+            .iter()
+            .filter_map(|i| results.get(*i))
+            .filter(|r| r.is_error)
+            .map(|r| r.relative_file_path.clone())
+            .collect();
+        if failed.is_empty() {
+            continue;
+        }
+        let message = format!("Group '{key}' failed due to: {}", failed.join(", "));
+        error!("{message}");
+        for index in members {
+            if let Some(result) = results.get_mut(index).filter(|r| !r.is_error) {
+                result.push_detail(DiffDetail::Error(message.clone()));
+                result.error();
+            }
+        }
+    }
+}
+
 /// Use this function if you don't want this crate to load and parse a config file but provide a custom rules struct yourself
 pub fn compare_folders_cfg(
     nominal: impl AsRef<Path>,
@@ -301,38 +1135,130 @@ pub fn compare_folders_cfg(
     config_struct: ConfigurationFile,
     report_path: impl AsRef<Path>,
 ) -> Result<bool, Error> {
-    let mut rule_results: Vec<report::RuleDifferences> = Vec::new();
+    compare_folders_cfg_detailed(nominal, actual, config_struct, report_path)
+        .map(|(all_okay, _)| all_okay)
+}
 
-    let results: Vec<bool> = config_struct
-        .rules
-        .into_iter()
-        .map(|rule| {
-            let mut compare_results: Vec<Difference> = Vec::new();
-            let okay = process_rule(
-                nominal.as_ref(),
-                actual.as_ref(),
-                &rule,
-                &mut compare_results,
-            );
+/// Like [`compare_folders_cfg`] but additionally returns the differences found per rule, as written to the report
+pub fn compare_folders_cfg_detailed(
+    nominal: impl AsRef<Path>,
+    actual: impl AsRef<Path>,
+    config_struct: ConfigurationFile,
+    report_path: impl AsRef<Path>,
+) -> Result<(bool, Vec<RuleDifferences>), Error> {
+    let started = std::time::Instant::now();
+    let remote_cache = is_remote(nominal.as_ref())
+        .then(tempfile::tempdir)
+        .transpose()
+        .map_err(|e| FatIOError::from_std_io_err(e, std::env::temp_dir()))?;
+    let nominal = match &remote_cache {
+        Some(cache) => remote::fetch_nominal(&nominal.as_ref().to_string_lossy(), cache.path())?,
+        None => nominal.as_ref().to_path_buf(),
+    };
+    let actual = actual.as_ref();
+    let skip_identical_files = config_struct.skip_identical_files;
+    let report_excluded_files = config_struct.report_excluded_files;
+    let report_format = config_struct.report_format;
+    let report_options = report::ReportOptions {
+        flat_diffs: config_struct.flat_diffs_json,
+        embed_csv_sources: config_struct.embed_csv_sources,
+        inline_images: config_struct.inline_images,
+        config_hash: config_struct.config_hash()?,
+        json_out: config_struct.json_out.clone(),
+    };
 
-            let rule_name = rule.name.as_str();
+    let rules = sidecar::resolve_rules(&nominal, config_struct.rules)?;
+    let compare_rule = |sidecar::ScopedRule { folder, rule }| {
+        let mut compare_results: Vec<Difference> = Vec::new();
+        let mut one_sided = Vec::new();
+        // joining an empty folder would append a separator, breaking single file comparisons
+        let scoped = |root: &Path| match folder.as_os_str().is_empty() {
+            true => root.to_path_buf(),
+            false => root.join(&folder),
+        };
+        let okay = process_rule(
+            scoped(&nominal),
+            scoped(actual),
+            &rule,
+            skip_identical_files,
+            &mut compare_results,
+            &mut one_sided,
+        );
+
+        let rule_name = rule.name.as_str();
 
-            let result = okay.unwrap_or_else(|e| {
-                println!("Error occurred during rule-processing for rule {rule_name}: {e}");
-                false
-            });
-            rule_results.push(report::RuleDifferences {
+        let result = okay.unwrap_or_else(|e| {
+            println!("Error occurred during rule-processing for rule {rule_name}: {e}");
+            false
+        });
+        let excluded = if report_excluded_files {
+            excluded_files(&scoped(&nominal), &scoped(actual), &rule).unwrap_or_else(|e| {
+                warn!("Could not list the excluded files of rule {rule_name}: {e}");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        (
+            result,
+            RuleDifferences {
                 rule,
                 diffs: compare_results,
-            });
-
-            result
-        })
-        .collect();
+                excluded,
+                one_sided,
+            },
+        )
+    };
+    // collecting a parallel iterator keeps the rule order
+    let (results, rule_results): (Vec<bool>, Vec<RuleDifferences>) = if config_struct.parallel_rules
+    {
+        rules.into_par_iter().map(compare_rule).unzip()
+    } else {
+        rules.into_iter().map(compare_rule).unzip()
+    };
 
     let all_okay = results.iter().all(|result| *result);
-    report::create_reports(&rule_results, &report_path)?;
-    Ok(all_okay)
+    report::create_reports(&rule_results, &report_path, &report_options)?;
+    report::archive_report(&report_path, report_format)?;
+    if let Some(metrics_out) = config_struct.metrics_out.as_deref() {
+        let summary = report::Summary::new(&rule_results, &results);
+        report::write_metrics(&summary, started.elapsed(), metrics_out)?;
+    }
+    Ok((all_okay, rule_results))
+}
+
+/// Accepts the actual files as new baseline: copies the actual file over the nominal one for every failed comparison.
+/// Nominal files missing in actual are kept. Returns the updated nominal files
+pub fn update_baseline(rule_results: &[RuleDifferences]) -> Result<Vec<PathBuf>, Error> {
+    let failed_files: BTreeSet<(&Path, &Path)> = rule_results
+        .iter()
+        .flat_map(|r| r.diffs.iter())
+        .filter(|d| d.is_error)
+        .map(|d| (d.nominal_file.as_path(), d.actual_file.as_path()))
+        .collect();
+    let mut updated = Vec::new();
+    for (nominal, actual) in failed_files {
+        if !actual.is_file() {
+            warn!(
+                "Keeping nominal {}, actual {} is no file",
+                nominal.to_string_lossy(),
+                actual.to_string_lossy()
+            );
+            continue;
+        }
+        if let Some(parent) = nominal.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fat_io_wrap_std(parent, &std::fs::create_dir_all)?;
+        }
+        std::fs::copy(actual, nominal)
+            .map_err(|e| FatIOError::from_std_io_err(e, nominal.to_path_buf()))?;
+        info!(
+            "Updated nominal {} from {}",
+            nominal.to_string_lossy(),
+            actual.to_string_lossy()
+        );
+        updated.push(nominal.to_path_buf());
+    }
+    Ok(updated)
 }
 
 /// The main function for comparing folders. It will parse a config file in yaml format, create a report in report_path and compare the folders nominal and actual.
@@ -383,13 +1309,36 @@ mod tests {
     fn folder_not_found_is_false() {
         let rule = Rule {
             name: "test rule".to_string(),
-            file_type: ComparisonMode::Image(ImageCompareConfig { threshold: 1.0 }),
+            file_type: ComparisonMode::Image(ImageCompareConfig::from_threshold(1.0)),
             pattern_include: vec!["*.".to_string()],
             pattern_exclude: None,
+            name_transform: None,
+            content_filter: None,
+            require_matches: false,
+            group_by: None,
+            pairing: Pairing::default(),
+            require_newer_actual: false,
+            compare_empty_directories: false,
         };
         let mut result = Vec::new();
-        assert!(!process_rule("NOT_EXISTING", ".", &rule, &mut result).unwrap());
-        assert!(!process_rule(".", "NOT_EXISTING", &rule, &mut result).unwrap());
+        assert!(!process_rule(
+            "NOT_EXISTING",
+            ".",
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert!(!process_rule(
+            ".",
+            "NOT_EXISTING",
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -414,4 +1363,1205 @@ mod tests {
             get_files("tests/csv/data/", &pattern_include, &excludes).expect("could not glob");
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn identical_files_skip_module_comparison() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Broken external checker"
+pattern_include:
+  - "**/*.json"
+External:
+  executable: "non_existent"
+  extra_params: []
+"#,
+        )
+        .unwrap();
+        let folder = "tests/integ/data/json/expected/";
+
+        let mut result = Vec::new();
+        assert!(!process_rule(folder, folder, &rule, false, &mut result, &mut Vec::new()).unwrap());
+        assert!(result.iter().all(|r| r.is_error));
+
+        let mut result = Vec::new();
+        assert!(process_rule(folder, folder, &rule, true, &mut result, &mut Vec::new()).unwrap());
+        assert!(!result.is_empty());
+        assert!(result.iter().all(|r| !r.is_error && r.detail.is_empty()));
+
+        let file = "tests/integ/data/json/expected/guy.json";
+        let mut result = Vec::new();
+        assert!(!process_rule(file, file, &rule, false, &mut result, &mut Vec::new()).unwrap());
+        let mut result = Vec::new();
+        assert!(process_rule(file, file, &rule, true, &mut result, &mut Vec::new()).unwrap());
+        assert_eq!(result.len(), 1);
+        assert!(result[0].detail.is_empty());
+    }
+
+    #[test]
+    fn identical_files_detection() {
+        let nominal =
+            Path::new("tests/integ/data/images/expected/SaveImage_100DPI_default_size.jpg");
+        let actual = Path::new("tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg");
+        assert!(files_are_identical(nominal, nominal).unwrap());
+        assert!(!files_are_identical(nominal, actual).unwrap());
+        assert!(files_are_identical(nominal, Path::new("NOT_EXISTING")).is_err());
+    }
+
+    #[test]
+    fn name_transform_pairs_files() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Suffixed csv files"
+pattern_include:
+  - "*.csv"
+name_transform:
+  pattern: "_expected\\.csv$"
+  replacement: "_actual.csv"
+CSV:
+  comparison_modes:
+    - Absolute: 0.0
+"#,
+        )
+        .unwrap();
+        let nominal = "tests/integ/data/name_transform/expected/";
+        let actual = "tests/integ/data/name_transform/actual/";
+
+        let mut result = Vec::new();
+        assert!(process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap());
+        assert_eq!(result.len(), 2);
+        assert!(result
+            .iter()
+            .all(|r| r.actual_file.to_string_lossy().ends_with("_actual.csv")));
+
+        // without transform no relative path matches, all four files are reported as missing their counterpart
+        let rule = Rule {
+            name_transform: None,
+            ..rule
+        };
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap()
+        );
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().all(|r| r.is_error
+            && matches!(&r.detail[..], [DiffDetail::Error(message)] if message.contains("is missing for"))));
+
+        // the former sorted order pairs foo_bar_expected.csv with foo_actual.csv
+        let rule = Rule {
+            pairing: Pairing::SortedPaths,
+            ..rule
+        };
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap()
+        );
+        assert!(result[0].nominal_file.ends_with("foo_bar_expected.csv"));
+        assert!(result[0].actual_file.ends_with("foo_actual.csv"));
+    }
+
+    #[test]
+    fn lenient_config_skips_malformed_rules() {
+        let config = r#"
+rules:
+  - name: "Broken"
+    pattern_include:
+      - "*.json"
+    Json:
+      sort_arrays: "not a bool"
+  - name: "Valid"
+    pattern_include:
+      - "*.json"
+    Json:
+      ignore_keys: []
+"#;
+        assert!(ConfigurationFile::from_reader(config.as_bytes()).is_err());
+
+        let lenient_config = format!("lenient_rules: true\n{config}");
+        let config = ConfigurationFile::from_reader(lenient_config.as_bytes()).unwrap();
+        assert_eq!(config.rules.len(), 1);
+        let rule = config.rules.first().unwrap();
+        assert_eq!(rule.name, "Valid");
+
+        let folder = "tests/integ/data/json/expected/";
+        let mut result = Vec::new();
+        assert!(process_rule(folder, folder, rule, false, &mut result, &mut Vec::new()).unwrap());
+        assert!(!result.is_empty());
+    }
+
+    #[test]
+    fn json_config_round_trips_yaml_config() {
+        let yaml = r#"
+skip_identical_files: true
+rules:
+  - name: "CSV"
+    pattern_include:
+      - "**/*.csv"
+    CSV:
+      comparison_modes:
+        - Absolute: 0.5
+      preprocessing:
+        - ExtractHeaders
+        - DeleteCellByName:
+            column: "Time"
+            row: 0
+        - TransformColumn:
+            column: "Radius"
+            scale: 2.0
+  - name: "Images"
+    pattern_include:
+      - "**/*.png"
+    Image:
+      threshold: 0.9
+"#;
+        let from_yaml = ConfigurationFile::from_reader(yaml.as_bytes()).unwrap();
+        let json = serde_json::to_string_pretty(&from_yaml).unwrap();
+        let from_json =
+            ConfigurationFile::from_reader_with_format(json.as_bytes(), ConfigFormat::Json)
+                .unwrap();
+        assert_eq!(from_json.rules.len(), 2);
+        assert!(from_json.skip_identical_files);
+        assert_eq!(
+            from_yaml.config_hash().unwrap(),
+            from_json.config_hash().unwrap()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in [("config.json", json.as_str()), ("config.YAML", yaml)] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            let from_file = ConfigurationFile::from_file(&path).unwrap();
+            assert_eq!(
+                from_file.config_hash().unwrap(),
+                from_yaml.config_hash().unwrap()
+            );
+        }
+        assert_eq!(
+            ConfigFormat::from_path("config.yml").unwrap(),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path("config.Json").unwrap(),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path("config").unwrap(),
+            ConfigFormat::Yaml
+        );
+        let toml = dir.path().join("config.toml");
+        std::fs::write(&toml, yaml).unwrap();
+        assert!(matches!(
+            ConfigurationFile::from_file(&toml),
+            Err(Error::UnsupportedConfigFormat(_))
+        ));
+
+        let yaml_as_json =
+            ConfigurationFile::from_reader_with_format(yaml.as_bytes(), ConfigFormat::Json);
+        assert!(matches!(yaml_as_json, Err(Error::SerdeJsonConfigFail(_))));
+    }
+
+    #[test]
+    fn lenient_json_config_skips_malformed_rules() {
+        let config = r#"{
+            "lenient_rules": true,
+            "rules": [
+                {"name": "Broken", "pattern_include": ["*.json"], "Json": {"sort_arrays": "not a bool"}},
+                {"name": "Valid", "pattern_include": ["*.json"], "Json": {"ignore_keys": []}}
+            ]
+        }"#;
+        let config =
+            ConfigurationFile::from_reader_with_format(config.as_bytes(), ConfigFormat::Json)
+                .unwrap();
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].name, "Valid");
+    }
+
+    #[test]
+    fn content_filter_selects_files() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Volume csv files"
+pattern_include:
+  - "*.csv"
+content_filter:
+  pattern: "Volume"
+CSV:
+  comparison_modes:
+    - Absolute: 0.0
+"#,
+        )
+        .unwrap();
+        let nominal = "tests/integ/data/content_filter/expected/";
+        let actual = "tests/integ/data/content_filter/actual/";
+
+        let mut result = Vec::new();
+        assert!(process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap());
+        assert_eq!(result.len(), 1);
+        assert!(result[0].nominal_file.ends_with("volumes.csv"));
+
+        let rule = Rule {
+            content_filter: None,
+            ..rule
+        };
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap()
+        );
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn require_matches_fails_on_empty_match() {
+        let mut rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Typo in pattern"
+pattern_include:
+  - "**/*.jsno"
+Json:
+  ignore_keys: []
+"#,
+        )
+        .unwrap();
+        let folder = "tests/integ/data/json/expected/";
+
+        let mut result = Vec::new();
+        assert!(process_rule(folder, folder, &rule, false, &mut result, &mut Vec::new()).unwrap());
+        assert!(result.is_empty());
+
+        rule.require_matches = true;
+        assert!(matches!(
+            process_rule(folder, folder, &rule, false, &mut result, &mut Vec::new()),
+            Err(Error::NoMatchingFiles(_))
+        ));
+
+        rule.pattern_include = vec!["**/*.json".to_string()];
+        assert!(process_rule(folder, folder, &rule, false, &mut result, &mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn single_files_are_compared_directly() {
+        let config: ConfigurationFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: "JSON"
+    pattern_include:
+      - "**/*.json"
+    Json:
+      ignore_keys: []
+  - name: "CSV"
+    pattern_include:
+      - "**/*.csv"
+    CSV:
+      comparison_modes:
+        - Absolute: 0.0
+"#,
+        )
+        .unwrap();
+        let nominal = "tests/integ/data/json/expected/guy.json";
+        let actual = "tests/integ/data/json/actual/guy.json";
+
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            nominal,
+            actual,
+            &config.rules[0],
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 1);
+        assert!(process_rule(
+            nominal,
+            actual,
+            &config.rules[1],
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 1);
+
+        let report_dir = tempfile::tempdir().unwrap();
+        assert!(!compare_folders_cfg(nominal, actual, config, &report_dir).unwrap());
+        let config: ConfigurationFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: "JSON"
+    pattern_include:
+      - "*.json"
+    Json:
+      ignore_keys: []
+"#,
+        )
+        .unwrap();
+        assert!(compare_folders_cfg(nominal, nominal, config, &report_dir).unwrap());
+    }
+
+    #[test]
+    fn failing_group_member_fails_whole_group() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Meshes with sidecars"
+pattern_include:
+  - "*"
+group_by: "^([^.]+)\\."
+Hash:
+  function: Sha256
+"#,
+        )
+        .unwrap();
+        let nominal = "tests/integ/data/groups/expected/";
+        let actual = "tests/integ/data/groups/actual/";
+
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap()
+        );
+        assert_eq!(result.len(), 4);
+        let failed: Vec<_> = result
+            .iter()
+            .filter(|r| r.is_error)
+            .map(|r| r.relative_file_path.as_str())
+            .collect();
+        assert_eq!(failed, vec!["part1.json", "part1.stl"]);
+
+        let rule = Rule {
+            group_by: None,
+            ..rule
+        };
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(nominal, actual, &rule, false, &mut result, &mut Vec::new()).unwrap()
+        );
+        assert_eq!(result.iter().filter(|r| r.is_error).count(), 1);
+    }
+
+    #[test]
+    fn parallel_rules_report_in_rule_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        std::fs::create_dir(&nominal).unwrap();
+        std::fs::create_dir(&actual).unwrap();
+        let mut rules = String::from("rules:\n");
+        for index in 0..32 {
+            let content: String = (0..500).map(|row| format!("{row};{index}.5\n")).collect();
+            std::fs::write(nominal.join(format!("{index}.csv")), &content).unwrap();
+            let changed = content.replace("499;", "499.5;");
+            let actual_content = if index % 5 == 0 { &changed } else { &content };
+            std::fs::write(actual.join(format!("{index}.csv")), actual_content).unwrap();
+            rules.push_str(&format!(
+                "  - name: \"Rule {index}\"\n    pattern_include: [\"{index}.csv\"]\n    CSV:\n      comparison_modes: [Absolute: 0.0]\n"
+            ));
+        }
+
+        let run = |parallel_rules: bool| {
+            let mut config = ConfigurationFile::from_reader(rules.as_bytes()).unwrap();
+            config.parallel_rules = parallel_rules;
+            let report_dir = tempfile::tempdir().unwrap();
+            let (all_okay, rule_results) =
+                compare_folders_cfg_detailed(&nominal, &actual, config, &report_dir).unwrap();
+            let verdicts: Vec<(String, bool)> = rule_results
+                .into_iter()
+                .map(|r| (r.rule.name, r.diffs.iter().any(|d| d.is_error)))
+                .collect();
+            (all_okay, verdicts)
+        };
+        let (sequential_okay, sequential) = run(false);
+        let (parallel_okay, parallel) = run(true);
+        assert!(!sequential_okay && !parallel_okay);
+        assert_eq!(sequential.len(), 32);
+        assert_eq!(sequential, parallel);
+        assert_eq!(sequential[1], ("Rule 1".to_string(), false));
+        assert_eq!(sequential[5], ("Rule 5".to_string(), true));
+    }
+
+    #[test]
+    fn excluded_files_are_listed_in_report() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in [
+            ("result.csv", "kind;result\n1;2\n"),
+            ("skip_me.csv", "kind;result\n1;2\n"),
+            ("log.csv", "kind;log\n1;2\n"),
+        ] {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+        let config = r#"
+report_excluded_files: true
+rules:
+  - name: "Results"
+    pattern_include:
+      - "*.csv"
+    pattern_exclude:
+      - "skip_*"
+    content_filter:
+      pattern: "result"
+    Hash:
+      function: Sha256
+"#;
+        let config = ConfigurationFile::from_reader(config.as_bytes()).unwrap();
+        let report_dir = tempfile::tempdir().unwrap();
+        let (all_okay, rule_results) =
+            compare_folders_cfg_detailed(dir.path(), dir.path(), config, &report_dir).unwrap();
+        assert!(all_okay);
+        assert_eq!(rule_results[0].diffs.len(), 1);
+        assert_eq!(
+            rule_results[0].excluded,
+            vec![
+                ExcludedFile {
+                    relative_file_path: "log.csv".to_owned(),
+                    reason: "Content does not match filter 'result'".to_owned(),
+                },
+                ExcludedFile {
+                    relative_file_path: "skip_me.csv".to_owned(),
+                    reason: "Matches exclude pattern 'skip_*'".to_owned(),
+                },
+            ]
+        );
+
+        let index = std::fs::read_to_string(report_dir.path().join("index.html")).unwrap();
+        assert!(index.contains("Excluded files"));
+        assert!(index.contains("skip_me.csv"));
+        assert!(index.contains("Matches exclude pattern"));
+        let json = std::fs::read_to_string(report_dir.path().join("report.json")).unwrap();
+        assert!(json.contains("\"excluded\""));
+    }
+
+    #[test]
+    fn intersection_pairing_lists_one_sided_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        for (path, content) in [
+            ("nominal/shared.txt", "same"),
+            ("nominal/changed.txt", "before"),
+            ("nominal/old/gone.txt", "gone"),
+            ("actual/shared.txt", "same"),
+            ("actual/changed.txt", "after"),
+            ("actual/new.txt", "new"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let config = |exclude: &str| {
+            let config = format!(
+                r#"
+rules:
+  - name: "Explore"
+    pattern_include:
+      - "**/*.txt"
+    pattern_exclude: [{exclude}]
+    pairing: Intersection
+    Hash:
+      function: Sha256
+"#
+            );
+            ConfigurationFile::from_reader(config.as_bytes()).unwrap()
+        };
+        let report_dir = tempfile::tempdir().unwrap();
+        let (all_okay, rule_results) =
+            compare_folders_cfg_detailed(&nominal, &actual, config("changed.txt"), &report_dir)
+                .unwrap();
+        assert!(all_okay);
+        assert_eq!(rule_results[0].diffs.len(), 1);
+        assert_eq!(rule_results[0].diffs[0].relative_file_path, "shared.txt");
+        assert!(rule_results[0].excluded.is_empty());
+        assert_eq!(
+            rule_results[0].one_sided,
+            vec![
+                ExcludedFile {
+                    relative_file_path: "new.txt".to_owned(),
+                    reason: "Only present in actual".to_owned(),
+                },
+                ExcludedFile {
+                    relative_file_path: Path::new("old")
+                        .join("gone.txt")
+                        .to_string_lossy()
+                        .into_owned(),
+                    reason: "Only present in nominal".to_owned(),
+                },
+            ]
+        );
+        let index = std::fs::read_to_string(report_dir.path().join("index.html")).unwrap();
+        assert!(index.contains("Files present on one side only"));
+        assert!(index.contains("Only present in actual"));
+        assert!(!index.contains("Excluded files"));
+
+        let (all_okay, rule_results) =
+            compare_folders_cfg_detailed(&nominal, &actual, config(""), &report_dir).unwrap();
+        assert!(!all_okay);
+        assert_eq!(rule_results[0].diffs.len(), 2);
+        assert_eq!(rule_results[0].one_sided.len(), 2);
+    }
+
+    #[test]
+    fn stale_outputs_are_flagged_by_freshness_check() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Outputs newer than inputs"
+pattern_include:
+  - "*.in"
+  - "*.out"
+name_transform:
+  pattern: "\\.in$"
+  replacement: ".out"
+require_newer_actual: true
+FileProperties: {}
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = dir.path().join("inputs");
+        let outputs = dir.path().join("outputs");
+        std::fs::create_dir(&inputs).unwrap();
+        std::fs::create_dir(&outputs).unwrap();
+        let now = std::time::SystemTime::now();
+        let hour = std::time::Duration::from_secs(3600);
+        for (name, input_time, output_time) in
+            [("fresh", now - hour, now), ("stale", now, now - hour)]
+        {
+            for (path, time) in [
+                (inputs.join(format!("{name}.in")), input_time),
+                (outputs.join(format!("{name}.out")), output_time),
+            ] {
+                std::fs::write(&path, name).unwrap();
+                File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(time)
+                    .unwrap();
+            }
+        }
+
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &inputs,
+            &outputs,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        let failed: Vec<_> = result
+            .iter()
+            .filter(|r| r.is_error)
+            .map(|r| r.relative_file_path.as_str())
+            .collect();
+        assert_eq!(failed, vec!["stale.out-stale.in"]);
+    }
+
+    #[test]
+    fn stale_actual_files_are_flagged() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Regenerated outputs"
+pattern_include:
+  - "*.txt"
+require_newer_actual: true
+Hash:
+  function: Sha256
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        std::fs::create_dir(&nominal).unwrap();
+        std::fs::create_dir(&actual).unwrap();
+        let now = std::time::SystemTime::now();
+        let hour = std::time::Duration::from_secs(3600);
+        for (name, nominal_time, actual_time) in [
+            ("fresh.txt", now - hour, now),
+            ("stale.txt", now, now - hour),
+        ] {
+            for (folder, time) in [(&nominal, nominal_time), (&actual, actual_time)] {
+                let path = folder.join(name);
+                std::fs::write(&path, "content").unwrap();
+                File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(time)
+                    .unwrap();
+            }
+        }
+
+        let mut result = Vec::new();
+        assert!(
+            !process_rule(&nominal, &actual, &rule, true, &mut result, &mut Vec::new()).unwrap()
+        );
+        let failed: Vec<_> = result
+            .iter()
+            .filter(|r| r.is_error)
+            .map(|r| r.relative_file_path.as_str())
+            .collect();
+        assert_eq!(failed, vec!["stale.txt"]);
+
+        let rule = Rule {
+            require_newer_actual: false,
+            compare_empty_directories: false,
+            ..rule
+        };
+        let mut result = Vec::new();
+        assert!(
+            process_rule(&nominal, &actual, &rule, true, &mut result, &mut Vec::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn config_hash_changes_with_config() {
+        let config = r#"
+rules:
+  - name: "Images"
+    pattern_include:
+      - "**/*.png"
+    Image:
+      threshold: 0.9
+"#;
+        let first = ConfigurationFile::from_reader(config.as_bytes()).unwrap();
+        let second = ConfigurationFile::from_reader(config.as_bytes()).unwrap();
+        assert_eq!(first.config_hash().unwrap(), second.config_hash().unwrap());
+        assert_eq!(first.config_hash().unwrap().len(), 64);
+
+        let changed = config.replace("0.9", "0.95");
+        let changed = ConfigurationFile::from_reader(changed.as_bytes()).unwrap();
+        assert_ne!(first.config_hash().unwrap(), changed.config_hash().unwrap());
+
+        let report_dir = tempfile::tempdir().unwrap();
+        let hash = first.config_hash().unwrap();
+        let folder = "tests/integ/data/images/expected/";
+        assert!(compare_folders_cfg(folder, folder, first, &report_dir).unwrap());
+        let report: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(report_dir.path().join("report.json")).unwrap(),
+        )
+        .unwrap();
+        assert!(report.is_array());
+        assert_eq!(
+            std::fs::read_to_string(report_dir.path().join("config_hash.txt")).unwrap(),
+            hash
+        );
+        let index = std::fs::read_to_string(report_dir.path().join("index.html")).unwrap();
+        assert!(index.contains(&hash));
+    }
+
+    #[test]
+    fn report_json_is_written_to_json_out() {
+        let mut config: ConfigurationFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: "JSON"
+    pattern_include:
+      - "*.json"
+    Json:
+      ignore_keys: []
+"#,
+        )
+        .unwrap();
+        let hash = config.config_hash().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let json_out = dir.path().join("ci").join("havocompare.json");
+        config.json_out = Some(json_out.clone());
+        assert_eq!(config.config_hash().unwrap(), hash);
+
+        let folder = "tests/integ/data/json/expected/";
+        let report_dir = dir.path().join("report");
+        assert!(compare_folders_cfg(folder, folder, config, &report_dir).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&json_out).unwrap(),
+            std::fs::read_to_string(report_dir.join("report.json")).unwrap()
+        );
+    }
+
+    #[test]
+    fn metrics_are_written_to_metrics_out() {
+        let mut config: ConfigurationFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: "CSV"
+    pattern_include:
+      - "**/*.csv"
+    CSV:
+      comparison_modes:
+        - Absolute: 0.0
+  - name: "Images"
+    pattern_include:
+      - "**/*.png"
+    Image:
+      threshold: 0.5
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        for root in [&nominal, &actual] {
+            std::fs::create_dir_all(root).unwrap();
+            std::fs::write(root.join("same.csv"), "1.0;2.0\n").unwrap();
+        }
+        std::fs::write(nominal.join("values.csv"), "1.0;2.0\n").unwrap();
+        std::fs::write(actual.join("values.csv"), "1.5;2.5\n").unwrap();
+        let metrics_out = dir.path().join("metrics").join("havocompare.prom");
+        config.metrics_out = Some(metrics_out.clone());
+
+        let report_dir = dir.path().join("report");
+        assert!(!compare_folders_cfg(&nominal, &actual, config, &report_dir).unwrap());
+        let metrics = std::fs::read_to_string(&metrics_out).unwrap();
+        let value = |name: &str| {
+            metrics
+                .lines()
+                .find_map(|l| l.strip_prefix(&format!("havocompare_{name} ")))
+                .unwrap()
+                .to_owned()
+        };
+        assert!(metrics.contains("# TYPE havocompare_rules_total gauge"));
+        assert_eq!(value("rules_total"), "2");
+        assert_eq!(value("rules_failed"), "1");
+        assert_eq!(value("files_total"), "2");
+        assert_eq!(value("files_failed"), "1");
+        assert_eq!(value("files_comparison_failed"), "0");
+        assert_eq!(value("files_warning"), "0");
+        assert_eq!(value("csv_diffs_total"), "2");
+        assert_eq!(value("image_score_worst"), "NaN");
+        assert!(value("run_duration_seconds").parse::<f64>().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn failed_comparison_is_told_apart_from_differing_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal.csv");
+        let actual = dir.path().join("actual.csv");
+        std::fs::write(&nominal, "1.0;2.0\n").unwrap();
+        std::fs::write(&actual, "1.0;2.5\n").unwrap();
+        let mode = ComparisonMode::CSV(CSVCompareConfig {
+            comparison_modes: vec![csv::Mode::Absolute(0.1)],
+            ..Default::default()
+        });
+
+        let result = compare_files(&nominal, &nominal, &mode);
+        assert_eq!(result.error_kind(), None);
+
+        let content_differs = compare_files(&nominal, &actual, &mode);
+        assert_eq!(
+            content_differs.error_kind(),
+            Some(ErrorKind::ContentDiffers)
+        );
+
+        let missing = dir.path().join("missing.csv");
+        let result = compare_files(&nominal, &missing, &mode);
+        assert!(result.is_error && result.comparison_failed);
+        assert_eq!(result.error_kind(), Some(ErrorKind::ComparisonFailed));
+
+        let summary = report::Summary::new(
+            &[RuleDifferences {
+                rule: Rule {
+                    name: "kinds".to_owned(),
+                    pattern_include: vec![],
+                    pattern_exclude: None,
+                    name_transform: None,
+                    content_filter: None,
+                    require_matches: false,
+                    group_by: None,
+                    pairing: Pairing::default(),
+                    require_newer_actual: false,
+                    compare_empty_directories: false,
+                    file_type: mode,
+                },
+                diffs: vec![content_differs, result],
+                excluded: Vec::new(),
+                one_sided: Vec::new(),
+            }],
+            &[false],
+        );
+        assert_eq!(summary.failed_files, 2);
+        assert_eq!(summary.failed_comparisons, 1);
+    }
+
+    #[test]
+    fn basename_pairing_matches_nested_with_flat() {
+        let mut rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Flattened outputs"
+pattern_include:
+  - "**/*.txt"
+pairing: Basename
+Hash:
+  function: Sha256
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("flat");
+        for (path, content) in [
+            ("nominal/a/z.txt", "z"),
+            ("nominal/b/c/x.txt", "x"),
+            ("nominal/y.txt", "y"),
+            ("flat/x.txt", "x"),
+            ("flat/y.txt", "y"),
+            ("flat/z.txt", "z"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut result = Vec::new();
+        assert!(process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 3);
+
+        rule.pairing = Pairing::SortedPaths;
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+
+        std::fs::remove_file(actual.join("y.txt")).unwrap();
+        std::fs::write(actual.join("extra.txt"), "extra").unwrap();
+        rule.pairing = Pairing::Basename;
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 4);
+        let messages: Vec<_> = result
+            .iter()
+            .flat_map(|r| r.detail.iter())
+            .filter_map(|d| match d {
+                DiffDetail::Error(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("Actual file") && messages[0].contains("y.txt"));
+        assert!(messages[1].starts_with("Nominal file") && messages[1].contains("extra.txt"));
+
+        std::fs::create_dir(actual.join("sub")).unwrap();
+        std::fs::write(actual.join("sub/x.txt"), "x").unwrap();
+        std::fs::write(nominal.join("w.txt"), "w").unwrap();
+        rule.pairing = Pairing::Basename;
+        assert!(matches!(
+            process_rule(
+                &nominal,
+                &actual,
+                &rule,
+                false,
+                &mut result,
+                &mut Vec::new()
+            ),
+            Err(Error::AmbiguousPairing(_))
+        ));
+    }
+
+    #[test]
+    fn relative_path_pairing_reports_missing_files() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Nested outputs"
+pattern_include:
+  - "**/*.txt"
+Hash:
+  function: Sha256
+"#,
+        )
+        .unwrap();
+        assert_eq!(rule.pairing, Pairing::RelativePath);
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        for (path, content) in [
+            ("nominal/a/x.txt", "ax"),
+            ("nominal/b/x.txt", "bx"),
+            ("nominal/b/y.txt", "by"),
+            ("actual/a/x.txt", "ax"),
+            ("actual/b/x.txt", "bx"),
+            ("actual/b/yy.txt", "by"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 4);
+        assert_eq!(result.iter().filter(|r| r.is_error).count(), 2);
+        let messages: Vec<_> = result
+            .iter()
+            .flat_map(|r| r.detail.iter())
+            .filter_map(|d| match d {
+                DiffDetail::Error(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("Actual file") && messages[0].contains("y.txt"));
+        assert!(messages[1].starts_with("Nominal file") && messages[1].contains("yy.txt"));
+
+        std::fs::remove_file(actual.join("b/yy.txt")).unwrap();
+        std::fs::write(actual.join("b/y.txt"), "by").unwrap();
+        let mut result = Vec::new();
+        assert!(process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 3);
+    }
+
+    #[test]
+    fn hash_manifest_replaces_nominal_files() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Release bundle"
+pattern_include:
+  - "**/*.txt"
+Hash:
+  function: Sha256
+  manifest: SHA256SUMS
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        std::fs::create_dir_all(&nominal).unwrap();
+        std::fs::write(
+            nominal.join("SHA256SUMS"),
+            "# release 1.0\n\
+             5e85370e555e95d27df68f93c0ccaa4edfc1da5e281b47a2ebba2649a13ea5f4  a/x.txt\n\
+             3FF1726CD4437F1F30E268F95C9E56D7D77DDBAA40EF94D7FB376882E9C32ADA *./b/x.txt\n\
+             3ff1726cd4437f1f30e268f95c9e56d7d77ddbaa40ef94d7fb376882e9c32ada  b/y.txt\n\
+             a7e2d26e8d15814dd9c6a1bdc90585c8d0a3170dfffeb21fc42986683113041b  c/z.txt\n",
+        )
+        .unwrap();
+        for (path, content) in [
+            ("a/x.txt", "ax"),
+            ("b/x.txt", "bx"),
+            ("b/y.txt", "by"),
+            ("extra.txt", "extra"),
+        ] {
+            let path = actual.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 5);
+        let failed: Vec<_> = result
+            .iter()
+            .filter(|r| r.is_error)
+            .map(|r| (r.relative_file_path.as_str(), &r.detail[0]))
+            .collect();
+        assert_eq!(failed.len(), 3);
+        assert!(
+            matches!(failed[0], ("b/y.txt", DiffDetail::Hash { actual, .. })
+            if actual == "a7e2d26e8d15814dd9c6a1bdc90585c8d0a3170dfffeb21fc42986683113041b")
+        );
+        assert!(
+            matches!(failed[1], ("c/z.txt", DiffDetail::Error(message)) if message.contains("is missing"))
+        );
+        assert!(
+            matches!(failed[2], ("extra.txt", DiffDetail::Error(message)) if message.contains("not listed"))
+        );
+
+        std::fs::write(nominal.join("SHA256SUMS"), "not a hash line\n").unwrap();
+        assert!(matches!(
+            process_rule(
+                &nominal,
+                &actual,
+                &rule,
+                false,
+                &mut Vec::new(),
+                &mut Vec::new()
+            ),
+            Err(Error::HashModuleError(hash::Error::MalformedManifest(1, _)))
+        ));
+    }
+
+    #[test]
+    fn empty_directory_on_one_side_is_flagged() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "Tree"
+pattern_include:
+  - "**/*"
+pattern_exclude:
+  - "**/ignored"
+compare_empty_directories: true
+Hash:
+  function: Sha256
+"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        for root in [&nominal, &actual] {
+            std::fs::create_dir_all(root.join("data/both_empty")).unwrap();
+            std::fs::write(root.join("data/file.txt"), "content").unwrap();
+        }
+        let mut result = Vec::new();
+        assert!(process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        assert_eq!(result.len(), 1);
+
+        std::fs::create_dir_all(nominal.join("data/only_nominal")).unwrap();
+        std::fs::create_dir_all(actual.join("only_actual/nested")).unwrap();
+        std::fs::create_dir_all(actual.join("ignored")).unwrap();
+        let mut result = Vec::new();
+        assert!(!process_rule(
+            &nominal,
+            &actual,
+            &rule,
+            false,
+            &mut result,
+            &mut Vec::new()
+        )
+        .unwrap());
+        let messages: Vec<_> = result
+            .iter()
+            .flat_map(|r| r.detail.iter())
+            .filter_map(|d| match d {
+                DiffDetail::Error(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(
+            messages[0].starts_with("Actual directory") && messages[0].contains("only_nominal")
+        );
+        assert!(messages[1].starts_with("Nominal directory") && messages[1].contains("nested"));
+
+        for rule in [
+            Rule {
+                compare_empty_directories: false,
+                ..rule.clone()
+            },
+            Rule {
+                pairing: Pairing::Intersection,
+                ..rule.clone()
+            },
+            Rule {
+                pairing: Pairing::Basename,
+                ..rule
+            },
+        ] {
+            let mut result = Vec::new();
+            assert!(process_rule(
+                &nominal,
+                &actual,
+                &rule,
+                false,
+                &mut result,
+                &mut Vec::new()
+            )
+            .unwrap());
+            assert_eq!(result.len(), 1);
+        }
+    }
+
+    #[test]
+    fn sidecar_loosens_threshold_for_subfolder() {
+        let rules = |tolerance: f64| {
+            format!(
+                r#"
+rules:
+  - name: "CSV"
+    pattern_include:
+      - "**/*.csv"
+    CSV:
+      comparison_modes:
+        - Absolute: {tolerance:?}
+"#
+            )
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal");
+        let actual = dir.path().join("actual");
+        for (path, content) in [
+            ("nominal/strict.csv", "1.0\n"),
+            ("nominal/loose/noisy.csv", "1.0\n"),
+            ("nominal/loose/deeper/noisy.csv", "1.0\n"),
+            ("actual/strict.csv", "1.0\n"),
+            ("actual/loose/noisy.csv", "1.5\n"),
+            ("actual/loose/deeper/noisy.csv", "1.5\n"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let config: ConfigurationFile = serde_yaml::from_str(&rules(0.0)).unwrap();
+        let report_dir = dir.path().join("report_strict");
+        assert!(!compare_folders_cfg(&nominal, &actual, config, &report_dir).unwrap());
+
+        let sidecar_file = nominal.join("loose").join(sidecar::SIDECAR_FILE_NAME);
+        std::fs::write(&sidecar_file, rules(1.0)).unwrap();
+        let config: ConfigurationFile = serde_yaml::from_str(&rules(0.0)).unwrap();
+        let resolved = sidecar::resolve_rules(&nominal, config.rules.clone()).unwrap();
+        let names: Vec<_> = resolved.iter().map(|r| r.rule.name.as_str()).collect();
+        assert_eq!(names, vec!["CSV", "CSV (loose)"]);
+
+        let report_dir = dir.path().join("report_loose");
+        assert!(compare_folders_cfg(&nominal, &actual, config, &report_dir).unwrap());
+        let report = std::fs::read_to_string(report_dir.join("report.json")).unwrap();
+        assert!(report.contains("strict.csv"));
+        assert!(report.contains("deeper"));
+
+        // the sidecar only applies to its folder
+        std::fs::write(actual.join("strict.csv"), "1.5\n").unwrap();
+        let config: ConfigurationFile = serde_yaml::from_str(&rules(0.0)).unwrap();
+        let report_dir = dir.path().join("report_strict_fails");
+        assert!(!compare_folders_cfg(&nominal, &actual, config, &report_dir).unwrap());
+    }
 }
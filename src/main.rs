@@ -1,6 +1,9 @@
 use anyhow::anyhow;
 use clap::Parser;
-use havocompare::{compare_files, compare_folders, get_schema, validate_config, ComparisonMode};
+use havocompare::{
+    compare_files, compare_folders_cfg_detailed, get_schema, is_remote, update_baseline,
+    validate_config, ComparisonMode, ConfigurationFile,
+};
 use std::path::{Path, PathBuf};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -11,7 +14,7 @@ const DEFAULT_REPORT_FOLDER: &str = "report";
 enum Commands {
     /// Compare two folders using a config file
     Compare {
-        /// Nominal data folder
+        /// Nominal data folder, or a plain `http://` url to fetch it from - `https://` urls are rejected, redirects and chunked responses are not supported
         nominal: String,
         /// Actual data folder
         actual: String,
@@ -23,6 +26,18 @@ enum Commands {
         /// Open the report immediately after comparison
         #[arg(short, long)]
         open: bool,
+        /// Optional: Additionally write the report.json to this exact file path
+        #[arg(long = "json-out")]
+        json_out: Option<PathBuf>,
+        /// Optional: Write a summary of the run in Prometheus text format to this file path
+        #[arg(long = "metrics-out")]
+        metrics_out: Option<PathBuf>,
+        /// Copy the actual files over the nominal ones for all failed files after reporting, needs `--confirm-update-baseline`
+        #[arg(long = "update-baseline", requires = "confirm_update_baseline")]
+        update_baseline: bool,
+        /// Confirm that nominal files may be overwritten by `--update-baseline`
+        #[arg(long = "confirm-update-baseline")]
+        confirm_update_baseline: bool,
     },
     /// Compare two files given a config-string that contains a json-serialized config
     FileCompare {
@@ -81,9 +96,28 @@ fn main() -> Result<(), vg_errortools::MainError> {
             actual,
             report_config,
             open,
+            json_out,
+            metrics_out,
+            update_baseline: update,
+            ..
         } => {
             let report_path = Path::new(report_config.as_str());
-            let result = compare_folders(nominal, actual, compare_config, report_path)?;
+            let mut config = ConfigurationFile::from_file(compare_config)?;
+            if json_out.is_some() {
+                config.json_out = json_out;
+            }
+            if metrics_out.is_some() {
+                config.metrics_out = metrics_out;
+            }
+            if update && is_remote(Path::new(&nominal)) {
+                return Err(anyhow!("Cannot update a remote baseline {nominal}").into());
+            }
+            let (result, rule_results) =
+                compare_folders_cfg_detailed(nominal, actual, config, report_path)?;
+            if update && !result {
+                let updated = update_baseline(&rule_results)?;
+                info!("Updated {} nominal files", updated.len());
+            }
             if open {
                 info!("Opening report");
                 opener::open(report_path.join("index.html")).expect("Could not open report!");
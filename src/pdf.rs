@@ -3,9 +3,8 @@ use crate::report;
 use crate::report::{DiffDetail, Difference};
 use pdf_extract::extract_text;
 use std::path::Path;
-use strsim::normalized_damerau_levenshtein;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use vg_errortools::FatIOError;
 
 #[derive(Debug, Error)]
@@ -42,7 +41,7 @@ pub fn compare_files<P: AsRef<Path>>(
             exclusion_list.iter().all(|exc| !exc.is_match(a)) && exclusion_list.iter().all(|exc| !exc.is_match(n))
         )
         .for_each(|((l, a), n)| {
-            let distance = normalized_damerau_levenshtein(a,n);
+            let distance = config.metric.similarity(a, n);
             if  distance < config.threshold {
 
                 let error =  format!(
@@ -53,6 +52,10 @@ pub fn compare_files<P: AsRef<Path>>(
                 error!("{}" , &error);
                 difference.push_detail(DiffDetail::Text {actual:a.to_owned(), nominal:n.to_owned(), score: distance, line: l});
                 difference.error();
+            } else if config.is_warning(distance) {
+                warn!("Line {} of PDF-Text-file close to threshold. Expected: '{}' found '{}' (diff: {}, threshold: {})", l, n, a, distance, config.threshold);
+                difference.push_detail(DiffDetail::Text {actual:a.to_owned(), nominal:n.to_owned(), score: distance, line: l});
+                difference.warn();
             }
         });
 
@@ -90,6 +93,7 @@ mod test {
             &HTMLCompareConfig {
                 threshold: 1.0,
                 ignore_lines: Some(vec!["/workspace/".to_owned()]),
+                ..Default::default()
             },
         )
         .unwrap();
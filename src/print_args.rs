@@ -7,6 +7,14 @@ fn main() {
     // will fail on jenkins/qa tough, that's why we need to ignore the result
     let _ = enable_ansi_support::enable_ansi_support();
 
+    // prints the given JSON as only output - for testing structured output, logging would corrupt it
+    if let Some(json) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--print-json=").map(str::to_owned))
+    {
+        println!("{json}");
+        std::process::exit(0);
+    }
+
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::INFO)
         .finish();
@@ -19,6 +27,26 @@ fn main() {
             eprintln!("E: setting error code to -1");
             terminate_code = -1;
         }
+        if let Some(name) = arg.strip_prefix("--print-env=") {
+            println!("{name}={}", std::env::var(name).unwrap_or_default());
+        }
+        if arg.as_str() == "--print-cwd" {
+            let cwd = std::env::current_dir().expect("reading the working directory failed");
+            println!("cwd={}", cwd.to_string_lossy());
+        }
+        if let Some(secs) = arg.strip_prefix("--sleep=") {
+            // simulates a hanging checker - for testing timeouts
+            let secs = secs.parse().expect("sleep needs a number of seconds");
+            std::thread::sleep(std::time::Duration::from_secs(secs));
+        }
+        if let Some(marker) = arg.strip_prefix("--fail-once=") {
+            // fails with 3 unless the marker exists, creating it - for testing retries
+            if std::fs::metadata(marker).is_err() {
+                eprintln!("E: first call, setting error code to 3");
+                std::fs::write(marker, "").expect("creating the marker failed");
+                terminate_code = 3;
+            }
+        }
     }
     std::process::exit(terminate_code);
 }
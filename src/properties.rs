@@ -1,11 +1,12 @@
 use crate::report::{get_relative_path, DiffDetail, Difference};
 use crate::Error;
-use chrono::offset::Utc;
+use chrono::format::{Item, StrftimeItems};
+use chrono::offset::{Local, Utc};
 use chrono::DateTime;
 use regex::Regex;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
+use serde::{Deserialize, Deserializer};
 use std::path::Path;
 use std::time::SystemTime;
 use tracing::error;
@@ -16,11 +17,103 @@ pub struct PropertiesConfig {
     /// Compare the file size, difference must be smaller then given value
     file_size_tolerance_bytes: Option<u64>,
 
-    /// Compare the modification date, difference must be smaller then the given value
+    /// Compare the modification date, difference must be smaller then the given value.
+    /// Either seconds or a duration string like `30m`, `1h`, `2d` or `1d 12h`
+    #[serde(default, deserialize_with = "deserialize_duration_secs")]
+    #[schemars(with = "Option<DurationSecs>")]
     modification_date_tolerance_secs: Option<u64>,
 
     /// Fail if the name contains that regex
     forbid_name_regex: Option<String>,
+
+    /// The timezone the modification dates are displayed in, defaults to UTC
+    #[serde(default)]
+    date_timezone: DisplayTimeZone,
+
+    /// The chrono format string the modification dates are displayed with, defaults to `%Y-%m-%d %T`
+    #[serde(default = "default_date_format")]
+    date_format: String,
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d %T".to_string()
+}
+
+/// A duration given as seconds or as string of numbers with the units `s`, `m`, `h`, `d` or `w`
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum DurationSecs {
+    Seconds(u64),
+    Text(String),
+}
+
+fn deserialize_duration_secs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    match Option::<DurationSecs>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(DurationSecs::Seconds(secs)) => Ok(Some(secs)),
+        Some(DurationSecs::Text(text)) => parse_duration_secs(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parses durations like `90`, `30m`, `2d` or `1d 12h` into seconds, a number without unit is seconds
+pub(crate) fn parse_duration_secs(text: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid duration '{text}', expected e.g. '90s', '30m', '1h' or '2d'");
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total: u64 = 0;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+        let unit_length = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_length] {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        total = value
+            .checked_mul(factor)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        rest = rest[unit_length..].trim_start();
+    }
+    Ok(total)
+}
+
+/// The timezone used for displaying timestamps in the report
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayTimeZone {
+    /// Coordinated universal time
+    #[default]
+    Utc,
+    /// The local timezone of the machine creating the report
+    Local,
+}
+
+fn format_timestamp(time: SystemTime, timezone: DisplayTimeZone, format: &str) -> String {
+    let format = if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        error!("Invalid date format '{format}', falling back to default");
+        default_date_format()
+    } else {
+        format.to_string()
+    };
+    match timezone {
+        DisplayTimeZone::Utc => DateTime::<Utc>::from(time).format(&format).to_string(),
+        DisplayTimeZone::Local => DateTime::<Local>::from(time).format(&format).to_string(),
+    }
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -68,7 +161,7 @@ fn file_size_out_of_tolerance(nominal: &Path, actual: &Path, tolerance: u64) ->
         );
         error!("{}", &msg);
         result.push_detail(DiffDetail::Error(msg));
-        result.is_error = true;
+        result.fail();
     }
     result
 }
@@ -77,17 +170,17 @@ fn file_modification_time_out_of_tolerance(
     nominal: &Path,
     actual: &Path,
     tolerance: u64,
+    timezone: DisplayTimeZone,
+    format: &str,
 ) -> Difference {
     let mut result = Difference::new_for_file(nominal, actual);
     if let (Ok(nominal_meta), Ok(actual_meta)) = (nominal.metadata(), actual.metadata()) {
         if let (Ok(mod_time_act), Ok(mod_time_nom)) =
             (nominal_meta.modified(), actual_meta.modified())
         {
-            let nominal_datetime: DateTime<Utc> = mod_time_nom.into();
-            let actual_datetime: DateTime<Utc> = mod_time_act.into();
             result.push_detail(DiffDetail::Properties(MetaDataPropertyDiff::CreationDate {
-                nominal: nominal_datetime.format("%Y-%m-%d %T").to_string(),
-                actual: actual_datetime.format("%Y-%m-%d %T").to_string(),
+                nominal: format_timestamp(mod_time_nom, timezone, format),
+                actual: format_timestamp(mod_time_act, timezone, format),
             }));
 
             let now = SystemTime::now();
@@ -107,13 +200,13 @@ fn file_modification_time_out_of_tolerance(
                     "Could not calculate duration between modification timestamps".to_string();
                 error!("{}", &msg);
                 result.push_detail(DiffDetail::Error(msg));
-                result.is_error = true;
+                result.fail();
             }
         } else {
             let msg = "Could not read file modification timestamps".to_string();
             error!("{}", &msg);
             result.push_detail(DiffDetail::Error(msg));
-            result.is_error = true;
+            result.fail();
         }
     } else {
         let msg = format!(
@@ -124,7 +217,39 @@ fn file_modification_time_out_of_tolerance(
         error!("{}", &msg);
         result.push_detail(DiffDetail::Error(msg));
 
-        result.is_error = true;
+        result.fail();
+    }
+    result
+}
+
+/// Fails if the actual file was not modified after the nominal file, i.e. the actual file is stale
+pub(crate) fn actual_newer_than_nominal(nominal: &Path, actual: &Path) -> Difference {
+    let mut result = Difference::new_for_file(nominal, actual);
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified());
+    match (modified(nominal), modified(actual)) {
+        (Ok(mod_time_nom), Ok(mod_time_act)) => {
+            if mod_time_act <= mod_time_nom {
+                let msg = format!(
+                    "Actual file {} is stale: modified {} but nominal was modified {}",
+                    actual.to_string_lossy(),
+                    format_timestamp(mod_time_act, DisplayTimeZone::Utc, &default_date_format()),
+                    format_timestamp(mod_time_nom, DisplayTimeZone::Utc, &default_date_format())
+                );
+                error!("{}", &msg);
+                result.push_detail(DiffDetail::Error(msg));
+                result.error();
+            }
+        }
+        _ => {
+            let msg = format!(
+                "Could not read file modification timestamps for either: {} or {}",
+                &nominal.to_string_lossy(),
+                &actual.to_string_lossy()
+            );
+            error!("{}", &msg);
+            result.push_detail(DiffDetail::Error(msg));
+            result.fail();
+        }
     }
     result
 }
@@ -155,9 +280,15 @@ pub(crate) fn compare_files<P: AsRef<Path>>(
         .map(|tolerance| file_size_out_of_tolerance(nominal, actual, tolerance));
     result.map(|r| total_diff.join(r));
 
-    let result = config
-        .modification_date_tolerance_secs
-        .map(|tolerance| file_modification_time_out_of_tolerance(nominal, actual, tolerance));
+    let result = config.modification_date_tolerance_secs.map(|tolerance| {
+        file_modification_time_out_of_tolerance(
+            nominal,
+            actual,
+            tolerance,
+            config.date_timezone,
+            &config.date_format,
+        )
+    });
     result.map(|r| total_diff.join(r));
 
     Ok(total_diff)
@@ -200,12 +331,119 @@ mod tests {
         let toml_file = "Cargo.toml";
         let lock_file = "Cargo.lock";
         assert!(
-            !file_modification_time_out_of_tolerance(Path::new(toml_file), Path::new(toml_file), 0)
+            !file_modification_time_out_of_tolerance(
+                Path::new(toml_file),
+                Path::new(toml_file),
+                0,
+                DisplayTimeZone::Utc,
+                &default_date_format()
+            )
+            .is_error
+        );
+        assert!(
+            file_modification_time_out_of_tolerance(
+                Path::new(toml_file),
+                Path::new(lock_file),
+                0,
+                DisplayTimeZone::Utc,
+                &default_date_format()
+            )
+            .is_error
+        );
+    }
+
+    #[test]
+    fn modification_tolerance_as_duration_string() {
+        let config: PropertiesConfig =
+            serde_yaml::from_str("modification_date_tolerance_secs: 2d").unwrap();
+        assert_eq!(
+            config.modification_date_tolerance_secs,
+            Some(2 * 24 * 60 * 60)
+        );
+        let config: PropertiesConfig =
+            serde_yaml::from_str("modification_date_tolerance_secs: 90").unwrap();
+        assert_eq!(config.modification_date_tolerance_secs, Some(90));
+        let config: PropertiesConfig = serde_yaml::from_str("date_format: '%T'").unwrap();
+        assert_eq!(config.modification_date_tolerance_secs, None);
+        assert!(
+            serde_yaml::from_str::<PropertiesConfig>("modification_date_tolerance_secs: 2y")
+                .is_err()
+        );
+        assert_eq!(parse_duration_secs("30m"), Ok(30 * 60));
+        assert_eq!(parse_duration_secs("1h"), Ok(60 * 60));
+        assert_eq!(parse_duration_secs("1d 12h"), Ok(36 * 60 * 60));
+        assert!(parse_duration_secs("").is_err());
+        assert!(parse_duration_secs("h").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let nominal = dir.path().join("nominal.txt");
+        let actual = dir.path().join("actual.txt");
+        std::fs::write(&nominal, "a").unwrap();
+        std::fs::write(&actual, "a").unwrap();
+        let now = SystemTime::now();
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&nominal)
+            .unwrap()
+            .set_modified(now - day)
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&actual)
+            .unwrap()
+            .set_modified(now)
+            .unwrap();
+
+        let two_days: PropertiesConfig =
+            serde_yaml::from_str("modification_date_tolerance_secs: 2d").unwrap();
+        assert!(
+            !compare_files(&nominal, &actual, &two_days)
+                .unwrap()
                 .is_error
         );
+        let half_a_day: PropertiesConfig =
+            serde_yaml::from_str("modification_date_tolerance_secs: 12h").unwrap();
         assert!(
-            file_modification_time_out_of_tolerance(Path::new(toml_file), Path::new(lock_file), 0)
+            compare_files(&nominal, &actual, &half_a_day)
+                .unwrap()
                 .is_error
         );
     }
+
+    #[test]
+    fn modification_date_format() {
+        let toml_file = Path::new("Cargo.toml");
+        let result = file_modification_time_out_of_tolerance(
+            toml_file,
+            toml_file,
+            0,
+            DisplayTimeZone::Utc,
+            "%Y/%m/%d %H:%M %z",
+        );
+        if let Some(DiffDetail::Properties(MetaDataPropertyDiff::CreationDate {
+            nominal,
+            actual,
+        })) = result.detail.first()
+        {
+            let expected = Regex::new(r"^\d{4}/\d{2}/\d{2} \d{2}:\d{2} \+0000$").unwrap();
+            assert!(expected.is_match(nominal));
+            assert_eq!(nominal, actual);
+        } else {
+            panic!("wrong diffdetail");
+        }
+
+        let time = SystemTime::UNIX_EPOCH;
+        assert_eq!(
+            format_timestamp(time, DisplayTimeZone::Utc, "%d.%m.%Y"),
+            "01.01.1970"
+        );
+        assert_eq!(
+            format_timestamp(time, DisplayTimeZone::Utc, "%Q"),
+            "1970-01-01 00:00:00"
+        );
+        let local = format_timestamp(time, DisplayTimeZone::Local, "%Y-%m-%d %T %:z");
+        let local_offset = DateTime::<Local>::from(time).format("%:z").to_string();
+        assert!(local.ends_with(&local_offset));
+    }
 }
@@ -0,0 +1,307 @@
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, info};
+use vg_errortools::{fat_io_wrap_std, FatIOError};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SCHEME: &str = "http://";
+const TLS_SCHEME: &str = "https://";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unsupported url {0}, only plain http:// urls with an optional #sha256=<hex> fragment are supported, https:// is not available")]
+    UnsupportedUrl(String),
+    #[error("Network error while fetching {0}: {1}")]
+    Network(String, std::io::Error),
+    #[error("Fetching {0} failed, server responded: {1}")]
+    HttpStatus(String, String),
+    #[error("Checksum mismatch for {url}: expected {expected}, found {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Invalid entry in remote index: {0}")]
+    InvalidIndexEntry(String),
+    #[error("File access failed {0}")]
+    FileAccessFailed(#[from] FatIOError),
+}
+
+struct Url<'a> {
+    host: &'a str,
+    path: &'a str,
+    sha256: Option<&'a str>,
+}
+
+/// Whether the given nominal is a url instead of a local path.
+/// `https://` urls count as remote as well, fetching them fails with [`Error::UnsupportedUrl`] though:
+/// the minimal HTTP/1.0 client below neither speaks TLS nor follows redirects nor decodes chunked responses.
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|p| p.starts_with(SCHEME) || p.starts_with(TLS_SCHEME))
+}
+
+fn parse_url(url: &str) -> Result<Url<'_>, Error> {
+    let unsupported = || Error::UnsupportedUrl(url.to_owned());
+    let rest = url.strip_prefix(SCHEME).ok_or_else(unsupported)?;
+    let (rest, sha256) = match rest.split_once('#') {
+        Some((rest, fragment)) => (
+            rest,
+            Some(fragment.strip_prefix("sha256=").ok_or_else(unsupported)?),
+        ),
+        None => (rest, None),
+    };
+    let (host, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(unsupported());
+    }
+    Ok(Url { host, path, sha256 })
+}
+
+fn get(host: &str, path: &str, url: &str) -> Result<Vec<u8>, Error> {
+    let network_error = |e| Error::Network(url.to_owned(), e);
+    debug!("Fetching {url}");
+    let address = if host.contains(':') {
+        host.to_owned()
+    } else {
+        format!("{host}:80")
+    };
+    let mut stream = TcpStream::connect(address).map_err(network_error)?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(network_error)?;
+    stream
+        .set_write_timeout(Some(TIMEOUT))
+        .map_err(network_error)?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.0\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )
+    .map_err(network_error)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(network_error)?;
+
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| Error::HttpStatus(url.to_owned(), "malformed response".to_owned()))?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(Error::HttpStatus(url.to_owned(), status.to_owned()));
+    }
+    Ok(response.split_off(header_end + 4))
+}
+
+fn verify_checksum(url: &str, content: &[u8], expected: &str) -> Result<(), Error> {
+    let actual = HEXLOWER.encode(&Sha256::digest(content));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(Error::ChecksumMismatch {
+            url: url.to_owned(),
+            expected: expected.to_owned(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fat_io_wrap_std(parent, &fs::create_dir_all)?;
+    }
+    fs::write(path, content).map_err(|e| FatIOError::from_std_io_err(e, path.to_path_buf()))?;
+    Ok(())
+}
+
+/// Downloads the nominal behind the url into `target_dir` and returns the local path to compare against.
+/// An url ending with `/` is an index listing one relative file path per line, optionally preceded by its sha256 like `sha256sum` prints it.
+/// The url itself may carry a `#sha256=<hex>` fragment to verify the downloaded file or index.
+pub(crate) fn fetch_nominal(url: &str, target_dir: &Path) -> Result<PathBuf, Error> {
+    let parsed = parse_url(url)?;
+    let content = get(parsed.host, parsed.path, url)?;
+    if let Some(expected) = parsed.sha256 {
+        verify_checksum(url, &content, expected)?;
+    }
+
+    if !parsed.path.ends_with('/') {
+        let file_name = parsed
+            .path
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("nominal");
+        let file = target_dir.join(file_name);
+        write_file(&file, &content)?;
+        info!("Fetched nominal {url}");
+        return Ok(file);
+    }
+
+    let index = String::from_utf8_lossy(&content);
+    for line in index
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    {
+        let (checksum, entry) = match line.split_once(char::is_whitespace) {
+            Some((checksum, entry)) => (Some(checksum), entry.trim_start()),
+            None => (None, line),
+        };
+        let relative = Path::new(entry);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::InvalidIndexEntry(line.to_owned()));
+        }
+        let path = format!("{}{entry}", parsed.path);
+        let file_url = format!("{SCHEME}{}{path}", parsed.host);
+        let content = get(parsed.host, &path, &file_url)?;
+        if let Some(expected) = checksum {
+            verify_checksum(&file_url, &content, expected)?;
+        }
+        write_file(&target_dir.join(relative), &content)?;
+    }
+    info!("Fetched nominal index {url}");
+    Ok(target_dir.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConfigurationFile;
+    use std::net::TcpListener;
+
+    fn serve(files: Vec<(&'static str, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let request = String::from_utf8_lossy(&request).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                match files.iter().find(|(p, _)| *p == path) {
+                    Some((_, body)) => {
+                        write!(stream, "HTTP/1.0 200 OK\r\n\r\n").unwrap();
+                        stream.write_all(body).unwrap();
+                    }
+                    None => write!(stream, "HTTP/1.0 404 Not Found\r\n\r\n").unwrap(),
+                }
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn sha256(content: &[u8]) -> String {
+        HEXLOWER.encode(&Sha256::digest(content))
+    }
+
+    #[test]
+    fn fetches_single_file_with_checksum() {
+        let content = b"a,b\n1,2\n".to_vec();
+        let server = serve(vec![("/golden/values.csv", content.clone())]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let url = format!("{server}/golden/values.csv#sha256={}", sha256(&content));
+        let file = fetch_nominal(&url, dir.path()).unwrap();
+        assert_eq!(file, dir.path().join("values.csv"));
+        assert_eq!(fs::read(&file).unwrap(), content);
+
+        let url = format!("{server}/golden/values.csv#sha256={}", sha256(b"other"));
+        assert!(matches!(
+            fetch_nominal(&url, dir.path()),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            fetch_nominal(&format!("{server}/missing.csv"), dir.path()),
+            Err(Error::HttpStatus(..))
+        ));
+        assert!(matches!(
+            fetch_nominal("ftp://localhost/values.csv", dir.path()),
+            Err(Error::UnsupportedUrl(_))
+        ));
+    }
+
+    #[test]
+    fn https_is_remote_but_rejected() {
+        assert!(is_remote(Path::new("http://localhost/values.csv")));
+        assert!(is_remote(Path::new("https://localhost/values.csv")));
+        assert!(!is_remote(Path::new("tests/integ/data/csv")));
+
+        let config: ConfigurationFile = serde_yaml::from_str("rules: []").unwrap();
+        let report_dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            crate::compare_folders_cfg(
+                "https://localhost/golden/",
+                "tests/integ/data/json/expected/",
+                config,
+                &report_dir
+            ),
+            Err(crate::Error::RemoteNominalError(Error::UnsupportedUrl(_)))
+        ));
+    }
+
+    #[test]
+    fn fetches_index_and_rejects_escaping_entries() {
+        let first = b"first".to_vec();
+        let index = format!("{}  sub/first.txt\nsecond.txt\n", sha256(&first));
+        let server = serve(vec![
+            ("/golden/", index.into_bytes()),
+            ("/golden/sub/first.txt", first.clone()),
+            ("/golden/second.txt", b"second".to_vec()),
+            ("/evil/", b"../escaped.txt\n".to_vec()),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let folder = fetch_nominal(&format!("{server}/golden/"), dir.path()).unwrap();
+        assert_eq!(fs::read(folder.join("sub/first.txt")).unwrap(), first);
+        assert_eq!(fs::read(folder.join("second.txt")).unwrap(), b"second");
+        assert!(matches!(
+            fetch_nominal(&format!("{server}/evil/"), dir.path()),
+            Err(Error::InvalidIndexEntry(_))
+        ));
+    }
+
+    #[test]
+    fn compares_against_fetched_nominal() {
+        let nominal = fs::read("tests/integ/data/json/expected/guy.json").unwrap();
+        let server = serve(vec![("/golden/guy.json", nominal)]);
+        let config: ConfigurationFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: "JSON"
+    pattern_include:
+      - "*.json"
+    Json:
+      ignore_keys: []
+"#,
+        )
+        .unwrap();
+        let report_dir = tempfile::tempdir().unwrap();
+        let url = format!("{server}/golden/guy.json");
+        assert!(crate::compare_folders_cfg(
+            &url,
+            "tests/integ/data/json/expected/guy.json",
+            config,
+            &report_dir
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,152 @@
+use super::Error;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use vg_errortools::{fat_io_wrap_std, FatIOError};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Returns the path of the archive created for the given report folder: `<report_path>.tar.gz`
+pub(crate) fn archive_path(report_path: impl AsRef<Path>) -> PathBuf {
+    let mut archive = report_path.as_ref().as_os_str().to_owned();
+    archive.push(".tar.gz");
+    PathBuf::from(archive)
+}
+
+/// Packs all files of the report folder into a gzip compressed tar archive next to the folder
+pub(crate) fn create_archive(report_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let report_dir = report_path.as_ref();
+    let archive_file = archive_path(report_dir);
+    let mut files = Vec::new();
+    collect_files(report_dir, &mut files)?;
+    files.sort();
+
+    let writer = fat_io_wrap_std(&archive_file, &File::create)?;
+    let mut encoder = GzEncoder::new(writer, Compression::default());
+    for file in files {
+        let name = file
+            .strip_prefix(report_dir)
+            .map_err(|_| Error::FileNameParsing(file.to_string_lossy().to_string()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut content = Vec::new();
+        fat_io_wrap_std(&file, &File::open)?
+            .read_to_end(&mut content)
+            .map_err(|e| FatIOError::from_std_io_err(e, file.clone()))?;
+        debug!("Adding {} to report archive", &name);
+        encoder.write_all(&tar_header(&name, content.len() as u64)?)?;
+        encoder.write_all(&content)?;
+        encoder.write_all(&vec![0u8; padding(content.len())])?;
+    }
+    encoder.write_all(&[0u8; 2 * BLOCK_SIZE])?;
+    encoder.finish()?;
+    Ok(archive_file)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fat_io_wrap_std(dir, &fs::read_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn padding(len: usize) -> usize {
+    (BLOCK_SIZE - len % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// Creates a ustar header for a regular file, long names are split into prefix and name
+fn tar_header(name: &str, size: u64) -> Result<[u8; BLOCK_SIZE], Error> {
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| Error::FileNameParsing(format!("Path too long for archive: {name}")))?
+    };
+
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = header.iter().map(|b| *b as u64).sum();
+    write_octal(&mut header[148..155], checksum);
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Lists the archive with the system `tar`, so the hand-written headers are checked by a real reader
+    fn archived_names(archive: &Path) -> Vec<String> {
+        let output = Command::new("tar")
+            .arg("-tzf")
+            .arg(archive)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn archive_contains_report_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_dir = dir.path().join("report");
+        super::super::create_reports(&[], &report_dir, &Default::default()).unwrap();
+        let long_name = format!("{}/{}.txt", "a".repeat(80), "b".repeat(80));
+        let long_file = report_dir.join(&long_name);
+        fs::create_dir_all(long_file.parent().unwrap()).unwrap();
+        fs::write(&long_file, "long").unwrap();
+
+        let archive = create_archive(&report_dir).unwrap();
+        assert_eq!(archive, dir.path().join("report.tar.gz"));
+        let names = archived_names(&archive);
+        assert!(names.contains(&"index.html".to_string()));
+        assert!(names.contains(&"report.json".to_string()));
+        assert!(names.contains(&long_name));
+    }
+
+    #[test]
+    fn long_names_use_prefix() {
+        let name = format!("{}/{}", "a".repeat(80), "b".repeat(80));
+        let header = tar_header(&name, 0).unwrap();
+        assert_eq!(&header[..80], "b".repeat(80).as_bytes());
+        assert_eq!(&header[345..425], "a".repeat(80).as_bytes());
+        assert!(tar_header(&"c".repeat(300), 0).is_err());
+    }
+}
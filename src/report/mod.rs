@@ -1,19 +1,24 @@
+mod archive;
 mod template;
 
-use crate::csv::{DiffType, Position, Table};
+use crate::csv::{prepare_tables, DetailView, DiffType, Position, Side};
 use crate::properties::MetaDataPropertyDiff;
 use crate::{CSVCompareConfig, ComparisonMode, Rule};
+use data_encoding::BASE64;
 use pdf_extract::extract_text;
-use serde::Serialize;
+use schemars_derive::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
 use std::iter::zip;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tera::{Context, Tera};
 use thiserror::Error;
-use tracing::{debug, error, info, span};
+use tracing::{debug, error, info, span, warn};
 use vg_errortools::{fat_io_wrap_std, FatIOError};
 
 #[derive(Error, Debug)]
@@ -38,6 +43,18 @@ pub enum Error {
     PdfExtract(#[from] pdf_extract::OutputError),
 }
 
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+/// The form in which the report is written
+pub enum ReportFormat {
+    /// A folder containing the html report and `report.json`
+    #[default]
+    Folder,
+    /// The report folder and additionally a `<report folder>.tar.gz` archive of it
+    FolderAndTarGz,
+    /// Only a `<report folder>.tar.gz` archive, the report folder is removed after archiving
+    TarGz,
+}
+
 #[derive(Serialize, Debug, Default, Clone)]
 pub struct AdditionalOverviewColumn {
     pub nominal_value: String,
@@ -60,29 +77,136 @@ pub struct CSVReportColumn {
 
 #[derive(Serialize, Debug, Clone)]
 pub struct CSVReportRow {
+    pub row: usize,
     pub columns: Vec<CSVReportColumn>,
     pub has_diff: bool,  //tolerable error
     pub has_error: bool, //intolerable error
 }
 
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct CSVColumnSummary {
+    pub column: usize,
+    pub header: Option<String>,
+    pub diff_count: usize,
+    pub out_of_tolerance_count: usize,
+    /// largest absolute deviation of the out of tolerance cells and its row
+    pub worst_deviation: Option<(f64, usize)>,
+}
+
+/// The column a difference belongs to, `None` for differences of the whole table or row
+fn diff_column(diff: &DiffType) -> Option<usize> {
+    match diff {
+        DiffType::UnequalStrings { position, .. }
+        | DiffType::OutOfTolerance { position, .. }
+        | DiffType::DifferentValueTypes { position, .. }
+        | DiffType::UnsortedColumn { position, .. }
+        | DiffType::IncompatibleUnits { position, .. }
+        | DiffType::UnequalJson { position, .. }
+        | DiffType::RegexMismatch { position, .. } => Some(position.col),
+        DiffType::UnequalColumnType { column, .. } => Some(*column),
+        _ => None,
+    }
+}
+
+/// Aggregates the differences per column, only columns with differences are listed
+pub(crate) fn summarize_columns(
+    diffs: &[&DiffType],
+    headers: &[Option<String>],
+) -> Vec<CSVColumnSummary> {
+    let mut summaries: Vec<CSVColumnSummary> = Vec::new();
+    for diff in diffs {
+        let Some(column) = diff_column(diff) else {
+            continue;
+        };
+        let index = match summaries.iter().position(|s| s.column == column) {
+            Some(index) => index,
+            None => {
+                summaries.push(CSVColumnSummary {
+                    column,
+                    header: headers.get(column).cloned().flatten(),
+                    diff_count: 0,
+                    out_of_tolerance_count: 0,
+                    worst_deviation: None,
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.diff_count += 1;
+        if let DiffType::OutOfTolerance {
+            deviation,
+            position,
+            ..
+        } = diff
+        {
+            summary.out_of_tolerance_count += 1;
+            match summary.worst_deviation {
+                Some((worst, _)) if worst >= deviation.absolute => {}
+                _ => summary.worst_deviation = Some((deviation.absolute, position.row)),
+            }
+        }
+    }
+    summaries.sort_by_key(|s| s.column);
+    summaries
+}
+
 #[derive(Serialize, Debug, Clone)]
+/// The results of all file comparisons of a rule
 pub struct RuleDifferences {
+    /// the rule the files were compared with
     pub rule: Rule,
+    /// one entry per compared file
     pub diffs: Vec<Difference>,
+    /// the files matching the include patterns which were not compared, only with `report_excluded_files`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<ExcludedFile>,
+    /// the files present on one side only with `Intersection` pairing, listed for information without failing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub one_sided: Vec<ExcludedFile>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// A file matching the include patterns of a rule which was not compared
+pub struct ExcludedFile {
+    /// path of the file relative to the compared folder
+    pub relative_file_path: String,
+    /// why the file was not compared
+    pub reason: String,
 }
 
 #[derive(Serialize, Debug, Clone)]
 pub struct RenderToHtmlRuleDifferences {
     pub rule: Rule,
     pub diffs: Vec<RenderToHtmlDifference>,
+    pub excluded: Vec<ExcludedFile>,
+    pub one_sided: Vec<ExcludedFile>,
+}
+
+/// Why a compared file pair failed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The files could be compared but their contents differ
+    ContentDiffers,
+    /// The files could not be compared, e.g. due to IO errors, unparsable files or a failing external tool
+    ComparisonFailed,
 }
 
 #[derive(Serialize, Debug, Clone, Default)]
+/// The result of comparing a nominal file with an actual file
 pub struct Difference {
+    /// path of the nominal file
     pub nominal_file: PathBuf,
+    /// path of the actual file
     pub actual_file: PathBuf,
+    /// path of the file relative to the compared folder
     pub relative_file_path: String,
+    /// the files differ or could not be compared
     pub is_error: bool,
+    /// the comparison raised a warning
+    pub is_warning: bool,
+    /// the comparison itself failed, `is_error` is set as well
+    pub comparison_failed: bool,
+    /// the details of the comparison, e.g. every differing cell
     pub detail: Vec<DiffDetail>,
 }
 
@@ -95,6 +219,7 @@ pub struct RenderToHtmlDifference {
 }
 
 impl Difference {
+    /// An empty, successful result for the given files
     pub fn new_for_file(nominal: impl AsRef<Path>, actual: impl AsRef<Path>) -> Self {
         Self {
             relative_file_path: get_relative_path(actual.as_ref(), nominal.as_ref())
@@ -106,19 +231,45 @@ impl Difference {
         }
     }
 
+    /// Marks the difference as failed because the contents differ
     pub fn error(&mut self) {
         self.is_error = true;
     }
 
+    /// Marks the difference as failed because the files could not be compared
+    pub fn fail(&mut self) {
+        self.is_error = true;
+        self.comparison_failed = true;
+    }
+
+    /// Why the comparison failed, `None` if it succeeded
+    pub fn error_kind(&self) -> Option<ErrorKind> {
+        match (self.is_error, self.comparison_failed) {
+            (false, _) => None,
+            (true, false) => Some(ErrorKind::ContentDiffers),
+            (true, true) => Some(ErrorKind::ComparisonFailed),
+        }
+    }
+
+    /// Marks the difference as warning
+    pub fn warn(&mut self) {
+        self.is_warning = true;
+    }
+
+    /// Adds a detail to the difference
     pub fn push_detail(&mut self, detail: DiffDetail) {
         self.detail.push(detail);
     }
 
+    /// Merges the results of another comparison of the same files into this one.
+    /// Returns false and does nothing if the nominal files differ
     pub fn join(&mut self, other: Self) -> bool {
         if self.nominal_file != other.nominal_file {
             return false;
         }
         self.is_error |= other.is_error;
+        self.is_warning |= other.is_warning;
+        self.comparison_failed |= other.comparison_failed;
         self.detail.extend(other.detail);
         true
     }
@@ -126,36 +277,206 @@ impl Difference {
 
 #[derive(Serialize, Debug, Clone)]
 #[allow(clippy::upper_case_acronyms)]
+/// A single finding of a file comparison
 pub enum DiffDetail {
+    /// A difference between two csv tables
     CSV(DiffType),
+    /// The images are not similar enough
     Image {
+        /// similarity of the images
         score: f64,
-        diff_image: String,
+        /// similarity of the alpha channels, if compared separately
+        alpha_score: Option<f64>,
+        /// file name of the difference image, if generated
+        diff_image: Option<String>,
+        /// file name of the csv grid of the similarity values, if exported
+        similarity_map: Option<String>,
     },
+    /// A line of a text file differs
     Text {
+        /// actual line
         actual: String,
+        /// nominal line
         nominal: String,
+        /// line number
         line: usize,
+        /// similarity of the lines
         score: f64,
     },
+    /// The text formatting differs, e.g. the line endings
+    TextFormat {
+        /// actual format
+        actual: String,
+        /// nominal format
+        nominal: String,
+    },
+    /// The hashes differ
     Hash {
+        /// actual hash
+        actual: String,
+        /// nominal hash
+        nominal: String,
+    },
+    /// The first differing byte of two binary files
+    Binary {
+        /// offset of the first differing byte
+        offset: u64,
+        /// hex bytes of actual starting at the offset
         actual: String,
+        /// hex bytes of nominal starting at the offset
         nominal: String,
     },
+    /// The output of a failed external checker
     External {
+        /// standard output of the checker
         stdout: String,
+        /// standard error of the checker
         stderr: String,
+        /// how often the checker was run
+        attempts: u32,
     },
+    /// A difference reported by an external checker in its JSON output
+    ExternalFinding {
+        /// description of the difference
+        message: String,
+        /// where the difference was found, e.g. a page or line
+        location: Option<String>,
+        /// the checker reported it as warning instead of error
+        warning: bool,
+    },
+    /// The json documents differ
     Json {
+        /// the keys with mismatching values
         differences: String,
+        /// the keys only present in actual
         right: String,
+        /// the keys only present in nominal
         left: String,
+        /// the mismatch of the document roots, if any
         root_mismatch: Option<String>,
+        /// the differences ignored by the configuration
+        ignored: String,
     },
+    /// The file metadata differs
     Properties(MetaDataPropertyDiff),
+    /// A message describing a failure
     Error(String),
 }
 
+impl Display for DiffDetail {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffDetail::CSV(diff) => write!(f, "{diff}"),
+            DiffDetail::Image {
+                score,
+                alpha_score,
+                diff_image,
+                ..
+            } => {
+                write!(f, "Image similarity {score}")?;
+                if let Some(alpha_score) = alpha_score {
+                    write!(f, ", alpha similarity {alpha_score}")?;
+                }
+                match diff_image {
+                    Some(diff_image) => write!(f, ", diff image: {diff_image}"),
+                    None => Ok(()),
+                }
+            }
+            DiffDetail::Text {
+                actual,
+                nominal,
+                line,
+                score,
+            } => write!(
+                f,
+                "Mismatch in line {line}. Expected: '{nominal}' found '{actual}' (diff: {score})"
+            ),
+            DiffDetail::TextFormat { actual, nominal } => write!(
+                f,
+                "Mismatch in text format. Expected: '{nominal}' found '{actual}'"
+            ),
+            DiffDetail::Hash { actual, nominal } => {
+                write!(f, "Nominal file's hash is '{nominal}' actual is '{actual}'")
+            }
+            DiffDetail::Binary {
+                offset,
+                actual,
+                nominal,
+            } => write!(
+                f,
+                "First difference at byte {offset}. Expected: '{nominal}' found '{actual}'"
+            ),
+            DiffDetail::External {
+                stdout,
+                stderr,
+                attempts,
+            } => {
+                write!(
+                    f,
+                    "External checker output after {attempts} attempt(s): {stdout} {stderr}"
+                )
+            }
+            DiffDetail::ExternalFinding {
+                message,
+                location,
+                warning,
+            } => {
+                let severity = if *warning { "Warning" } else { "Error" };
+                match location {
+                    Some(location) => write!(f, "{severity} at {location}: {message}"),
+                    None => write!(f, "{severity}: {message}"),
+                }
+            }
+            DiffDetail::Json {
+                differences,
+                right,
+                left,
+                root_mismatch,
+                ..
+            } => {
+                if let Some(root_mismatch) = root_mismatch {
+                    write!(f, "Root mismatch: {root_mismatch} ")?;
+                }
+                write!(
+                    f,
+                    "Differences: [{differences}] Left extra: [{left}] Right extra: [{right}]"
+                )
+            }
+            DiffDetail::Properties(MetaDataPropertyDiff::Size { nominal, actual }) => {
+                write!(f, "File size: expected {nominal}, found {actual}")
+            }
+            DiffDetail::Properties(MetaDataPropertyDiff::IllegalName) => {
+                write!(f, "File name matches the forbidden name regex")
+            }
+            DiffDetail::Properties(MetaDataPropertyDiff::CreationDate { nominal, actual }) => {
+                write!(f, "Modification date: expected {nominal}, found {actual}")
+            }
+            DiffDetail::Error(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FlatDifference<'a> {
+    pub rule: &'a str,
+    pub relative_file_path: &'a str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReportOptions {
+    /// additionally write `diffs_flat.json` with one entry per diff detail
+    pub flat_diffs: bool,
+    /// copy the compared csv files into their detail folders and render from the copies
+    pub embed_csv_sources: bool,
+    /// inline the images of image details as base64 data uris instead of copying them
+    pub inline_images: bool,
+    /// hash of the configuration the report was created with
+    pub config_hash: String,
+    /// additionally write `report.json` to this path
+    pub json_out: Option<PathBuf>,
+}
+
 pub fn create_detail_folder(report_dir: impl AsRef<Path>) -> Result<DetailPath, Error> {
     let temp_path = tempfile::Builder::new()
         .prefix("havocompare-")
@@ -220,28 +541,53 @@ pub(crate) fn write_csv_detail(
     diffs: &[&DiffType],
     config: &CSVCompareConfig,
     report_dir: impl AsRef<Path>,
+    embed_sources: bool,
 ) -> Result<Option<DetailPath>, Error> {
     let mut headers: CSVReportRow = CSVReportRow {
+        row: 0,
         columns: vec![],
         has_diff: false,
         has_error: false,
     };
 
-    let mut nominal_table = Table::from_reader(File::open(nominal.as_ref())?, &config.delimiters)?;
-    let mut actual_table = Table::from_reader(File::open(actual.as_ref())?, &config.delimiters)?;
-
-    if let Some(preprocessors) = &config.preprocessing {
-        for preprocessor in preprocessors.iter() {
-            preprocessor.process(&mut nominal_table)?;
-            preprocessor.process(&mut actual_table)?;
-        }
+    let mut detail_path = None;
+    let mut embedded_sources = None;
+    if embed_sources {
+        let path = create_detail_folder(report_dir.as_ref())?;
+        let embed = |source: &Path, prefix: &str| -> Result<String, Error> {
+            let file_name = source
+                .file_name()
+                .ok_or_else(|| Error::FileNameParsing(source.to_string_lossy().to_string()))?;
+            let embedded_name = format!("{prefix}_{}", file_name.to_string_lossy());
+            fat_io_wrap_std(source, &|s| fs::copy(s, path.path.join(&embedded_name)))?;
+            Ok(embedded_name)
+        };
+        embedded_sources = Some((
+            embed(nominal.as_ref(), "nominal")?,
+            embed(actual.as_ref(), "actual")?,
+        ));
+        detail_path = Some(path);
     }
 
+    let (nominal_table, actual_table) = match (&detail_path, &embedded_sources) {
+        (Some(path), Some((nominal_copy, actual_copy))) => prepare_tables(
+            File::open(path.path.join(nominal_copy))?,
+            File::open(path.path.join(actual_copy))?,
+            config,
+        )?,
+        _ => prepare_tables(
+            File::open(nominal.as_ref())?,
+            File::open(actual.as_ref())?,
+            config,
+        )?,
+    };
+
     nominal_table
         .columns
         .iter()
         .zip(actual_table.columns.iter())
-        .for_each(|(n, a)| {
+        .enumerate()
+        .for_each(|(col, (n, a))| {
             let a_header = a.header.as_deref();
             let n_header = n.header.as_deref();
 
@@ -249,14 +595,37 @@ pub(crate) fn write_csv_detail(
                 let actual_value = a_header.to_owned();
                 let nominal_value = n_header.to_owned();
 
-                if nominal_value != actual_value {
+                if !config.headers_equal(&nominal_value, &actual_value) {
+                    headers.has_diff = true;
+                }
+
+                let column_diffs: Vec<String> = diffs
+                    .iter()
+                    .filter_map(|diff| match diff {
+                        DiffType::UnequalColumnType {
+                            column,
+                            nominal,
+                            actual,
+                        } if *column == col => {
+                            Some(format!("Different column types: {nominal} vs {actual}"))
+                        }
+                        DiffType::MissingColumn { header, side }
+                            if (*side == Side::Actual && header == n_header)
+                                || (*side == Side::Nominal && header == a_header) =>
+                        {
+                            Some(format!("Column '{header}' missing in {side}"))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if !column_diffs.is_empty() {
                     headers.has_diff = true;
                 }
 
                 headers.columns.push(CSVReportColumn {
                     actual_value,
                     nominal_value,
-                    diffs: Vec::new(),
+                    diffs: column_diffs,
                 });
             }
         });
@@ -285,6 +654,11 @@ pub(crate) fn write_csv_detail(
                                     DiffType::UnequalStrings { position, .. } => position,
                                     DiffType::OutOfTolerance { position, .. } => position,
                                     DiffType::DifferentValueTypes { position, .. } => position,
+                                    DiffType::UnsortedColumn { position, .. } => position,
+                                    DiffType::IncompatibleUnits { position, .. } => position,
+                                    DiffType::UnequalJson { position, .. } => position,
+                                    DiffType::RegexMismatch { position, .. } => position,
+                                    DiffType::MissingRow { position, .. } => position,
                                     _ => {
                                         return false;
                                     }
@@ -294,12 +668,27 @@ pub(crate) fn write_csv_detail(
                             })
                             .map(|diff| match diff {
                                 DiffType::UnequalStrings { .. } => "Different strings".to_owned(),
-                                DiffType::OutOfTolerance { mode, .. } => {
-                                    format!("Out of tolerance. Mode: {mode}")
+                                DiffType::OutOfTolerance {
+                                    mode, deviation, ..
+                                } => {
+                                    format!("Out of tolerance. Mode: {mode}, {deviation}")
                                 }
                                 DiffType::DifferentValueTypes { .. } => {
                                     "Different value types".to_owned()
                                 }
+                                DiffType::UnsortedColumn { .. } => "Not sorted".to_owned(),
+                                DiffType::IncompatibleUnits { .. } => {
+                                    "Incompatible units".to_owned()
+                                }
+                                DiffType::MissingRow { side, .. } => {
+                                    format!("Row missing in {side}")
+                                }
+                                DiffType::UnequalJson { differences, .. } => {
+                                    format!("Different JSON: {differences}")
+                                }
+                                DiffType::RegexMismatch { pattern, .. } => {
+                                    format!("Does not match '{pattern}'")
+                                }
                                 _ => "Unknown difference".to_owned(),
                             })
                             .collect(),
@@ -318,6 +707,7 @@ pub(crate) fn write_csv_detail(
                 .collect();
 
             CSVReportRow {
+                row,
                 has_error,
                 has_diff,
                 columns,
@@ -325,21 +715,58 @@ pub(crate) fn write_csv_detail(
         })
         .collect();
 
-    let detail_path = create_detail_folder(report_dir)?;
+    let row_count = rows.len();
+    let sampled_rows = config.sampled_rows(row_count);
+    let rows: Vec<CSVReportRow> = match sampled_rows.as_ref() {
+        Some(sampled_rows) => rows
+            .into_iter()
+            .filter(|r| sampled_rows.binary_search(&r.row).is_ok())
+            .collect(),
+        None => rows,
+    };
+    let diff_rows_only = config.detail_view == DetailView::DiffRows;
+    let rows: Vec<CSVReportRow> = if diff_rows_only {
+        rows.into_iter()
+            .filter(|r| r.has_diff || r.has_error)
+            .collect()
+    } else {
+        rows
+    };
+
+    let detail_path = match detail_path {
+        Some(path) => path,
+        None => create_detail_folder(report_dir)?,
+    };
 
     let detail_file = detail_path.path.join(template::DETAIL_FILENAME);
 
     let mut tera = Tera::default();
-    tera.add_raw_template(
-        &detail_file.to_string_lossy(),
-        template::PLAIN_CSV_DETAIL_TEMPLATE,
-    )?;
+    let detail_template = match config.detail_view {
+        DetailView::Rows | DetailView::DiffRows => template::PLAIN_CSV_DETAIL_TEMPLATE,
+        DetailView::Columns => template::PLAIN_CSV_COLUMN_DETAIL_TEMPLATE,
+    };
+    tera.add_raw_template(&detail_file.to_string_lossy(), detail_template)?;
 
     let mut ctx = Context::new();
     ctx.insert("actual", &actual.as_ref().to_string_lossy());
     ctx.insert("nominal", &nominal.as_ref().to_string_lossy());
+    if config.detail_view == DetailView::Columns {
+        let column_headers: Vec<Option<String>> = nominal_table
+            .columns
+            .iter()
+            .map(|c| c.header.clone())
+            .collect();
+        ctx.insert(
+            "column_summaries",
+            &summarize_columns(diffs, &column_headers),
+        );
+    }
     ctx.insert("rows", &rows);
     ctx.insert("headers", &headers);
+    ctx.insert("sampled", &sampled_rows.is_some());
+    ctx.insert("diff_rows_only", &diff_rows_only);
+    ctx.insert("row_count", &row_count);
+    ctx.insert("embedded_sources", &embedded_sources);
 
     let file = fat_io_wrap_std(&detail_file, &File::create)?;
     debug!("detail html {:?} created", &detail_file);
@@ -349,11 +776,20 @@ pub(crate) fn write_csv_detail(
     Ok(Some(detail_path))
 }
 
+/// Score, alpha score, diff image and similarity map of an image difference
+pub type ImageDiff<'a> = (
+    &'a f64,
+    &'a Option<f64>,
+    &'a Option<String>,
+    &'a Option<String>,
+);
+
 pub fn write_image_detail(
     nominal: impl AsRef<Path>,
     actual: impl AsRef<Path>,
-    diffs: &[(&f64, &String)],
+    diffs: &[ImageDiff],
     report_dir: impl AsRef<Path>,
+    inline_images: bool,
 ) -> Result<Option<DetailPath>, Error> {
     if diffs.is_empty() {
         return Ok(None);
@@ -384,21 +820,56 @@ pub fn write_image_detail(
             })
     }
 
-    let actual_image = format!("actual_image_{}", get_file_name(actual.as_ref())?);
-    let nominal_image = format!("nominal_image_.{}", get_file_name(nominal.as_ref())?);
-
-    fs::copy(actual.as_ref(), detail_path.path.join(&actual_image))
-        .map_err(|e| FatIOError::from_std_io_err(e, actual.as_ref().to_path_buf()))?;
-    fs::copy(nominal.as_ref(), detail_path.path.join(&nominal_image))
-        .map_err(|e| FatIOError::from_std_io_err(e, nominal.as_ref().to_path_buf()))?;
+    // the image sources are either data uris or paths relative to the detail file
+    let image_source = |image: &Path, target_name: &str| -> Result<String, Error> {
+        if inline_images {
+            return image_data_uri(image);
+        }
+        fs::copy(image, detail_path.path.join(target_name))
+            .map_err(|e| FatIOError::from_std_io_err(e, image.to_path_buf()))?;
+        Ok(format!("./{target_name}"))
+    };
+    let actual_image = image_source(
+        actual.as_ref(),
+        &format!("actual_image_{}", get_file_name(actual.as_ref())?),
+    )?;
+    let nominal_image = image_source(
+        nominal.as_ref(),
+        &format!("nominal_image_.{}", get_file_name(nominal.as_ref())?),
+    )?;
 
-    let (score, diff_image) = diffs[0];
-    let img_target = detail_path.path.join(diff_image);
-    fs::copy(diff_image, &img_target)
-        .map_err(|e| FatIOError::from_std_io_err(e, img_target.to_path_buf()))?;
+    let (score, alpha_score, diff_image, similarity_map) = diffs[0];
+    // diff image and similarity map are temporary files of the comparison, moved into the report
+    let diff_image = diff_image
+        .as_deref()
+        .map(|diff_image| -> Result<String, Error> {
+            let diff_image = Path::new(diff_image);
+            let source = image_source(diff_image, &get_file_name(diff_image)?)?;
+            remove_temporary_file(diff_image);
+            Ok(source)
+        })
+        .transpose()?;
+    // the similarity map is data, so it is always linked instead of inlined
+    let similarity_map = similarity_map
+        .as_deref()
+        .map(|similarity_map| -> Result<String, Error> {
+            let similarity_map = Path::new(similarity_map);
+            let target_name = get_file_name(similarity_map)?.to_string();
+            let target = detail_path.path.join(&target_name);
+            fs::copy(similarity_map, &target)
+                .map_err(|e| FatIOError::from_std_io_err(e, target.to_path_buf()))?;
+            remove_temporary_file(similarity_map);
+            Ok(target_name)
+        })
+        .transpose()?;
 
-    ctx.insert("error", &format!("Score {score}"));
-    ctx.insert("diff_image", diff_image);
+    let error = match alpha_score {
+        Some(alpha_score) => format!("Score {score}, alpha score {alpha_score}"),
+        None => format!("Score {score}"),
+    };
+    ctx.insert("error", &error);
+    ctx.insert("diff_image", &diff_image);
+    ctx.insert("similarity_map", &similarity_map);
     ctx.insert("actual_image", &actual_image);
     ctx.insert("nominal_image", &nominal_image);
 
@@ -410,6 +881,37 @@ pub fn write_image_detail(
     Ok(Some(detail_path))
 }
 
+fn remove_temporary_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        warn!(
+            "Could not remove temporary file {}: {e}",
+            path.to_string_lossy()
+        );
+    }
+}
+
+/// The image file as base64 encoded data uri, e.g. `data:image/png;base64,...`
+fn image_data_uri(image: &Path) -> Result<String, Error> {
+    let extension = image
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    };
+    let content = fat_io_wrap_std(image, &fs::read)?;
+    Ok(format!(
+        "data:{mime_type};base64,{}",
+        BASE64.encode(&content)
+    ))
+}
+
 pub fn write_pdf_detail(
     nominal: impl AsRef<Path>,
     actual: impl AsRef<Path>,
@@ -481,6 +983,7 @@ pub fn write_external_detail(
     actual: impl AsRef<Path>,
     stdout: &str,
     stderr: &str,
+    attempts: u32,
     report_dir: impl AsRef<Path>,
 ) -> Result<Option<DetailPath>, Error> {
     let detail_path = create_detail_folder(report_dir.as_ref())?;
@@ -497,6 +1000,7 @@ pub fn write_external_detail(
     ctx.insert("nominal", &nominal.as_ref().to_string_lossy());
     ctx.insert("stdout", stdout);
     ctx.insert("stderr", stderr);
+    ctx.insert("attempts", &attempts);
 
     let file = fat_io_wrap_std(&detail_file, &File::create)?;
     debug!("detail html {:?} created", &detail_file);
@@ -506,6 +1010,7 @@ pub fn write_external_detail(
     Ok(Some(detail_path))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn write_json_detail(
     nominal: impl AsRef<Path>,
     actual: impl AsRef<Path>,
@@ -513,6 +1018,7 @@ pub fn write_json_detail(
     right: &str,
     differences: &str,
     root_mismatch: &Option<String>,
+    ignored: &str,
     report_dir: impl AsRef<Path>,
 ) -> Result<Option<DetailPath>, Error> {
     let detail_path = create_detail_folder(report_dir.as_ref())?;
@@ -531,6 +1037,7 @@ pub fn write_json_detail(
     ctx.insert("left", left);
     ctx.insert("right", right);
     ctx.insert("root_mismatch", root_mismatch);
+    ctx.insert("ignored", ignored);
 
     let file = fat_io_wrap_std(&detail_file, &File::create)?;
     debug!("detail html {:?} created", &detail_file);
@@ -583,6 +1090,7 @@ pub fn write_error_detail(
 pub(crate) fn create_reports(
     rule_differences: &[RuleDifferences],
     report_path: impl AsRef<Path>,
+    options: &ReportOptions,
 ) -> Result<(), Error> {
     let _reporting_span = span!(tracing::Level::INFO, "Reporting").entered();
     let report_dir = report_path.as_ref();
@@ -594,11 +1102,35 @@ pub(crate) fn create_reports(
     fat_io_wrap_std(&report_dir, &fs::create_dir)?;
 
     create_json(rule_differences, &report_path)?;
-    create_html(rule_differences, &report_path)?;
+    write_config_hash(&report_path, &options.config_hash)?;
+    if let Some(json_out) = options.json_out.as_deref() {
+        copy_json(&report_path, json_out)?;
+    }
+    if options.flat_diffs {
+        create_flat_json(rule_differences, &report_path)?;
+    }
+    create_html(rule_differences, &report_path, options)?;
 
     Ok(())
 }
 
+pub(crate) fn archive_report(
+    report_path: impl AsRef<Path>,
+    format: ReportFormat,
+) -> Result<(), Error> {
+    if format == ReportFormat::Folder {
+        return Ok(());
+    }
+    let _reporting_span = span!(tracing::Level::INFO, "Archive").entered();
+    let archive = archive::create_archive(report_path.as_ref())?;
+    info!("Report archived to {}", archive.to_string_lossy());
+    if format == ReportFormat::TarGz {
+        info!("Delete report folder");
+        fat_io_wrap_std(report_path.as_ref(), &fs::remove_dir_all)?;
+    }
+    Ok(())
+}
+
 pub(crate) fn create_json(
     rule_differences: &[RuleDifferences],
     report_path: impl AsRef<Path>,
@@ -611,9 +1143,165 @@ pub(crate) fn create_json(
     Ok(())
 }
 
+/// Writes the configuration hash to `config_hash.txt`, next to `report.json` whose layout stays unchanged
+fn write_config_hash(report_path: impl AsRef<Path>, config_hash: &str) -> Result<(), Error> {
+    let file = report_path.as_ref().join("config_hash.txt");
+    fs::write(&file, config_hash).map_err(|e| FatIOError::from_std_io_err(e, file))?;
+    Ok(())
+}
+
+/// Totals over all rules of a run
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Summary {
+    pub rules: usize,
+    pub failed_rules: usize,
+    pub files: usize,
+    pub failed_files: usize,
+    pub failed_comparisons: usize,
+    pub warned_files: usize,
+    pub csv_diffs: usize,
+    pub worst_image_score: Option<f64>,
+}
+
+impl Summary {
+    /// Aggregates the rule results, `rules_okay` holds the overall result of each rule including processing errors
+    pub(crate) fn new(rule_differences: &[RuleDifferences], rules_okay: &[bool]) -> Self {
+        let files = || rule_differences.iter().flat_map(|r| r.diffs.iter());
+        let details = || files().flat_map(|d| d.detail.iter());
+        Summary {
+            rules: rules_okay.len(),
+            failed_rules: rules_okay.iter().filter(|okay| !**okay).count(),
+            files: files().count(),
+            failed_files: files().filter(|d| d.is_error).count(),
+            failed_comparisons: files()
+                .filter(|d| d.error_kind() == Some(ErrorKind::ComparisonFailed))
+                .count(),
+            warned_files: files().filter(|d| !d.is_error && d.is_warning).count(),
+            csv_diffs: details()
+                .filter(|d| matches!(d, DiffDetail::CSV(_)))
+                .count(),
+            worst_image_score: details()
+                .filter_map(|d| match d {
+                    DiffDetail::Image { score, .. } => Some(*score),
+                    _ => None,
+                })
+                .reduce(f64::min),
+        }
+    }
+}
+
+/// Writes the run summary as Prometheus text format metrics
+pub(crate) fn write_metrics(
+    summary: &Summary,
+    duration: Duration,
+    metrics_out: &Path,
+) -> Result<(), Error> {
+    let worst_image_score = summary
+        .worst_image_score
+        .map(|score| score.to_string())
+        .unwrap_or_else(|| "NaN".to_owned());
+    let metrics = [
+        (
+            "rules_total",
+            "Number of rules processed",
+            summary.rules.to_string(),
+        ),
+        (
+            "rules_failed",
+            "Number of failed rules",
+            summary.failed_rules.to_string(),
+        ),
+        (
+            "files_total",
+            "Number of compared files",
+            summary.files.to_string(),
+        ),
+        (
+            "files_failed",
+            "Number of failed files",
+            summary.failed_files.to_string(),
+        ),
+        (
+            "files_comparison_failed",
+            "Number of files that could not be compared",
+            summary.failed_comparisons.to_string(),
+        ),
+        (
+            "files_warning",
+            "Number of passed files with warnings",
+            summary.warned_files.to_string(),
+        ),
+        (
+            "csv_diffs_total",
+            "Number of CSV differences",
+            summary.csv_diffs.to_string(),
+        ),
+        (
+            "image_score_worst",
+            "Lowest reported image similarity score, NaN if none",
+            worst_image_score,
+        ),
+        (
+            "run_duration_seconds",
+            "Duration of the comparison run",
+            duration.as_secs_f64().to_string(),
+        ),
+    ];
+    let content: String = metrics
+        .iter()
+        .map(|(name, help, value)| {
+            format!("# HELP havocompare_{name} {help}\n# TYPE havocompare_{name} gauge\nhavocompare_{name} {value}\n")
+        })
+        .collect();
+    if let Some(parent) = metrics_out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fat_io_wrap_std(parent, &fs::create_dir_all)?;
+    }
+    fs::write(metrics_out, content)
+        .map_err(|e| FatIOError::from_std_io_err(e, metrics_out.to_path_buf()))?;
+    info!("Wrote metrics to {}", metrics_out.to_string_lossy());
+    Ok(())
+}
+
+fn copy_json(report_path: impl AsRef<Path>, json_out: &Path) -> Result<(), Error> {
+    if let Some(parent) = json_out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fat_io_wrap_std(parent, &fs::create_dir_all)?;
+    }
+    fs::copy(report_path.as_ref().join("report.json"), json_out)
+        .map_err(|e| FatIOError::from_std_io_err(e, json_out.to_path_buf()))?;
+    info!("Wrote report.json to {}", json_out.to_string_lossy());
+    Ok(())
+}
+
+pub(crate) fn flatten_differences(rule_differences: &[RuleDifferences]) -> Vec<FlatDifference<'_>> {
+    rule_differences
+        .iter()
+        .flat_map(|rule_difference| {
+            rule_difference.diffs.iter().flat_map(move |difference| {
+                difference.detail.iter().map(move |detail| FlatDifference {
+                    rule: rule_difference.rule.name.as_str(),
+                    relative_file_path: difference.relative_file_path.as_str(),
+                    message: detail.to_string(),
+                })
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn create_flat_json(
+    rule_differences: &[RuleDifferences],
+    report_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let _reporting_span = span!(tracing::Level::INFO, "Flat JSON").entered();
+    let writer = report_path.as_ref().join("diffs_flat.json");
+    let writer = fat_io_wrap_std(writer, &File::create)?;
+    serde_json::to_writer_pretty(writer, &flatten_differences(rule_differences))?;
+    Ok(())
+}
+
 pub(crate) fn create_html(
     rule_differences: &[RuleDifferences],
     report_path: impl AsRef<Path>,
+    options: &ReportOptions,
 ) -> Result<(), Error> {
     let _reporting_span = span!(tracing::Level::INFO, "HTML").entered();
     let report_dir = report_path.as_ref();
@@ -667,6 +1355,7 @@ pub(crate) fn create_html(
                             &diffs,
                             config,
                             &sub_folder,
+                            options.embed_csv_sources,
                         )
                         .unwrap_or_else(|e| log_detail_html_creation_error(&e))
                     }
@@ -684,6 +1373,10 @@ pub(crate) fn create_html(
                                     "Mismatch in line {}. Expected: '{}' found '{}' (diff: {})",
                                     line, nominal, actual, score
                                 )),
+                                DiffDetail::TextFormat { actual, nominal } => Some(format!(
+                                    "Mismatch in text format. Expected: '{}' found '{}'",
+                                    nominal, actual
+                                )),
                                 _ => None,
                             })
                             .collect();
@@ -721,13 +1414,16 @@ pub(crate) fn create_html(
                             .unwrap_or_else(|e| log_detail_html_creation_error(&e))
                     }
                     ComparisonMode::Image(_) => {
-                        let diffs: Vec<(&f64, &String)> = file
+                        let diffs: Vec<ImageDiff> = file
                             .detail
                             .iter()
                             .filter_map(|r| match r {
-                                DiffDetail::Image { score, diff_image } => {
-                                    Some((score, diff_image))
-                                }
+                                DiffDetail::Image {
+                                    score,
+                                    alpha_score,
+                                    diff_image,
+                                    similarity_map,
+                                } => Some((score, alpha_score, diff_image, similarity_map)),
                                 _ => None,
                             })
                             .collect();
@@ -737,15 +1433,20 @@ pub(crate) fn create_html(
                             &file.actual_file,
                             &diffs, //should actually only 1 image per file compare
                             &sub_folder,
+                            options.inline_images,
                         )
                         .unwrap_or_else(|e| log_detail_html_creation_error(&e))
                     }
                     ComparisonMode::External(_) => {
-                        if let Some((stdout, stderr)) = file
+                        if let Some((stdout, stderr, attempts)) = file
                             .detail
                             .iter()
                             .filter_map(|r| match r {
-                                DiffDetail::External { stdout, stderr } => Some((stdout, stderr)),
+                                DiffDetail::External {
+                                    stdout,
+                                    stderr,
+                                    attempts,
+                                } => Some((stdout, stderr, *attempts)),
                                 _ => None,
                             })
                             .next()
@@ -755,15 +1456,28 @@ pub(crate) fn create_html(
                                 &file.actual_file,
                                 stdout,
                                 stderr,
+                                attempts,
                                 &sub_folder,
                             )
                             .unwrap_or_else(|e| log_detail_html_creation_error(&e))
                         } else {
-                            None
+                            let findings: Vec<String> = file
+                                .detail
+                                .iter()
+                                .filter(|r| matches!(r, DiffDetail::ExternalFinding { .. }))
+                                .map(|r| r.to_string())
+                                .collect();
+                            write_html_detail(
+                                &file.nominal_file,
+                                &file.actual_file,
+                                &findings,
+                                &sub_folder,
+                            )
+                            .unwrap_or_else(|e| log_detail_html_creation_error(&e))
                         }
                     }
-                    ComparisonMode::Json(_) => {
-                        if let Some((differences, left, right, root_mismatch)) = file
+                    ComparisonMode::Json(_) | ComparisonMode::Yaml(_) => {
+                        if let Some((differences, left, right, root_mismatch, ignored)) = file
                             .detail
                             .iter()
                             .filter_map(|r| match r {
@@ -772,7 +1486,8 @@ pub(crate) fn create_html(
                                     differences,
                                     right,
                                     root_mismatch,
-                                } => Some((differences, left, right, root_mismatch)),
+                                    ignored,
+                                } => Some((differences, left, right, root_mismatch, ignored)),
                                 _ => None,
                             })
                             .next()
@@ -784,6 +1499,7 @@ pub(crate) fn create_html(
                                 right,
                                 differences,
                                 root_mismatch,
+                                ignored,
                                 &sub_folder,
                             )
                             .unwrap_or_else(|e| log_detail_html_creation_error(&e))
@@ -792,6 +1508,22 @@ pub(crate) fn create_html(
                         }
                     }
                     ComparisonMode::FileProperties(_) => None, //we need only additional columns in the index.html
+                    ComparisonMode::Binary(_) => {
+                        let diffs: Vec<String> = file
+                            .detail
+                            .iter()
+                            .filter(|r| matches!(r, DiffDetail::Binary { .. }))
+                            .map(|r| r.to_string())
+                            .collect();
+
+                        write_html_detail(
+                            &file.nominal_file,
+                            &file.actual_file,
+                            &diffs,
+                            &sub_folder,
+                        )
+                        .unwrap_or_else(|e| log_detail_html_creation_error(&e))
+                    }
                     ComparisonMode::Hash(_) => {
                         let diffs: Vec<String> = file
                             .detail
@@ -891,10 +1623,12 @@ pub(crate) fn create_html(
         html_rule_differences.push(RenderToHtmlRuleDifferences {
             rule: rule_difference.rule.clone(),
             diffs: render_diffs,
+            excluded: rule_difference.excluded.clone(),
+            one_sided: rule_difference.one_sided.clone(),
         });
     }
 
-    write_index(report_dir, &html_rule_differences)?;
+    write_index(report_dir, &html_rule_differences, &options.config_hash)?;
 
     Ok(())
 }
@@ -902,6 +1636,7 @@ pub(crate) fn create_html(
 pub(crate) fn write_index(
     report_dir: impl AsRef<Path>,
     rule_results: &[RenderToHtmlRuleDifferences],
+    config_hash: &str,
 ) -> Result<(), Error> {
     let index_file = report_dir.as_ref().join(template::INDEX_FILENAME);
 
@@ -912,6 +1647,7 @@ pub(crate) fn write_index(
     let mut ctx = Context::new();
     ctx.insert("rule_results", rule_results);
     ctx.insert("detail_filename", template::DETAIL_FILENAME);
+    ctx.insert("config_hash", config_hash);
 
     let file = fat_io_wrap_std(&index_file, &File::create)?;
     tera.render_to(&index_file.to_string_lossy(), &ctx, file)?;
@@ -1028,4 +1764,307 @@ mod tests {
         assert!(sub_folder.path.is_dir());
         assert!(!sub_folder.name.is_empty());
     }
+
+    #[test]
+    fn csv_detail_shows_only_sampled_rows() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = report_dir.path().join("nominal.csv");
+        let actual = report_dir.path().join("actual.csv");
+        fs::write(
+            &nominal,
+            (0..50).map(|i| format!("{i}\n")).collect::<String>(),
+        )
+        .unwrap();
+        fs::write(
+            &actual,
+            (0..50).map(|i| format!("{i}\n")).collect::<String>(),
+        )
+        .unwrap();
+        let config = CSVCompareConfig {
+            sample_rows: Some(3),
+            ..Default::default()
+        };
+
+        let detail = write_csv_detail(&nominal, &actual, &[], &config, &report_dir, false)
+            .unwrap()
+            .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("only 3 of 50 rows were compared"));
+        let sampled = config.sampled_rows(50).unwrap();
+        let shown_rows = html.matches("<td data-order=").count() / 2;
+        assert_eq!(shown_rows, sampled.len());
+        for row in sampled {
+            assert!(html.contains(&format!("<td data-order=\"{row}\">")));
+        }
+    }
+
+    #[test]
+    fn csv_detail_shows_only_differing_rows_in_diff_rows_view() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = report_dir.path().join("nominal.csv");
+        let actual = report_dir.path().join("actual.csv");
+        fs::write(
+            &nominal,
+            (0..50).map(|i| format!("{i}\n")).collect::<String>(),
+        )
+        .unwrap();
+        fs::write(
+            &actual,
+            (0..50)
+                .map(|i| {
+                    if i == 7 || i == 31 {
+                        format!("{}\n", i + 1)
+                    } else {
+                        format!("{i}\n")
+                    }
+                })
+                .collect::<String>(),
+        )
+        .unwrap();
+        let config = CSVCompareConfig {
+            comparison_modes: vec![crate::csv::Mode::Absolute(0.0)],
+            detail_view: DetailView::DiffRows,
+            ..Default::default()
+        };
+        let diffs = crate::csv::compare_paths(&nominal, &actual, &config).unwrap();
+        let diffs: Vec<&DiffType> = diffs
+            .detail
+            .iter()
+            .filter_map(|detail| match detail {
+                DiffDetail::CSV(diff) => Some(diff),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(diffs.len(), 2);
+
+        let detail = write_csv_detail(&nominal, &actual, &diffs, &config, &report_dir, false)
+            .unwrap()
+            .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("only the 2 of 50 rows with differences are shown"));
+        assert_eq!(html.matches("<td data-order=").count() / 2, 2);
+        assert!(html.contains("<td data-order=\"7\">"));
+        assert!(html.contains("<td data-order=\"31\">"));
+    }
+
+    #[test]
+    fn flat_diffs_contain_every_detail() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: "JSON"
+pattern_include:
+  - "*.json"
+Json:
+  ignore_keys: []
+"#,
+        )
+        .unwrap();
+        let mut first = Difference::new_for_file("nominal/a.json", "actual/a.json");
+        first.push_detail(DiffDetail::Error("first".to_string()));
+        first.push_detail(DiffDetail::Hash {
+            actual: "abc".to_string(),
+            nominal: "def".to_string(),
+        });
+        let mut second = Difference::new_for_file("nominal/b.json", "actual/b.json");
+        second.push_detail(DiffDetail::Error("second".to_string()));
+        let clean = Difference::new_for_file("nominal/c.json", "actual/c.json");
+        let rule_differences = vec![RuleDifferences {
+            rule,
+            diffs: vec![first, second, clean],
+            excluded: Vec::new(),
+            one_sided: Vec::new(),
+        }];
+
+        let report_dir = tempfile::tempdir().unwrap();
+        let options = ReportOptions {
+            flat_diffs: true,
+            ..Default::default()
+        };
+        create_reports(&rule_differences, &report_dir, &options).unwrap();
+
+        let flat: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(report_dir.path().join("diffs_flat.json")).unwrap(),
+        )
+        .unwrap();
+        let flat = flat.as_array().unwrap();
+        let total_details: usize = rule_differences
+            .iter()
+            .flat_map(|r| r.diffs.iter())
+            .map(|d| d.detail.len())
+            .sum();
+        assert_eq!(flat.len(), total_details);
+        assert_eq!(flat[0]["rule"], "JSON");
+        assert_eq!(flat[0]["relative_file_path"], "a.json");
+        assert_eq!(flat[2]["message"], "second");
+    }
+
+    #[test]
+    fn csv_detail_with_embedded_sources_survives_deletion() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = source_dir.path().join("nominal.csv");
+        let actual = source_dir.path().join("actual.csv");
+        fs::write(&nominal, "a,b\n1,2\n").unwrap();
+        fs::write(&actual, "a,b\n1,3\n").unwrap();
+        let config = CSVCompareConfig::default();
+
+        let detail = write_csv_detail(&nominal, &actual, &[], &config, &report_dir, true)
+            .unwrap()
+            .unwrap();
+        drop(source_dir);
+        assert!(!nominal.exists());
+
+        let nominal_copy = detail.path.join("nominal_nominal.csv");
+        let actual_copy = detail.path.join("actual_actual.csv");
+        assert!(nominal_copy.is_file());
+        assert!(actual_copy.is_file());
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("href=\"nominal_nominal.csv\""));
+        assert!(html.contains("href=\"actual_actual.csv\""));
+
+        let rerendered = write_csv_detail(
+            &nominal_copy,
+            &actual_copy,
+            &[],
+            &config,
+            &report_dir,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let html = fs::read_to_string(rerendered.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("<td data-order=\"0\">"));
+    }
+
+    #[test]
+    fn image_detail_inlines_images_as_data_uris() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = "tests/integ/data/images/expected/SaveImage_100DPI_default_size.jpg";
+        let actual = "tests/integ/data/images/actual/SaveImage_100DPI_default_size.jpg";
+        let score = 0.5;
+
+        let detail = write_image_detail(
+            nominal,
+            actual,
+            &[(&score, &None, &None, &None)],
+            &report_dir,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert_eq!(html.matches("src=\"data:image/jpeg;base64,").count(), 2);
+        assert_eq!(fs::read_dir(&detail.path).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn csv_detail_keeps_header_and_row_numbers_in_view() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = report_dir.path().join("nominal.csv");
+        let actual = report_dir.path().join("actual.csv");
+        let wide = (0..40)
+            .map(|i| format!("c{i}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        fs::write(&nominal, format!("{wide}\n{wide}\n")).unwrap();
+        fs::write(&actual, format!("{wide}\n{wide}\n")).unwrap();
+        let config = CSVCompareConfig {
+            preprocessing: Some(vec![crate::csv::Preprocessor::ExtractHeaders]),
+            ..Default::default()
+        };
+
+        let detail = write_csv_detail(&nominal, &actual, &[], &config, &report_dir, false)
+            .unwrap()
+            .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("<div class=\"table-scroll\">\n<table id=\"report\""));
+        assert!(html.contains("#report thead th {\n\t\t\tposition: sticky;"));
+        assert!(html.contains(
+            "#report th:nth-child(-n+2), #report td:nth-child(-n+2) {\n\t\t\tposition: sticky;"
+        ));
+        assert_eq!(html.matches("<th>").count(), 42);
+        assert!(html.contains("<td data-order=\"0\">"));
+    }
+
+    #[test]
+    fn csv_detail_annotates_violated_mode() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = report_dir.path().join("nominal.csv");
+        let actual = report_dir.path().join("actual.csv");
+        fs::write(&nominal, "1.0,10.0\n").unwrap();
+        fs::write(&actual, "1.5,10.0\n").unwrap();
+        let config = CSVCompareConfig {
+            comparison_modes: vec![crate::csv::Mode::Absolute(0.1)],
+            ..Default::default()
+        };
+        let diffs = crate::csv::compare_paths(&nominal, &actual, &config).unwrap();
+        let diffs: Vec<&DiffType> = diffs
+            .detail
+            .iter()
+            .filter_map(|d| match d {
+                DiffDetail::CSV(d) => Some(d),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(diffs.len(), 1);
+
+        let detail = write_csv_detail(&nominal, &actual, &diffs, &config, &report_dir, false)
+            .unwrap()
+            .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains(
+            "title=\"Out of tolerance. Mode: Absolute (tol: 0.1), deviation: 0.5 (relative: 0.5), exceeds tolerance by: 0.4"
+        ));
+        assert_eq!(html.matches("title=").count(), 1);
+    }
+
+    #[test]
+    fn csv_column_view_groups_diffs_per_column() {
+        let report_dir = tempfile::tempdir().unwrap();
+        let nominal = report_dir.path().join("nominal.csv");
+        let actual = report_dir.path().join("actual.csv");
+        fs::write(&nominal, "Id,Volume,Name\n1,10.0,a\n2,20.0,b\n3,30.0,c\n").unwrap();
+        fs::write(&actual, "Id,Volume,Name\n1,10.5,a\n2,23.0,x\n3,30.2,c\n").unwrap();
+        let config = CSVCompareConfig {
+            comparison_modes: vec![crate::csv::Mode::Absolute(0.1)],
+            preprocessing: Some(vec![crate::csv::Preprocessor::ExtractHeaders]),
+            detail_view: DetailView::Columns,
+            ..Default::default()
+        };
+        let diffs = crate::csv::compare_paths(&nominal, &actual, &config).unwrap();
+        let diffs: Vec<&DiffType> = diffs
+            .detail
+            .iter()
+            .filter_map(|d| match d {
+                DiffDetail::CSV(d) => Some(d),
+                _ => None,
+            })
+            .collect();
+
+        let headers = vec![
+            Some("Id".to_owned()),
+            Some("Volume".to_owned()),
+            Some("Name".to_owned()),
+        ];
+        let summaries = summarize_columns(&diffs, &headers);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].header.as_deref(), Some("Volume"));
+        assert_eq!(summaries[0].diff_count, 3);
+        assert_eq!(summaries[0].out_of_tolerance_count, 3);
+        let (worst, worst_row) = summaries[0].worst_deviation.unwrap();
+        assert!((worst - 3.0).abs() < 1e-9);
+        assert_eq!(worst_row, 1);
+        assert_eq!(summaries[1].header.as_deref(), Some("Name"));
+        assert_eq!(summaries[1].diff_count, 1);
+        assert_eq!(summaries[1].out_of_tolerance_count, 0);
+        assert!(summaries[1].worst_deviation.is_none());
+
+        let detail = write_csv_detail(&nominal, &actual, &diffs, &config, &report_dir, false)
+            .unwrap()
+            .unwrap();
+        let html = fs::read_to_string(detail.path.join(template::DETAIL_FILENAME)).unwrap();
+        assert!(html.contains("<td>Volume</td>"));
+        assert!(html.contains("(row 1)"));
+        assert!(!html.contains("id=\"report\""));
+    }
 }
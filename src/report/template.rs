@@ -12,6 +12,10 @@ pub const INDEX_TEMPLATE: &str = r##"
             background-color: #fbcccc !important;
         }
 
+        .warning {
+            background-color: #fde9b8 !important;
+        }
+
         h3 {
 			background-color:black;
 			color:white;
@@ -82,7 +86,7 @@ pub const INDEX_TEMPLATE: &str = r##"
 		</thead>
 		<tbody>
 			{% for file in rule_report.diffs %}
-				<tr {% if file.is_error %} class="error" {% endif %}>
+				<tr {% if file.is_error %} class="error" {% elif file.is_warning %} class="warning" {% endif %}>
 					{% if rule_report.rule.FileProperties %}
 						<td {% if file.additional_columns.0.is_error %} class="text-error" {% endif %}>
 							{{ file.relative_file_path }}
@@ -99,7 +103,7 @@ pub const INDEX_TEMPLATE: &str = r##"
 						<td {% if file.additional_columns.2.is_error %} class="text-error" {% endif %}>
 							{{ file.additional_columns.2.actual_value }}
 						</td>
-						<td>{% if file.is_error %} <span class="text-error">&#10006;</span> {% else %} <span style="color:green;">&#10004;</span> {% endif %}</td>
+						<td>{% if file.comparison_failed %} <span class="text-error" title="Comparison failed">&#10071;</span> {% elif file.is_error %} <span class="text-error">&#10006;</span> {% elif file.is_warning %} <span style="color:orange;">&#9888;</span> {% else %} <span style="color:green;">&#10004;</span> {% endif %}</td>
 					{% else %}
 							<td>
 								{% if file.detail_path %}
@@ -108,15 +112,57 @@ pub const INDEX_TEMPLATE: &str = r##"
 									{{ file.relative_file_path }}
 								{% endif %}
 							</td>
-							<td>{% if file.is_error %} <span class="text-error">&#10006;</span> {% else %} <span style="color:green;">&#10004;</span> {% endif %}</td>
+							<td>{% if file.comparison_failed %} <span class="text-error" title="Comparison failed">&#10071;</span> {% elif file.is_error %} <span class="text-error">&#10006;</span> {% elif file.is_warning %} <span style="color:orange;">&#9888;</span> {% else %} <span style="color:green;">&#10004;</span> {% endif %}</td>
 					{% endif %}
 				</tr>
 			{% endfor %}
 		</tbody>
 	</table>
+	{% if rule_report.excluded %}
+	<h4>Excluded files</h4>
+	<table class="report cell-border">
+		<thead>
+			<tr>
+				<th>File</th>
+				<th>Reason</th>
+			</tr>
+		</thead>
+		<tbody>
+			{% for file in rule_report.excluded %}
+				<tr>
+					<td>{{ file.relative_file_path }}</td>
+					<td>{{ file.reason }}</td>
+				</tr>
+			{% endfor %}
+		</tbody>
+	</table>
+	{% endif %}
+	{% if rule_report.one_sided %}
+	<h4>Files present on one side only</h4>
+	<p>These files were not compared and do not fail the rule.</p>
+	<table class="report cell-border">
+		<thead>
+			<tr>
+				<th>File</th>
+				<th>Reason</th>
+			</tr>
+		</thead>
+		<tbody>
+			{% for file in rule_report.one_sided %}
+				<tr>
+					<td>{{ file.relative_file_path }}</td>
+					<td>{{ file.reason }}</td>
+				</tr>
+			{% endfor %}
+		</tbody>
+	</table>
+	{% endif %}
 	</div>
 {% endfor %}
 </div>
+{% if config_hash %}
+	<p class="container">Config hash: <code>{{ config_hash }}</code></p>
+{% endif %}
 
 <script src="https://code.jquery.com/jquery-3.6.0.min.js" integrity="sha256-/xUj+3OJU5yExlq6GSYGSHk7tPXikynS7ogEvDej/m4=" crossorigin="anonymous"></script>
 <script
@@ -243,18 +289,27 @@ pub const PLAIN_IMAGE_DETAIL_TEMPLATE: &str = r#"
 
 <p>
 <h3>Nominal:</h3>
-<img src="./{{ nominal_image }}" />
+<img src="{{ nominal_image | safe }}" />
 </p>
 
 <p>
 <h3>Actual:</h3>
-<img src="./{{ actual_image }}" />
+<img src="{{ actual_image | safe }}" />
 </p>
 
+{% if diff_image %}
 <p>
 <h3>Diff:</h3>
-<img src="./{{ diff_image }}" />
+<img src="{{ diff_image | safe }}" />
 </p>
+{% endif %}
+
+{% if similarity_map %}
+<p>
+<h3>Similarity map:</h3>
+<a href="./{{ similarity_map }}">{{ similarity_map }}</a>
+</p>
+{% endif %}
 
 <script src="https://code.jquery.com/jquery-3.6.0.min.js" integrity="sha256-/xUj+3OJU5yExlq6GSYGSHk7tPXikynS7ogEvDej/m4=" crossorigin="anonymous"></script>
 <script type="text/javascript" src="https://cdn.datatables.net/v/dt/dt-1.12.1/datatables.min.js"></script>
@@ -323,6 +378,44 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
 			white-space:pre;
 		}
 
+		.table-scroll {
+			overflow: auto;
+			max-height: 85vh;
+		}
+
+		#report thead th {
+			position: sticky;
+			top: 0;
+			z-index: 2;
+			background-color: white;
+		}
+
+		#report th:nth-child(-n+2), #report td:nth-child(-n+2) {
+			position: sticky;
+			box-sizing: border-box;
+			width: 4em;
+			min-width: 4em;
+			max-width: 4em;
+			background-color: white;
+			z-index: 1;
+		}
+
+		#report th:nth-child(1), #report td:nth-child(1) {
+			left: 0;
+		}
+
+		#report th:nth-child(2), #report td:nth-child(2) {
+			left: 4em;
+		}
+
+		#report thead th:nth-child(-n+2) {
+			z-index: 3;
+		}
+
+		#report tr.error td:nth-child(-n+2), #report thead tr.error th {
+			background-color: #fbcccc;
+		}
+
     </style>
 </head>
 <body>
@@ -339,6 +432,12 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
 				<td>Right file (actual):</td>
 				<td>{{ actual }}</td>
 			</tr>
+			{% if embedded_sources %}
+			<tr>
+				<td>Embedded copies:</td>
+				<td><a href="{{ embedded_sources[0] }}">nominal</a> | <a href="{{ embedded_sources[1] }}">actual</a></td>
+			</tr>
+			{% endif %}
 		</tbody>
 	</table>
 </p>
@@ -346,6 +445,18 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
 {% if headers.columns|length <= 0 %}
 	<p><i>Header preprocessing not enabled in config</i></p>
 {% endif %}
+<p>
+	Legend: first line nominal, second line actual -
+	<span class="diffs">violates the comparison modes (hover for the violated mode and deviation)</span> -
+	<span class="actual">differs but within tolerance</span>
+</p>
+{% if sampled %}
+	<p><b>Sampling enabled: only {{ rows|length }} of {{ row_count }} rows were compared and are shown</b></p>
+{% endif %}
+{% if diff_rows_only %}
+	<p><b>Compact view: only the {{ rows|length }} of {{ row_count }} rows with differences are shown</b></p>
+{% endif %}
+<div class="table-scroll">
 <table id="report" class="cell-border">
     <thead>
     {% if headers.columns|length > 0 %}
@@ -356,7 +467,7 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
 				<th>
 					{{ col.nominal_value }}
 					{% if headers.has_diff %}
-						<div class="{% if col.nominal_value != col.actual_value %} diffs {% endif %}">{{ col.actual_value }}</div>
+						<div class="{% if col.nominal_value != col.actual_value or col.diffs|length > 0 %} diffs {% endif %}"{% if col.diffs|length > 0 %} title="{{ col.diffs | join(sep=' | ') }}"{% endif %}>{{ col.actual_value }}</div>
 					{% endif %}
 				</th>
 			{% endfor %}
@@ -374,13 +485,13 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
     <tbody>
         {% for row in rows %}
             <tr {% if row.has_error %} class="error" {% endif %}>
-            	<td data-order="{{ loop.index0 }}">{{ loop.index0 }}{% if row.has_diff or row.has_error %}<br>&nbsp;{% endif %}</td>
-            	<td data-order="{{ loop.index0 }}">{% if row.has_diff or row.has_error %}&nbsp;<br>{% endif %}{{ loop.index0 }}</td>
+            	<td data-order="{{ row.row }}">{{ row.row }}{% if row.has_diff or row.has_error %}<br>&nbsp;{% endif %}</td>
+            	<td data-order="{{ row.row }}">{% if row.has_diff or row.has_error %}&nbsp;<br>{% endif %}{{ row.row }}</td>
             	{% for col in row.columns %}
 					<td>
 					<span class="pre-text">{{ col.nominal_value }}</span>
 					{% if row.has_diff or row.has_error %}
-						<div class="{% if col.diffs|length > 0 %} diffs {% elif col.nominal_value != col.actual_value %} actual {% else %} {% endif %}">
+						<div class="{% if col.diffs|length > 0 %} diffs {% elif col.nominal_value != col.actual_value %} actual {% else %} {% endif %}"{% if col.diffs|length > 0 %} title="{{ col.diffs | join(sep=' | ') }}"{% endif %}>
 						<span class="pre-text">{{ col.actual_value }}</span>
 						</div>
 					{% endif %}
@@ -390,6 +501,7 @@ pub const PLAIN_CSV_DETAIL_TEMPLATE: &str = r#"
         {% endfor %}
     </tbody>
 </table>
+</div>
 
 <script src="https://code.jquery.com/jquery-3.6.0.min.js" integrity="sha256-/xUj+3OJU5yExlq6GSYGSHk7tPXikynS7ogEvDej/m4=" crossorigin="anonymous"></script>
 <script type="text/javascript" src="https://cdn.datatables.net/v/dt/dt-1.12.1/datatables.min.js"></script>
@@ -618,6 +730,73 @@ pub const ERROR_DETAIL_TEMPLATE: &str = r#"
 </html>
 "#;
 
+pub const PLAIN_CSV_COLUMN_DETAIL_TEMPLATE: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Results</title>
+
+     <style>
+
+		h3 {
+			background-color:black;
+			color:white;
+			padding:10px;
+			margin:10px 0;
+		}
+
+		#columns th {
+			text-align:left;
+			background-color: #cccccc;
+			padding:10px;
+		}
+
+		#columns td {
+			padding:5px 10px;
+		}
+
+		table#columns {
+			border:1px solid grey;
+		}
+
+    </style>
+</head>
+<body>
+
+<h3>Compare Result of {{ actual }} and {{ nominal }}</h3>
+{% if sampled %}
+	<p><b>Sampling enabled: only {{ rows|length }} of {{ row_count }} rows were compared</b></p>
+{% endif %}
+{% if column_summaries|length > 0 %}
+<table id="columns">
+	<thead>
+		<tr>
+			<th>Column</th>
+			<th>Differences</th>
+			<th>Out of tolerance</th>
+			<th>Worst deviation</th>
+		</tr>
+	</thead>
+	<tbody>
+		{% for summary in column_summaries %}
+		<tr>
+			<td>{% if summary.header %}{{ summary.header }}{% else %}{{ summary.column }}{% endif %}</td>
+			<td>{{ summary.diff_count }}</td>
+			<td>{{ summary.out_of_tolerance_count }}</td>
+			<td>{% if summary.worst_deviation %}{{ summary.worst_deviation[0] }} (row {{ summary.worst_deviation[1] }}){% endif %}</td>
+		</tr>
+		{% endfor %}
+	</tbody>
+</table>
+{% else %}
+	<p><i>No differences in any column</i></p>
+{% endif %}
+
+</body>
+</html>
+"#;
+
 pub const PLAIN_EXTERNAL_DETAIL_TEMPLATE: &str = r#"
 <!DOCTYPE html>
 <html lang="en">
@@ -675,6 +854,7 @@ pub const PLAIN_EXTERNAL_DETAIL_TEMPLATE: &str = r#"
 <body>
 
 <h3>Compare Result of {{ actual }} and {{ nominal }}</h3>
+{% if attempts > 1 %}<p>Failed after {{ attempts }} attempts</p>{% endif %}
 
 <table id="compare">
 	<thead>
@@ -783,7 +963,10 @@ pub const PLAIN_JSON_DETAIL_TEMPLATE: &str = r#"
 		</tr>
 	</tbody>
 </table>
-
+{% if ignored %}
+<h4>Paths suppressed by ignore_keys</h4>
+<div style="white-space: pre;">{{ ignored }}</div>
+{% endif %}
 
 </body>
 </html>
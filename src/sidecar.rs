@@ -0,0 +1,109 @@
+use crate::{Error, Rule};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+use vg_errortools::fat_io_wrap_std;
+
+/// The file name of the per-folder rule files inside the nominal folder
+pub(crate) const SIDECAR_FILE_NAME: &str = ".havocompare.yml";
+
+#[derive(Deserialize)]
+struct Sidecar {
+    rules: Vec<Rule>,
+}
+
+/// A rule together with the folder it applies to, relative to the nominal and actual folder
+pub(crate) struct ScopedRule {
+    pub folder: PathBuf,
+    pub rule: Rule,
+}
+
+fn find_sidecars(nominal_root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let pattern = nominal_root.join("**").join(SIDECAR_FILE_NAME);
+    let mut sidecars: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())?
+        .filter_map(|p| p.ok())
+        .collect();
+    sidecars.sort();
+    Ok(sidecars)
+}
+
+/// Merges the rules of all sidecar files found in the nominal folder into the global rules.
+/// A sidecar rule applies to the sidecar's folder and below. If it has the name of a rule of a parent folder it replaces that rule there,
+/// the parent rule then excludes the folder. The sidecar files themselves are never compared.
+pub(crate) fn resolve_rules(
+    nominal_root: &Path,
+    rules: Vec<Rule>,
+) -> Result<Vec<ScopedRule>, Error> {
+    let global = rules.into_iter().map(|rule| ScopedRule {
+        folder: PathBuf::new(),
+        rule,
+    });
+    if !nominal_root.is_dir() {
+        return Ok(global.collect());
+    }
+    let sidecars = find_sidecars(nominal_root)?;
+    if sidecars.is_empty() {
+        return Ok(global.collect());
+    }
+
+    let mut scoped: Vec<ScopedRule> = global.collect();
+    for sidecar_file in sidecars {
+        let folder = sidecar_file
+            .parent()
+            .and_then(|p| p.strip_prefix(nominal_root).ok())
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+        let reader = BufReader::new(fat_io_wrap_std(&sidecar_file, &File::open)?);
+        let sidecar: Sidecar = serde_yaml::from_reader(reader)?;
+        info!(
+            "Loaded {} rules from {}",
+            sidecar.rules.len(),
+            sidecar_file.to_string_lossy()
+        );
+        for rule in sidecar.rules {
+            // a sidecar in the same folder replaces the rule entirely
+            scoped.retain(|s| !(s.folder == folder && s.rule.name == rule.name));
+            scoped.push(ScopedRule {
+                folder: folder.clone(),
+                rule,
+            });
+        }
+    }
+
+    let overrides: Vec<(PathBuf, String)> = scoped
+        .iter()
+        .map(|s| (s.folder.clone(), s.rule.name.clone()))
+        .collect();
+    for scoped_rule in scoped.iter_mut() {
+        let excludes = scoped_rule
+            .rule
+            .pattern_exclude
+            .get_or_insert_with(Vec::new);
+        excludes.push(format!("**/{SIDECAR_FILE_NAME}"));
+        for (folder, name) in overrides.iter() {
+            if *name != scoped_rule.rule.name || *folder == scoped_rule.folder {
+                continue;
+            }
+            if let Ok(sub_folder) = folder.strip_prefix(&scoped_rule.folder) {
+                debug!(
+                    "Rule '{name}' of folder '{}' excludes '{}'",
+                    scoped_rule.folder.to_string_lossy(),
+                    folder.to_string_lossy()
+                );
+                excludes.push(format!("{}/**/*", sub_folder.to_string_lossy()));
+            }
+        }
+    }
+    for scoped_rule in scoped.iter_mut() {
+        if !scoped_rule.folder.as_os_str().is_empty() {
+            let folder = scoped_rule
+                .folder
+                .to_string_lossy()
+                .replace(['/', '\\'], "_");
+            scoped_rule.rule.name = format!("{} ({folder})", scoped_rule.rule.name);
+        }
+    }
+    Ok(scoped)
+}
@@ -1,4 +1,4 @@
-use havocompare::compare_folders;
+use havocompare::{compare_folders, compare_folders_cfg_detailed, ConfigurationFile, DiffDetail};
 use test_log::test;
 
 #[test]
@@ -60,3 +60,66 @@ fn json_test() {
     )
     .unwrap());
 }
+
+#[test]
+fn detailed_results_are_returned_to_caller() {
+    let report_dir =
+        tempfile::tempdir().expect("Could not generate temporary directory for report");
+    let config =
+        ConfigurationFile::from_file("tests/integ/json.yml").expect("Could not read config");
+
+    let (all_okay, rule_results) = compare_folders_cfg_detailed(
+        "tests/integ/data/json/expected/",
+        "tests/integ/data/json/actual/",
+        config,
+        report_dir,
+    )
+    .expect("Could not compare folders");
+    assert!(!all_okay);
+    assert_eq!(rule_results.len(), 1);
+    assert_eq!(rule_results[0].rule.name, "Compare JSON files");
+    let diffs = &rule_results[0].diffs;
+    assert_eq!(diffs.len(), 1);
+    assert!(diffs[0].is_error);
+    assert_eq!(diffs[0].relative_file_path, "guy.json");
+    assert!(matches!(diffs[0].detail[0], DiffDetail::Json { .. }));
+}
+
+#[test]
+fn update_baseline_overwrites_nominal_only_when_confirmed() {
+    let dir = tempfile::tempdir().expect("Could not generate temporary directory");
+    let nominal = dir.path().join("nominal");
+    let actual = dir.path().join("actual");
+    for folder in [&nominal, &actual] {
+        std::fs::create_dir(folder).expect("Could not create folder");
+    }
+    std::fs::write(nominal.join("result.txt"), "old").expect("Could not write nominal");
+    std::fs::write(actual.join("result.txt"), "new").expect("Could not write actual");
+    let config = dir.path().join("config.yml");
+    std::fs::write(
+        &config,
+        "rules:\n  - name: \"Text\"\n    pattern_include: [\"*.txt\"]\n    PlainText:\n      threshold: 1.0\n",
+    )
+    .expect("Could not write config");
+
+    let compare = |flags: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_havocompare"))
+            .arg("compare")
+            .args([&nominal, &actual, &config])
+            .arg("--report_path")
+            .arg(dir.path().join("report"))
+            .args(flags)
+            .status()
+            .expect("Could not run havocompare")
+    };
+    let nominal_content =
+        || std::fs::read_to_string(nominal.join("result.txt")).unwrap_or_default();
+
+    assert!(!compare(&[]).success());
+    assert_eq!(nominal_content(), "old");
+    assert!(!compare(&["--update-baseline"]).success());
+    assert_eq!(nominal_content(), "old");
+    assert!(!compare(&["--update-baseline", "--confirm-update-baseline"]).success());
+    assert_eq!(nominal_content(), "new");
+    assert!(compare(&[]).success());
+}